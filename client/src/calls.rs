@@ -0,0 +1,64 @@
+//! Typed builders of the calls of the runtime.
+//!
+//! The calls are the `Call` of the runtime, so they are SCALE-encoded exactly the way the node
+//! decodes them.
+
+/// Calls of the template pallet.
+pub mod template {
+	use node_template_runtime::{pallet_template, BlockNumber, Call};
+
+	/// Store `something`, depositing `SomethingStored`.
+	pub fn do_something(something: u32) -> Call {
+		Call::TemplateModule(pallet_template::Call::do_something(something))
+	}
+
+	/// Increment the stored value, failing when none is stored.
+	pub fn cause_error() -> Call {
+		Call::TemplateModule(pallet_template::Call::cause_error())
+	}
+
+	/// Schedule `something` to be stored at block `at`.
+	pub fn schedule_set(at: BlockNumber, something: u32) -> Call {
+		Call::TemplateModule(pallet_template::Call::schedule_set(at, something))
+	}
+
+	/// Cancel the write the sender scheduled for block `at`.
+	pub fn cancel_set(at: BlockNumber) -> Call {
+		Call::TemplateModule(pallet_template::Call::cancel_set(at))
+	}
+}
+
+/// Calls of the balances pallet.
+pub mod balances {
+	use node_template_runtime::{Address, Balance, BalancesCall, Call};
+
+	/// Transfer `value` to `dest`, an account or an account index.
+	pub fn transfer(dest: impl Into<Address>, value: Balance) -> Call {
+		Call::Balances(BalancesCall::transfer(dest.into(), value))
+	}
+
+	/// Transfer `value` to `dest`, failing if it would leave the sender below the existential
+	/// deposit.
+	pub fn transfer_keep_alive(dest: impl Into<Address>, value: Balance) -> Call {
+		Call::Balances(BalancesCall::transfer_keep_alive(dest.into(), value))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use node_template_runtime::{AccountId, Address};
+
+	#[test]
+	fn calls_are_encoded_as_the_runtime_decodes_them() {
+		// The call index, then the value as a little-endian `u32`.
+		assert_eq!(template::do_something(42).encode()[1..], [0, 42, 0, 0, 0]);
+
+		// Accounts are sent as `Address::Id`, marked by `0xff`.
+		let bob = AccountId::from([2; 32]);
+		let transfer = balances::transfer(bob.clone(), 1);
+		assert_eq!(transfer, balances::transfer(Address::Id(bob), 1));
+		assert_eq!(transfer.encode()[2..4], [0xff, 2]);
+	}
+}
@@ -0,0 +1,83 @@
+//! Decoding of the events of a block, as stored under [`crate::storage::events`].
+
+use codec::Decode;
+use frame_system::{Phase, RawEvent as SystemEvent};
+use node_template_runtime::{
+	pallet_template::RawEvent as TemplateEvent, AccountId, Balance, Event, Hash,
+};
+use pallet_balances::RawEvent as BalancesEvent;
+use sp_runtime::DispatchError;
+
+/// An event deposited in a block, along with the phase it was deposited in.
+pub type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// Decode the events of a block, an absent value being no events.
+pub fn decode(data: Option<&[u8]>) -> Result<Vec<EventRecord>, codec::Error> {
+	match data {
+		Some(mut data) => Vec::decode(&mut data),
+		None => Ok(Vec::new()),
+	}
+}
+
+/// The outcome of the extrinsic at `index` in the block of `records`, if it has one.
+pub fn extrinsic_result(records: &[EventRecord], index: u32) -> Option<Result<(), DispatchError>> {
+	records.iter()
+		.filter(|record| record.phase == Phase::ApplyExtrinsic(index))
+		.find_map(|record| match &record.event {
+			Event::frame_system(SystemEvent::ExtrinsicSuccess(_)) => Some(Ok(())),
+			Event::frame_system(SystemEvent::ExtrinsicFailed(error, _)) => Some(Err(*error)),
+			_ => None,
+		})
+}
+
+/// The values stored by `TemplateModule::do_something`, with the accounts storing them.
+pub fn something_stored(records: &[EventRecord]) -> Vec<(u32, AccountId)> {
+	records.iter()
+		.filter_map(|record| match &record.event {
+			Event::pallet_template(TemplateEvent::SomethingStored(value, who)) =>
+				Some((*value, who.clone())),
+			_ => None,
+		})
+		.collect()
+}
+
+/// The balance transfers, as sender, recipient and amount.
+pub fn transfers(records: &[EventRecord]) -> Vec<(AccountId, AccountId, Balance)> {
+	records.iter()
+		.filter_map(|record| match &record.event {
+			Event::pallet_balances(BalancesEvent::Transfer(from, to, amount)) =>
+				Some((from.clone(), to.clone(), *amount)),
+			_ => None,
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_support::weights::DispatchInfo;
+
+	#[test]
+	fn events_are_decoded_and_matched_by_extrinsic() {
+		let alice = AccountId::from([1; 32]);
+		let records = vec![
+			EventRecord {
+				phase: Phase::ApplyExtrinsic(1),
+				event: Event::pallet_template(TemplateEvent::SomethingStored(7, alice.clone())),
+				topics: vec![],
+			},
+			EventRecord {
+				phase: Phase::ApplyExtrinsic(1),
+				event: Event::frame_system(SystemEvent::ExtrinsicSuccess(DispatchInfo::default())),
+				topics: vec![],
+			},
+		];
+		let decoded = decode(Some(&records.encode())).unwrap();
+
+		assert_eq!(something_stored(&decoded), vec![(7, alice)]);
+		assert_eq!(extrinsic_result(&decoded, 1), Some(Ok(())));
+		assert_eq!(extrinsic_result(&decoded, 0), None);
+		assert!(decode(None).unwrap().is_empty());
+	}
+}
@@ -0,0 +1,17 @@
+//! A client of the node template for backend services.
+//!
+//! Rather than encoding calls and storage keys by hand, services build them with [`calls`] and
+//! [`storage`] from the types of the runtime itself, decode events with [`events`], sign with
+//! sr25519 or ed25519 keys through a [`signer::Signer`], and talk to a node over WebSocket
+//! JSON-RPC, subscriptions included, with a [`Client`].
+
+#![warn(missing_docs)]
+
+pub mod calls;
+pub mod events;
+pub mod rpc;
+pub mod signer;
+pub mod storage;
+
+pub use node_template_runtime as runtime;
+pub use rpc::{Client, Error};
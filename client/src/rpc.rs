@@ -0,0 +1,233 @@
+//! The WebSocket JSON-RPC transport.
+//!
+//! The connection is driven by the `jsonrpc` clients of the Substrate RPC APIs, which run on a
+//! tokio 0.1 runtime owned by the [`Client`]. Requests and subscriptions are plain `futures` 0.3
+//! futures and streams, so they can be awaited on any executor.
+
+use std::fmt;
+use codec::{Decode, Encode};
+use futures::{
+	Stream, StreamExt, TryStreamExt, compat::{Future01CompatExt, Stream01CompatExt},
+};
+use jsonrpc_core_client::{RpcChannel, RpcError, transports::ws};
+use node_template_runtime::{
+	AccountId, Balance, BlockNumber, Call, Hash, Header, Index, SignedBlock, UncheckedExtrinsic,
+};
+use sc_rpc_api::{author::AuthorClient, chain::ChainClient, state::StateClient};
+use sp_core::{Bytes, storage::{StorageChangeSet, StorageKey}};
+use sp_rpc::{list::ListOrValue, number::NumberOrHex};
+use sp_transaction_pool::TransactionStatus;
+use sp_version::RuntimeVersion;
+use substrate_frame_rpc_system::SystemClient;
+use crate::{events::{self, EventRecord}, signer::{self, ChainParams, Signer}, storage};
+
+/// An error of the [`Client`].
+#[derive(Debug)]
+pub enum Error {
+	/// The request failed, or the node answered with an error.
+	Rpc(RpcError),
+	/// A value from the node could not be decoded.
+	Codec(codec::Error),
+	/// The node answered unexpectedly.
+	Other(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Rpc(e) => write!(f, "RPC error: {}", e),
+			Error::Codec(e) => write!(f, "Unable to decode: {}", e),
+			Error::Other(e) => write!(f, "{}", e),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<RpcError> for Error {
+	fn from(e: RpcError) -> Self {
+		Error::Rpc(e)
+	}
+}
+
+impl From<codec::Error> for Error {
+	fn from(e: codec::Error) -> Self {
+		Error::Codec(e)
+	}
+}
+
+impl From<String> for Error {
+	fn from(e: String) -> Self {
+		Error::Other(e)
+	}
+}
+
+/// The status of an extrinsic in the transaction pool of the node.
+pub type ExtrinsicStatus = TransactionStatus<Hash, Hash>;
+
+/// A connection to a node.
+pub struct Client {
+	author: AuthorClient<Hash, Hash>,
+	chain: ChainClient<BlockNumber, Hash, Header, SignedBlock>,
+	state: StateClient<Hash>,
+	system: SystemClient<AccountId, Index>,
+	genesis_hash: Hash,
+	// Drives the connection, which is closed when it is dropped.
+	_runtime: tokio01::runtime::Runtime,
+}
+
+impl Client {
+	/// Connect to the node at `url`, e.g. `ws://localhost:9944`.
+	pub async fn connect(url: &str) -> Result<Self, Error> {
+		let url = url::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+		let runtime = tokio01::runtime::Runtime::new()
+			.map_err(|e| format!("Unable to start the RPC runtime: {}", e))?;
+		// The transport spawns its tasks on the runtime it is connected from.
+		let channel: RpcChannel =
+			futures01::sync::oneshot::spawn(ws::connect(&url), &runtime.executor()).compat().await?;
+
+		let chain = ChainClient::from(channel.clone());
+		let genesis = ListOrValue::Value(NumberOrHex::Number(0));
+		let genesis_hash = match chain.block_hash(Some(genesis)).compat().await? {
+			ListOrValue::Value(Some(hash)) => hash,
+			_ => return Err("The node has no genesis block".to_string().into()),
+		};
+
+		Ok(Client {
+			author: channel.clone().into(),
+			chain,
+			state: channel.clone().into(),
+			system: channel.into(),
+			genesis_hash,
+			_runtime: runtime,
+		})
+	}
+
+	/// The genesis hash of the chain.
+	pub fn genesis_hash(&self) -> Hash {
+		self.genesis_hash
+	}
+
+	/// The version of the runtime at the best block.
+	pub async fn runtime_version(&self) -> Result<RuntimeVersion, Error> {
+		Ok(self.state.runtime_version(None).compat().await?)
+	}
+
+	/// The header of block `hash`, or of the best block.
+	pub async fn header(&self, hash: Option<Hash>) -> Result<Option<Header>, Error> {
+		Ok(self.chain.header(hash).compat().await?)
+	}
+
+	/// Read and decode the storage value at `key` at block `at`, or at the best block.
+	pub async fn storage<T: Decode>(
+		&self,
+		key: StorageKey,
+		at: Option<Hash>,
+	) -> Result<Option<T>, Error> {
+		match self.state.storage(key, at).compat().await? {
+			Some(data) => Ok(Some(T::decode(&mut &data.0[..])?)),
+			None => Ok(None),
+		}
+	}
+
+	/// The value of `TemplateModule::Something`.
+	pub async fn something(&self, at: Option<Hash>) -> Result<Option<u32>, Error> {
+		self.storage(storage::something(), at).await
+	}
+
+	/// The nonce and balances of the account `who`.
+	pub async fn account(
+		&self,
+		who: &AccountId,
+		at: Option<Hash>,
+	) -> Result<storage::AccountInfo, Error> {
+		Ok(self.storage(storage::account(who), at).await?.unwrap_or_default())
+	}
+
+	/// The events of block `at`, or of the best block.
+	pub async fn events(&self, at: Option<Hash>) -> Result<Vec<EventRecord>, Error> {
+		let data = self.state.storage(storage::events(), at).compat().await?;
+		Ok(events::decode(data.as_ref().map(|data| &data.0[..]))?)
+	}
+
+	/// The next nonce of `who`, counting its extrinsics in the transaction pool.
+	pub async fn nonce(&self, who: &AccountId) -> Result<Index, Error> {
+		Ok(self.system.nonce(who.clone()).compat().await?)
+	}
+
+	/// Sign `call` with the next nonce of `signer`, for the runtime at the best block.
+	pub async fn sign(
+		&self,
+		call: Call,
+		signer: &dyn Signer,
+		tip: Balance,
+	) -> Result<UncheckedExtrinsic, Error> {
+		let nonce = self.nonce(&signer.account_id()).await?;
+		let version = self.runtime_version().await?;
+		let chain = ChainParams {
+			genesis_hash: self.genesis_hash,
+			spec_version: version.spec_version,
+			transaction_version: version.transaction_version,
+		};
+		Ok(signer::sign(call, signer, nonce, tip, &chain))
+	}
+
+	/// Submit `extrinsic` to the transaction pool, returning its hash.
+	pub async fn submit(&self, extrinsic: &UncheckedExtrinsic) -> Result<Hash, Error> {
+		Ok(self.author.submit_extrinsic(Bytes(extrinsic.encode())).compat().await?)
+	}
+
+	/// Submit `extrinsic` and follow its status in the transaction pool.
+	pub async fn submit_and_watch(
+		&self,
+		extrinsic: &UncheckedExtrinsic,
+	) -> Result<impl Stream<Item = Result<ExtrinsicStatus, Error>>, Error> {
+		let statuses = self.author.watch_extrinsic(Bytes(extrinsic.encode())).compat().await?;
+		Ok(statuses.compat().map_err(Error::from))
+	}
+
+	/// Submit `extrinsic` and wait for a block including it, returning the hash of the block.
+	pub async fn submit_and_wait(&self, extrinsic: &UncheckedExtrinsic) -> Result<Hash, Error> {
+		let mut statuses = Box::pin(self.submit_and_watch(extrinsic).await?);
+		while let Some(status) = statuses.next().await {
+			match status? {
+				TransactionStatus::InBlock(hash) | TransactionStatus::Finalized(hash) =>
+					return Ok(hash),
+				TransactionStatus::Future | TransactionStatus::Ready
+				| TransactionStatus::Broadcast(_) | TransactionStatus::Retracted(_) => {},
+				status =>
+					return Err(format!("The extrinsic was not included: {:?}", status).into()),
+			}
+		}
+		Err("The node stopped reporting the status of the extrinsic".to_string().into())
+	}
+
+	/// Follow the headers of the new best blocks.
+	pub async fn subscribe_new_heads(
+		&self,
+	) -> Result<impl Stream<Item = Result<Header, Error>>, Error> {
+		let heads = self.chain.subscribe_new_heads().compat().await?;
+		Ok(heads.compat().map_err(Error::from))
+	}
+
+	/// Follow the changes of the storage values at `keys` in the new best blocks.
+	pub async fn subscribe_storage(
+		&self,
+		keys: Vec<StorageKey>,
+	) -> Result<impl Stream<Item = Result<StorageChangeSet<Hash>, Error>>, Error> {
+		let changes = self.state.subscribe_storage(Some(keys)).compat().await?;
+		Ok(changes.compat().map_err(Error::from))
+	}
+
+	/// Follow the events of the new best blocks, with the hashes of the blocks.
+	pub async fn subscribe_events(
+		&self,
+	) -> Result<impl Stream<Item = Result<(Hash, Vec<EventRecord>), Error>>, Error> {
+		let changes = self.subscribe_storage(vec![storage::events()]).await?;
+		Ok(changes.and_then(|changes| async move {
+			let data = changes.changes.into_iter().find_map(|(_, data)| data);
+			let records = events::decode(data.as_ref().map(|data| &data.0[..]))?;
+			Ok((changes.block, records))
+		}))
+	}
+}
@@ -0,0 +1,133 @@
+//! Signing of extrinsics with sr25519 or ed25519 keys.
+
+use codec::Encode;
+use node_template_runtime::{
+	signed_extra, signed_extra_additional, AccountId, Address, Balance, Call, Hash, Index,
+	Signature, UncheckedExtrinsic,
+};
+use sp_core::{Pair, ed25519, sr25519};
+use sp_runtime::{
+	MultiSignature, MultiSigner, generic::{Era, SignedPayload}, traits::IdentifyAccount,
+};
+
+/// Signs extrinsics on behalf of an account.
+pub trait Signer: Send + Sync {
+	/// The account sending the extrinsics.
+	fn account_id(&self) -> AccountId;
+
+	/// Sign `payload`, the encoding of a `SignedPayload` or its hash.
+	fn sign(&self, payload: &[u8]) -> Signature;
+}
+
+/// A signer holding the key pair of the account.
+pub struct PairSigner<P: Pair> {
+	pair: P,
+	account_id: AccountId,
+}
+
+/// A signer with an sr25519 key, the default scheme of Substrate accounts.
+pub type Sr25519Signer = PairSigner<sr25519::Pair>;
+
+/// A signer with an ed25519 key.
+pub type Ed25519Signer = PairSigner<ed25519::Pair>;
+
+impl<P: Pair> PairSigner<P> where P::Public: Into<MultiSigner> {
+	/// The signer of the account of `pair`.
+	pub fn new(pair: P) -> Self {
+		let account_id = pair.public().into().into_account();
+		PairSigner { pair, account_id }
+	}
+
+	/// The signer of the key of the secret URI `suri`, e.g. a mnemonic phrase or `//Alice`.
+	pub fn from_suri(suri: &str, password: Option<&str>) -> Result<Self, String> {
+		P::from_string(suri, password)
+			.map(Self::new)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))
+	}
+}
+
+impl<P> Signer for PairSigner<P> where
+	P: Pair,
+	P::Signature: Into<MultiSignature>,
+{
+	fn account_id(&self) -> AccountId {
+		self.account_id.clone()
+	}
+
+	fn sign(&self, payload: &[u8]) -> Signature {
+		self.pair.sign(payload).into()
+	}
+}
+
+/// What the chain checks extrinsics against, besides their sender.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainParams {
+	/// The genesis hash of the chain.
+	pub genesis_hash: Hash,
+	/// The spec version of its runtime.
+	pub spec_version: u32,
+	/// The transaction version of its runtime.
+	pub transaction_version: u32,
+}
+
+/// Sign `call` into an immortal extrinsic of the account of `signer`.
+pub fn sign(
+	call: Call,
+	signer: &dyn Signer,
+	nonce: Index,
+	tip: Balance,
+	chain: &ChainParams,
+) -> UncheckedExtrinsic {
+	let additional = signed_extra_additional(
+		chain.spec_version,
+		chain.transaction_version,
+		chain.genesis_hash,
+		chain.genesis_hash,
+	);
+	let extra = signed_extra(Era::Immortal, nonce, tip);
+	let payload = SignedPayload::from_raw(call, extra, additional);
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+	UncheckedExtrinsic::new_signed(call, Address::Id(signer.account_id()), signature, extra)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::calls;
+	use sp_keyring::{AccountKeyring, Ed25519Keyring};
+	use sp_runtime::traits::Verify;
+
+	#[test]
+	fn extrinsics_are_signed_with_either_scheme() {
+		let chain = ChainParams {
+			genesis_hash: Hash::repeat_byte(1),
+			spec_version: 1,
+			transaction_version: 1,
+		};
+		let signers: Vec<Box<dyn Signer>> = vec![
+			Box::new(Sr25519Signer::new(AccountKeyring::Alice.pair())),
+			Box::new(Ed25519Signer::new(Ed25519Keyring::Alice.pair())),
+		];
+
+		for signer in signers {
+			let extrinsic = sign(calls::template::do_something(1), &*signer, 3, 0, &chain);
+			let (address, signature, extra) = extrinsic.signature.unwrap();
+			assert_eq!(address, Address::Id(signer.account_id()));
+
+			let payload = (
+				calls::template::do_something(1),
+				extra,
+				(1u32, 1u32, chain.genesis_hash, chain.genesis_hash, (), (), (), ()),
+			);
+			assert!(signature.verify(&payload.encode()[..], &signer.account_id()));
+		}
+	}
+
+	#[test]
+	fn signers_are_built_from_secret_uris() {
+		let signer = Sr25519Signer::from_suri("//Alice", None).unwrap();
+		assert_eq!(signer.account_id(), AccountKeyring::Alice.to_account_id());
+		assert!(Ed25519Signer::from_suri("not a key", None).is_err());
+	}
+}
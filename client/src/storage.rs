@@ -0,0 +1,45 @@
+//! Storage keys of the runtime, and the types of their values.
+
+use node_template_runtime::{AccountId, Balance, Index};
+use sp_core::{hashing::{blake2_128, twox_128}, storage::StorageKey};
+
+/// The information the system pallet stores about an account, with its balances.
+pub type AccountInfo = frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+
+/// The storage key of a storage value.
+pub fn value_key(module: &[u8], item: &[u8]) -> StorageKey {
+	StorageKey([twox_128(module), twox_128(item)].concat())
+}
+
+/// The storage key of the entry of a map hashed with `Blake2_128Concat`.
+pub fn blake2_128_concat_key(module: &[u8], item: &[u8], key: &[u8]) -> StorageKey {
+	StorageKey([&twox_128(module)[..], &twox_128(item), &blake2_128(key), key].concat())
+}
+
+/// The key of `TemplateModule::Something`, an `Option<u32>`.
+pub fn something() -> StorageKey {
+	value_key(b"TemplateModule", b"Something")
+}
+
+/// The key of the `System::Account` entry of `who`, an [`AccountInfo`].
+pub fn account(who: &AccountId) -> StorageKey {
+	blake2_128_concat_key(b"System", b"Account", who.as_ref())
+}
+
+/// The key of `System::Events`, the events of a block, see [`crate::events`].
+pub fn events() -> StorageKey {
+	value_key(b"System", b"Events")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::storage::StorageMap;
+	use node_template_runtime::Runtime;
+
+	#[test]
+	fn map_keys_match_the_runtime() {
+		let who = AccountId::from([1; 32]);
+		assert_eq!(account(&who).0, frame_system::Account::<Runtime>::hashed_key_for(&who));
+	}
+}
@@ -0,0 +1,145 @@
+//! Tests of the client against an in-process development node authoring blocks with instant seal.
+
+use std::{future::Future, net::TcpListener};
+use futures::{StreamExt, TryStreamExt};
+use node_template::{service, testing};
+use node_template_client::{
+	Client, calls, events, runtime::{DOLLARS, VERSION},
+	signer::{Ed25519Signer, Signer, Sr25519Signer},
+	storage,
+};
+use sc_service::TaskManager;
+use sp_keyring::{AccountKeyring, Ed25519Keyring};
+use sp_runtime::traits::Header as _;
+
+/// A development node with a temporary database, serving WebSocket RPC on a free port.
+struct TestNode {
+	url: String,
+	// Dropped before the runtime its tasks run on.
+	_task_manager: TaskManager,
+	runtime: tokio::runtime::Runtime,
+}
+
+fn free_port() -> String {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	listener.local_addr().unwrap().port().to_string()
+}
+
+impl TestNode {
+	fn start() -> Self {
+		let ws_port = free_port();
+		let runtime = tokio::runtime::Builder::new()
+			.threaded_scheduler()
+			.enable_all()
+			.build()
+			.unwrap();
+		let config = testing::dev_config(
+			&["--ws-port", &ws_port, "--port", &free_port(), "--no-mdns", "--no-prometheus",
+				"--no-telemetry"],
+			runtime.handle().clone(),
+		);
+		let options = service::NodeOptions { instant_seal: true, ..Default::default() };
+		let task_manager = service::new_full(config, options).unwrap();
+
+		TestNode {
+			url: format!("ws://127.0.0.1:{}", ws_port),
+			_task_manager: task_manager,
+			runtime,
+		}
+	}
+
+	fn block_on<F: Future>(&mut self, future: F) -> F::Output {
+		self.runtime.block_on(future)
+	}
+}
+
+#[test]
+fn storage_is_read_with_the_typed_keys() {
+	let mut node = TestNode::start();
+	let url = node.url.clone();
+	node.block_on(async {
+		let client = Client::connect(&url).await.unwrap();
+		let alice = client.account(&AccountKeyring::Alice.to_account_id(), None).await.unwrap();
+		assert!(alice.data.free > 0);
+		assert_eq!(alice.nonce, 0);
+		assert_eq!(client.something(None).await.unwrap(), None);
+		assert_eq!(client.runtime_version().await.unwrap().spec_version, VERSION.spec_version);
+	});
+}
+
+#[test]
+fn signed_calls_are_included_and_their_events_decoded() {
+	let mut node = TestNode::start();
+	let url = node.url.clone();
+	node.block_on(async {
+		let client = Client::connect(&url).await.unwrap();
+		let alice = Sr25519Signer::new(AccountKeyring::Alice.pair());
+
+		let extrinsic = client.sign(calls::template::do_something(42), &alice, 0).await.unwrap();
+		let block = client.submit_and_wait(&extrinsic).await.unwrap();
+
+		assert_eq!(client.something(Some(block)).await.unwrap(), Some(42));
+		let records = client.events(Some(block)).await.unwrap();
+		assert_eq!(events::something_stored(&records), vec![(42, alice.account_id())]);
+		assert_eq!(events::extrinsic_result(&records, 1), Some(Ok(())));
+		assert_eq!(client.nonce(&alice.account_id()).await.unwrap(), 1);
+	});
+}
+
+#[test]
+fn ed25519_accounts_sign_transfers() {
+	let mut node = TestNode::start();
+	let url = node.url.clone();
+	node.block_on(async {
+		let client = Client::connect(&url).await.unwrap();
+		let alice = Sr25519Signer::new(AccountKeyring::Alice.pair());
+		let dave = Ed25519Signer::new(Ed25519Keyring::Dave.pair());
+
+		let fund = calls::balances::transfer(dave.account_id(), 10 * DOLLARS);
+		client.submit_and_wait(&client.sign(fund, &alice, 0).await.unwrap()).await.unwrap();
+
+		let refund = calls::balances::transfer_keep_alive(alice.account_id(), DOLLARS);
+		let block = client.submit_and_wait(&client.sign(refund, &dave, 0).await.unwrap())
+			.await
+			.unwrap();
+
+		let records = client.events(Some(block)).await.unwrap();
+		assert_eq!(
+			events::transfers(&records),
+			vec![(dave.account_id(), alice.account_id(), DOLLARS)],
+		);
+		let info = client.account(&dave.account_id(), Some(block)).await.unwrap();
+		assert_eq!(info.nonce, 1);
+		assert!(info.data.free < 9 * DOLLARS);
+	});
+}
+
+#[test]
+fn subscriptions_follow_new_blocks() {
+	let mut node = TestNode::start();
+	let url = node.url.clone();
+	node.block_on(async {
+		let client = Client::connect(&url).await.unwrap();
+		let alice = Sr25519Signer::new(AccountKeyring::Alice.pair());
+		// Each subscription starts with the current best block, the genesis block.
+		let mut heads = Box::pin(client.subscribe_new_heads().await.unwrap().skip(1));
+		let mut stored = Box::pin(
+			client.subscribe_storage(vec![storage::something()]).await.unwrap().skip(1)
+		);
+		let mut new_events = Box::pin(client.subscribe_events().await.unwrap().skip(1));
+
+		let extrinsic = client.sign(calls::template::do_something(7), &alice, 0).await.unwrap();
+		let block = client.submit_and_wait(&extrinsic).await.unwrap();
+
+		let header = heads.try_next().await.unwrap().unwrap();
+		assert_eq!((header.number, header.hash()), (1, block));
+
+		let changes = stored.try_next().await.unwrap().unwrap();
+		assert_eq!(changes.block, block);
+		assert_eq!(changes.changes[0].1.as_ref().map(|data| &data.0[..]), Some(&[7, 0, 0, 0][..]));
+
+		let (hash, records) = new_events.try_next().await.unwrap().unwrap();
+		assert_eq!(hash, block);
+		assert_eq!(events::something_stored(&records), vec![(7, alice.account_id())]);
+	});
+}
@@ -1,16 +1,28 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, BalancesConfig, ContractsConfig, ContractsSchedule, EVMConfig,
+	GenesisConfig, GrandpaConfig, IndicesConfig, SudoConfig, SystemConfig, VestingConfig,
+	WASM_BINARY, Signature, Balance, BlockNumber, DAYS,
 };
-use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+#[cfg(not(feature = "babe"))]
+use node_template_runtime::AuraConfig;
+#[cfg(feature = "babe")]
+use node_template_runtime::BabeConfig;
+#[cfg(not(feature = "babe"))]
+use sp_consensus_aura::sr25519::AuthorityId as BlockAuthorId;
+#[cfg(feature = "babe")]
+use sp_consensus_babe::AuthorityId as BlockAuthorId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{Verify, IdentifyAccount};
 use sc_service::ChainType;
+use std::collections::BTreeMap;
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// The initial balance of every pre-funded account.
+pub const ENDOWMENT: Balance = 1 << 60;
+
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
 pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig>;
 
@@ -30,10 +42,25 @@ pub fn get_account_id_from_seed<TPublic: Public>(seed: &str) -> AccountId where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Generate an Aura authority key.
-pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
+/// Generate a genesis vesting schedule for an endowed account.
+///
+/// Nothing of the endowment is transferable before the `cliff` block, from which on `per_block`
+/// is unlocked with every block. The remainder of the endowment that does not divide into whole
+/// blocks is liquid from genesis.
+pub fn vesting_schedule(
+	who: AccountId,
+	cliff: BlockNumber,
+	per_block: Balance,
+) -> (AccountId, BlockNumber, BlockNumber, Balance) {
+	let length = (ENDOWMENT / per_block.max(1)).min(BlockNumber::max_value() as Balance);
+	let liquid = ENDOWMENT - per_block * length;
+	(who, cliff, length as BlockNumber, liquid)
+}
+
+/// Generate a block authoring (Aura or BABE) and a GRANDPA authority key.
+pub fn authority_keys_from_seed(s: &str) -> (BlockAuthorId, GrandpaId) {
 	(
-		get_from_seed::<AuraId>(s),
+		get_from_seed::<BlockAuthorId>(s),
 		get_from_seed::<GrandpaId>(s),
 	)
 }
@@ -62,6 +89,8 @@ pub fn development_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 			],
+			// Vested balances
+			vec![],
 			true,
 		),
 		// Bootnodes
@@ -110,7 +139,17 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 			],
-			true,
+			// Vested balances
+			vec![
+				vesting_schedule(
+					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
+					// Cliff
+					DAYS,
+					// Unlocked per block, fully vested 30 days after the cliff
+					ENDOWMENT / (30 * DAYS) as Balance,
+				),
+			],
+			false,
 		),
 		// Bootnodes
 		vec![],
@@ -128,10 +167,11 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
-	initial_authorities: Vec<(AuraId, GrandpaId)>,
+	initial_authorities: Vec<(BlockAuthorId, GrandpaId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
-	_enable_println: bool,
+	vested_balances: Vec<(AccountId, BlockNumber, BlockNumber, Balance)>,
+	enable_println: bool,
 ) -> GenesisConfig {
 	GenesisConfig {
 		frame_system: Some(SystemConfig {
@@ -141,14 +181,39 @@ fn testnet_genesis(
 		}),
 		pallet_balances: Some(BalancesConfig {
 			// Configure endowed accounts with initial balance of 1 << 60.
-			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
+			balances: endowed_accounts.iter().cloned().map(|k|(k, ENDOWMENT)).collect(),
 		}),
+		pallet_vesting: Some(VestingConfig {
+			// Lock (part of) the endowment of vested accounts: (who, cliff, length, liquid).
+			vesting: vested_balances,
+		}),
+		pallet_indices: Some(IndicesConfig {
+			indices: vec![],
+		}),
+		#[cfg(not(feature = "babe"))]
 		pallet_aura: Some(AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
 		}),
+		#[cfg(feature = "babe")]
+		pallet_babe: Some(BabeConfig {
+			// All authorities share the same weight; the epoch configuration is taken from the
+			// runtime's `BabeApi::configuration`.
+			authorities: initial_authorities.iter().map(|x| (x.0.clone(), 1)).collect(),
+		}),
 		pallet_grandpa: Some(GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
 		}),
+		pallet_contracts: Some(ContractsConfig {
+			// Let contracts print to the node's console on development chains only.
+			current_schedule: ContractsSchedule {
+				enable_println,
+				..Default::default()
+			},
+		}),
+		pallet_evm: Some(EVMConfig {
+			// EVM accounts are funded through the accounts their addresses map to.
+			accounts: BTreeMap::new(),
+		}),
 		pallet_sudo: Some(SudoConfig {
 			// Assign network admin rights.
 			key: root_key,
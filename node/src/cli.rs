@@ -1,5 +1,8 @@
+use std::{net::Ipv4Addr, path::PathBuf, time::Duration};
 use structopt::StructOpt;
 use sc_cli::RunCmd;
+use sc_service::{ChainType, Configuration};
+use crate::{health, indexer::Index};
 
 #[derive(Debug, StructOpt)]
 pub struct Cli {
@@ -8,6 +11,62 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	#[structopt(flatten)]
+	pub health: HealthParams,
+
+	/// Index the blocks, extrinsics and events of the best chain into this SQLite database.
+	///
+	/// Catching up with blocks imported before requires their state, see `--pruning archive`.
+	#[structopt(long = "index-db", value_name = "PATH", parse(from_os_str))]
+	pub index_db: Option<PathBuf>,
+
+	/// Author a block as soon as a transaction enters the pool, without Aura or GRANDPA.
+	///
+	/// Meant for development chains and the tests of clients; the blocks are never finalized.
+	#[structopt(long = "instant-seal")]
+	pub instant_seal: bool,
+}
+
+/// Parameters of the health and readiness endpoints.
+#[derive(Debug, StructOpt)]
+pub struct HealthParams {
+	/// Serve `/health` and `/ready` over HTTP on this port.
+	#[structopt(long = "health-port", value_name = "PORT")]
+	pub health_port: Option<u16>,
+
+	/// Listen to all network interfaces for the health endpoints, rather than to localhost only.
+	#[structopt(long = "health-external")]
+	pub health_external: bool,
+
+	/// The maximum age of the best block, in seconds, for the node to be healthy.
+	#[structopt(long = "health-max-block-age", value_name = "SECONDS", default_value = "60")]
+	pub health_max_block_age: u64,
+
+	/// The maximum number of blocks between the best and the finalized block for the node to be
+	/// ready.
+	#[structopt(long = "health-max-finality-lag", value_name = "BLOCKS", default_value = "20")]
+	pub health_max_finality_lag: u32,
+}
+
+impl HealthParams {
+	/// The configuration of the health endpoints of a node, if they are enabled.
+	///
+	/// Development chains run on a single node, so they are healthy without peers.
+	pub fn config(&self, config: &Configuration) -> Option<health::Config> {
+		let interface = if self.health_external {
+			Ipv4Addr::UNSPECIFIED
+		} else {
+			Ipv4Addr::LOCALHOST
+		};
+
+		self.health_port.map(|port| health::Config {
+			addr: (interface, port).into(),
+			require_peers: config.chain_spec.chain_type() != ChainType::Development,
+			max_block_age: Duration::from_secs(self.health_max_block_age),
+			max_finality_lag: self.health_max_finality_lag,
+		})
+	}
 }
 
 #[derive(Debug, StructOpt)]
@@ -21,9 +80,15 @@ pub enum Subcommand {
 	/// Export blocks.
 	ExportBlocks(sc_cli::ExportBlocksCmd),
 
+	/// Export the decoded extrinsics and events of blocks.
+	ExportEvents(crate::export::ExportEventsCmd),
+
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Build a development chain spec from the state of a chain.
+	ForkOff(crate::fork_off::ForkOffCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
@@ -33,7 +98,101 @@ pub enum Subcommand {
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Write a snapshot of the chain, or bootstrap a database from one.
+	Snapshot(crate::snapshot::SnapshotCmd),
+
+	/// Query the index written with `--index-db`.
+	QueryIndex(QueryIndexCmd),
+
+	/// Build, sign and submit transactions without a local node.
+	Tx(crate::tx::TxCmd),
+
+	/// Dry-run a runtime upgrade on the state of the chain.
+	#[cfg(feature = "try-runtime")]
+	TryRuntime(crate::try_runtime::TryRuntimeCmd),
+
+	/// Dry-run a runtime upgrade on the state of the chain.
+	#[cfg(not(feature = "try-runtime"))]
+	TryRuntime,
+
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
 }
+
+/// The `query-index` command, printing the most recent rows first, one per line.
+#[derive(Debug, StructOpt)]
+pub struct QueryIndexCmd {
+	/// The index database.
+	#[structopt(long = "index-db", value_name = "PATH", parse(from_os_str))]
+	pub index_db: PathBuf,
+
+	/// The maximum number of rows to print.
+	#[structopt(long, default_value = "100")]
+	pub limit: u32,
+
+	#[structopt(subcommand)]
+	pub query: IndexQuery,
+}
+
+/// What to query the index for.
+#[derive(Debug, StructOpt)]
+pub enum IndexQuery {
+	/// Balance transfers.
+	Transfers {
+		/// Only the transfers from or to this SS58 address.
+		#[structopt(long)]
+		account: Option<String>,
+	},
+
+	/// Values stored in the template pallet.
+	SomethingStored,
+
+	/// Events.
+	Events {
+		/// Only the events of this pallet, e.g. `TemplateModule`.
+		#[structopt(long)]
+		pallet: Option<String>,
+	},
+}
+
+impl QueryIndexCmd {
+	/// Run the query and print its rows.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		if !self.index_db.exists() {
+			return Err(format!("No index at {}", self.index_db.display()).into());
+		}
+		let index = Index::open(&self.index_db).map_err(|e| e.to_string())?;
+
+		match &self.query {
+			IndexQuery::Transfers { account } => {
+				let transfers = index.transfers(account.as_deref(), self.limit)
+					.map_err(|e| e.to_string())?;
+				for t in transfers {
+					println!(
+						"#{}\t{}\t{} -> {}\t{}",
+						t.block_number, t.block_hash, t.sender, t.recipient, t.amount,
+					);
+				}
+			},
+			IndexQuery::SomethingStored => {
+				let stored = index.something_stored(self.limit).map_err(|e| e.to_string())?;
+				for s in stored {
+					println!("#{}\t{}\t{}\t{}", s.block_number, s.block_hash, s.who, s.value);
+				}
+			},
+			IndexQuery::Events { pallet } => {
+				let events = index.events(pallet.as_deref(), self.limit)
+					.map_err(|e| e.to_string())?;
+				for e in events {
+					let extrinsic = e.extrinsic.map_or("-".into(), |xt| xt.to_string());
+					println!(
+						"#{}\t{}\t{}\t{}::{}\t{}",
+						e.block_number, e.block_hash, extrinsic, e.pallet, e.name, e.data,
+					);
+				}
+			},
+		}
+		Ok(())
+	}
+}
@@ -17,6 +17,7 @@
 
 use crate::{chain_spec, service};
 use crate::cli::{Cli, Subcommand};
+use crate::snapshot::SnapshotCmd;
 use sc_cli::{SubstrateCli, RuntimeVersion, Role, ChainSpec};
 use sc_service::PartialComponents;
 use node_template_runtime::Block;
@@ -86,6 +87,14 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, config.database), task_manager))
 			})
 		},
+		Some(Subcommand::ExportEvents(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, task_manager, ..}
+					= service::new_partial(&config)?;
+				Ok((cmd.run(client), task_manager))
+			})
+		},
 		Some(Subcommand::ExportState(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -94,6 +103,18 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
+		Some(Subcommand::ForkOff(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			if cmd.state.is_some() {
+				runner.sync_run(|_| cmd.run_from_file())
+			} else {
+				runner.async_run(|config| {
+					let PartialComponents { client, task_manager, ..}
+						= service::new_partial(&config)?;
+					Ok((cmd.run_from_database(client, config.chain_spec), task_manager))
+				})
+			}
+		},
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -114,6 +135,42 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, backend), task_manager))
 			})
 		},
+		Some(Subcommand::Snapshot(SnapshotCmd::Create(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, task_manager, ..}
+					= service::new_partial(&config)?;
+				Ok((cmd.run(client), task_manager))
+			})
+		},
+		Some(Subcommand::Snapshot(SnapshotCmd::Restore(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, task_manager, backend, ..}
+					= service::new_partial(&config)?;
+				Ok((cmd.run(client, backend), task_manager))
+			})
+		},
+		Some(Subcommand::QueryIndex(cmd)) => cmd.run(),
+		Some(Subcommand::Tx(cmd)) => cmd.run(),
+		#[cfg(feature = "try-runtime")]
+		Some(Subcommand::TryRuntime(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			if cmd.needs_database() {
+				runner.async_run(|config| {
+					let PartialComponents { client, task_manager, ..}
+						= service::new_partial(&config)?;
+					Ok((cmd.run_from_database(client), task_manager))
+				})
+			} else {
+				runner.sync_run(|_| cmd.run_from_file())
+			}
+		},
+		#[cfg(not(feature = "try-runtime"))]
+		Some(Subcommand::TryRuntime) => {
+			Err("TryRuntime wasn't enabled when building the node. \
+			You can enable it with `--features try-runtime`.".into())
+		},
 		Some(Subcommand::Benchmark(cmd)) => {
 			if cfg!(feature = "runtime-benchmarks") {
 				let runner = cli.create_runner(cmd)?;
@@ -126,9 +183,19 @@ pub fn run() -> sc_cli::Result<()> {
 		},
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
-			runner.run_node_until_exit(|config| match config.role {
-				Role::Light => service::new_light(config),
-				_ => service::new_full(config),
+			let health = &cli.health;
+			let index_db = &cli.index_db;
+			let instant_seal = cli.instant_seal;
+			runner.run_node_until_exit(|config| {
+				let options = service::NodeOptions {
+					health: health.config(&config),
+					index_db: index_db.clone(),
+					instant_seal,
+				};
+				match config.role {
+					Role::Light => service::new_light(config, options),
+					_ => service::new_full(config, options),
+				}
 			})
 		}
 	}
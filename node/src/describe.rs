@@ -0,0 +1,369 @@
+//! Descriptions of runtime calls and events for offline tools.
+//!
+//! Calls and events are decoded from their encoding with the metadata of the runtime, the way
+//! [`call::encode`] encodes calls: the metadata names the pallet and the variant, and gives the
+//! type of each field, by which the field is decoded into a typed JSON value. Integers are JSON
+//! numbers, or decimal strings above 64 bits; accounts and addresses are SS58 strings like
+//! [`address`] renders them; hashes and bytes are `0x`-prefixed hex; nested calls are
+//! descriptions themselves; enums are written like serde writes them, e.g. `"BadOrigin"` or
+//! `{"Module": {"index": 5, "error": 0}}`.
+//!
+//! A field of a type this module does not know is given as the hex of the rest of the encoding,
+//! which it cannot split further, so that no field is lost.
+
+use codec::{Compact, Decode, Encode};
+use frame_support::{
+	traits::BalanceStatus,
+	weights::{DispatchClass, DispatchInfo, Pays},
+};
+use node_template_runtime::{AccountId, Address, BlockNumber, Call, Event, ProxyType};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sp_core::{H160, H256, U256, bytes, crypto::Ss58Codec};
+use sp_runtime::DispatchError;
+use crate::tx::call;
+
+/// The name of a pallet, and the name and the field types of each of its calls or events.
+struct Variants {
+	pallet: String,
+	variants: Vec<(String, Vec<String>)>,
+}
+
+/// The pallets with calls and those with events, in the order of the variants of `Call` and
+/// `Event`.
+struct Pallets {
+	calls: Vec<Variants>,
+	events: Vec<Variants>,
+}
+
+thread_local! {
+	static PALLETS: Result<Pallets, String> = pallets();
+}
+
+fn pallets() -> Result<Pallets, String> {
+	let mut pallets = Pallets { calls: Vec::new(), events: Vec::new() };
+	for module in call::modules()? {
+		let pallet = call::decoded(module.name)?;
+		if let Some(calls) = module.calls {
+			let variants = call::decoded(calls)?.into_iter()
+				.map(|call| {
+					let types = call::decoded(call.arguments)?.into_iter()
+						.map(|argument| call::decoded(argument.ty))
+						.collect::<Result<_, _>>()?;
+					Ok((call::decoded(call.name)?, types))
+				})
+				.collect::<Result<_, String>>()?;
+			pallets.calls.push(Variants { pallet: pallet.clone(), variants });
+		}
+		if let Some(events) = module.event {
+			let variants = call::decoded(events)?.into_iter()
+				.map(|event| Ok((call::decoded(event.name)?, call::decoded(event.arguments)?)))
+				.collect::<Result<_, String>>()?;
+			pallets.events.push(Variants { pallet, variants });
+		}
+	}
+	Ok(pallets)
+}
+
+/// The pallet, the name and the fields of a call or an event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Description {
+	/// The name of the pallet in the runtime, e.g. `Balances`.
+	pub pallet: String,
+	/// The name of the call or the event.
+	pub name: String,
+	/// The value of each field.
+	pub args: Vec<Value>,
+}
+
+/// Describe a call.
+pub fn call(call: &Call) -> Description {
+	describe(&call.encode(), |pallets| &pallets.calls)
+}
+
+/// Describe an event.
+pub fn event(event: &Event) -> Description {
+	describe(&event.encode(), |pallets| &pallets.events)
+}
+
+/// The SS58 form of an address, or its index prefixed with `#`.
+pub fn address(address: &Address) -> String {
+	match address {
+		Address::Id(who) => who.to_string(),
+		Address::Index(index) => format!("#{}", index),
+	}
+}
+
+/// Describe an encoded call or event, whose pallets are listed by `variants`.
+fn describe(encoded: &[u8], variants: fn(&Pallets) -> &Vec<Variants>) -> Description {
+	PALLETS.with(|pallets| match pallets {
+		Ok(pallets) => decode_variant(variants(pallets), encoded),
+		Err(e) => Err(e.clone()),
+	}).unwrap_or_else(|e| {
+		log::warn!(
+			target: "describe",
+			"Unable to describe {}: {}", bytes::to_hex(encoded, false), e,
+		);
+		Description { pallet: String::new(), name: String::new(), args: vec![hex(encoded)] }
+	})
+}
+
+/// Decode a call or an event, which starts with the index of its pallet in `pallets` and the
+/// index of its variant.
+fn decode_variant(pallets: &[Variants], mut input: &[u8]) -> Result<Description, String> {
+	let pallet = pallets.get(read::<u8>(&mut input)? as usize).ok_or("Unknown pallet")?;
+	let (name, types) = pallet.variants.get(read::<u8>(&mut input)? as usize)
+		.ok_or_else(|| format!("Unknown variant of {}", pallet.pallet))?;
+
+	let mut args = Vec::with_capacity(types.len());
+	for ty in types {
+		let mut field = input;
+		match decode(&call::normalize(ty), &mut field) {
+			Ok(value) => {
+				args.push(value);
+				input = field;
+			},
+			Err(e) => {
+				log::debug!(
+					target: "describe",
+					"Unable to decode {} of {}.{}: {}", ty, pallet.pallet, name, e,
+				);
+				args.push(hex(input));
+				break;
+			},
+		}
+	}
+	Ok(Description { pallet: pallet.pallet.clone(), name: name.clone(), args })
+}
+
+fn read<T: Decode>(input: &mut &[u8]) -> Result<T, String> {
+	T::decode(input).map_err(|e| e.to_string())
+}
+
+fn hex(bytes: &[u8]) -> Value {
+	Value::String(bytes::to_hex(bytes, false))
+}
+
+/// An unsigned integer of `bits` bits, as a number when JSON numbers hold it exactly.
+fn uint(n: u128, bits: u32) -> Value {
+	if bits <= 64 {
+		json!(n as u64)
+	} else {
+		json!(n.to_string())
+	}
+}
+
+/// The parameter of the generic type `ty` if it is a `name`.
+fn generic<'a>(ty: &'a str, name: &str) -> Option<&'a str> {
+	ty.strip_prefix(name)?.strip_prefix('<')?.strip_suffix('>')
+}
+
+/// Split the types of a tuple, leaving the commas of nested types alone.
+fn tuple(types: &str) -> Vec<&str> {
+	let mut items = Vec::new();
+	let mut depth = 0usize;
+	let mut start = 0;
+	for (i, c) in types.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth = depth.saturating_sub(1),
+			',' if depth == 0 => {
+				items.push(&types[start..i]);
+				start = i + 1;
+			},
+			_ => {},
+		}
+	}
+	if start < types.len() {
+		items.push(&types[start..]);
+	}
+	items
+}
+
+/// Decode a value of the type named `ty`, normalized like [`call::normalize`] does.
+fn decode(ty: &str, input: &mut &[u8]) -> Result<Value, String> {
+	if let Some(inner) = generic(ty, "Compact") {
+		let bits = call::uint_bits(inner).ok_or_else(|| format!("unsupported type {}", ty))?;
+		return Ok(uint(read::<Compact<u128>>(input)?.0, bits));
+	}
+	if let Some(inner) = generic(ty, "Option") {
+		return match read::<u8>(input)? {
+			0 => Ok(Value::Null),
+			1 => decode(inner, input),
+			_ => Err("invalid option".into()),
+		};
+	}
+	if let Some(inner) = generic(ty, "Box") {
+		return decode(inner, input);
+	}
+	if ty == "Vec<u8>" {
+		return Ok(hex(&read::<Vec<u8>>(input)?));
+	}
+	if let Some(inner) = generic(ty, "Vec") {
+		let len = read::<Compact<u32>>(input)?.0;
+		return (0..len).map(|_| decode(inner, input)).collect::<Result<_, _>>().map(Value::Array);
+	}
+	if let Some(items) = ty.strip_prefix('(').and_then(|ty| ty.strip_suffix(')')) {
+		return tuple(items).into_iter()
+			.map(|item| decode(item, input))
+			.collect::<Result<_, _>>()
+			.map(Value::Array);
+	}
+	if let Some(len) = ty.strip_prefix("[u8;").and_then(|ty| ty.strip_suffix(']')) {
+		let len = len.parse::<usize>().map_err(|e| e.to_string())?;
+		if input.len() < len {
+			return Err("not enough data".into());
+		}
+		let (bytes, rest) = input.split_at(len);
+		*input = rest;
+		return Ok(hex(bytes));
+	}
+	if let Some(bits) = call::uint_bits(ty) {
+		let n = match bits {
+			8 => read::<u8>(input)?.into(),
+			16 => read::<u16>(input)?.into(),
+			32 => read::<u32>(input)?.into(),
+			64 => read::<u64>(input)?.into(),
+			_ => read::<u128>(input)?,
+		};
+		return Ok(uint(n, bits));
+	}
+
+	Ok(match ty {
+		"bool" => json!(read::<bool>(input)?),
+		"AccountId" => json!(read::<AccountId>(input)?.to_ss58check()),
+		"<LookupasStaticLookup>::Source" => json!(address(&read::<Address>(input)?)),
+		"Hash" | "H256" | "CallHash" => hex(read::<H256>(input)?.as_bytes()),
+		"H160" => hex(read::<H160>(input)?.as_bytes()),
+		"U256" => json!(read::<U256>(input)?.to_string()),
+		"Call" => json!(call(&read::<Call>(input)?)),
+		"DispatchInfo" => dispatch_info(read(input)?),
+		"DispatchError" => dispatch_error(read(input)?),
+		"DispatchResult" => match read::<Result<(), DispatchError>>(input)? {
+			Ok(()) => json!({ "Ok": null }),
+			Err(e) => json!({ "Err": dispatch_error(e) }),
+		},
+		// GRANDPA authorities are ed25519 keys with a weight.
+		"AuthorityList" => decode("Vec<([u8;32],u64)>", input)?,
+		"TaskAddress<BlockNumber>" => decode("(BlockNumber,u32)", input)?,
+		"Timepoint<BlockNumber>" => json!({
+			"height": read::<BlockNumber>(input)?,
+			"index": read::<u32>(input)?,
+		}),
+		"ProxyType" | "CallFilter" => json!(match read::<ProxyType>(input)? {
+			ProxyType::Any => "Any",
+			ProxyType::NonTransfer => "NonTransfer",
+			ProxyType::Governance => "Governance",
+			ProxyType::TemplateOnly => "TemplateOnly",
+		}),
+		"Status" => json!(match read::<BalanceStatus>(input)? {
+			BalanceStatus::Free => "Free",
+			BalanceStatus::Reserved => "Reserved",
+		}),
+		// The log of an EVM execution.
+		"Log" => json!({
+			"address": decode("H160", input)?,
+			"topics": decode("Vec<H256>", input)?,
+			"data": decode("Vec<u8>", input)?,
+		}),
+		_ => return Err(format!("unsupported type {}", ty)),
+	})
+}
+
+fn dispatch_info(info: DispatchInfo) -> Value {
+	json!({
+		"weight": info.weight,
+		"class": match info.class {
+			DispatchClass::Normal => "Normal",
+			DispatchClass::Operational => "Operational",
+			DispatchClass::Mandatory => "Mandatory",
+		},
+		"paysFee": match info.pays_fee {
+			Pays::Yes => "Yes",
+			Pays::No => "No",
+		},
+	})
+}
+
+fn dispatch_error(error: DispatchError) -> Value {
+	match error {
+		DispatchError::Other(_) => json!("Other"),
+		DispatchError::CannotLookup => json!("CannotLookup"),
+		DispatchError::BadOrigin => json!("BadOrigin"),
+		DispatchError::Module { index, error, .. } =>
+			json!({ "Module": { "index": index, "error": error } }),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_template_runtime::{BalancesCall, pallet_template};
+	use sp_keyring::AccountKeyring;
+
+	#[test]
+	fn calls_are_described_by_pallet_method_and_args() {
+		let do_something = Call::TemplateModule(pallet_template::Call::do_something(42));
+		assert_eq!(call(&do_something), Description {
+			pallet: "TemplateModule".into(),
+			name: "do_something".into(),
+			args: vec![json!(42)],
+		});
+	}
+
+	#[test]
+	fn events_are_described_by_pallet_name_and_fields() {
+		let who = AccountKeyring::Alice.to_account_id();
+		let stored = Event::pallet_template(
+			pallet_template::RawEvent::SomethingStored(42, who.clone()),
+		);
+		assert_eq!(event(&stored), Description {
+			pallet: "TemplateModule".into(),
+			name: "SomethingStored".into(),
+			args: vec![json!(42), json!(who.to_ss58check())],
+		});
+	}
+
+	#[test]
+	fn accounts_and_addresses_are_rendered_in_ss58() {
+		let bob = AccountKeyring::Bob.to_account_id();
+		let transfer = Call::Balances(BalancesCall::transfer(Address::Id(bob.clone()), 5));
+		// balances do not fit in JSON numbers.
+		assert_eq!(call(&transfer).args, vec![json!(bob.to_ss58check()), json!("5")]);
+		let transfer = Call::Balances(BalancesCall::transfer(Address::Index(3), 5));
+		assert_eq!(call(&transfer).args, vec![json!("#3"), json!("5")]);
+	}
+
+	#[test]
+	fn nested_calls_and_dispatch_outcomes_are_typed() {
+		let inner = Call::TemplateModule(pallet_template::Call::do_something(1));
+		let sudo = Call::Sudo(pallet_sudo::Call::sudo(Box::new(inner)));
+		assert_eq!(call(&sudo).args, vec![json!({
+			"pallet": "TemplateModule",
+			"name": "do_something",
+			"args": [1],
+		})]);
+
+		let failed = Event::frame_system(frame_system::RawEvent::ExtrinsicFailed(
+			DispatchError::Module { index: 5, error: 2, message: None },
+			DispatchInfo { weight: 10, ..Default::default() },
+		));
+		assert_eq!(event(&failed).args, vec![
+			json!({ "Module": { "index": 5, "error": 2 } }),
+			json!({ "weight": 10, "class": "Normal", "paysFee": "Yes" }),
+		]);
+	}
+
+	#[test]
+	fn fields_are_decoded_by_their_type() {
+		let encoded = (Some(7u32), vec![(1u8, [2u8; 2])], true).encode();
+		let mut input = &encoded[..];
+		assert_eq!(decode("Option<u32>", &mut input), Ok(json!(7)));
+		assert_eq!(decode("Vec<(u8,[u8;2])>", &mut input), Ok(json!([[1, "0x0202"]])));
+		assert_eq!(decode("bool", &mut input), Ok(json!(true)));
+		assert!(input.is_empty());
+
+		assert!(decode("Unknown", &mut &[0u8][..]).is_err());
+		assert!(decode("u32", &mut &[0u8][..]).is_err());
+	}
+}
@@ -0,0 +1,255 @@
+//! The `export-events` command, exporting the decoded extrinsics and events of a range of blocks
+//! for offline analytics.
+
+use std::{
+	collections::HashMap, fs::File, io::{self, BufWriter, Write}, path::PathBuf,
+	str::FromStr, sync::Arc,
+};
+use codec::{Decode, Encode};
+use frame_system::{Phase, RawEvent as SystemEvent};
+use node_template_runtime::{opaque::Block, BlockNumber, Event, Hash, UncheckedExtrinsic};
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::{BlockBackend, StorageProvider, backend::Backend};
+use serde_json::json;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::generic::BlockId;
+use structopt::StructOpt;
+use crate::{describe, storage};
+
+/// The format of the exported records.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+	/// One JSON object per line.
+	Jsonl,
+	/// Comma-separated values, with a header line.
+	Csv,
+}
+
+impl FromStr for ExportFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"jsonl" => Ok(ExportFormat::Jsonl),
+			"csv" => Ok(ExportFormat::Csv),
+			_ => Err(format!("Unknown export format {}, expected jsonl or csv", s)),
+		}
+	}
+}
+
+/// The `export-events` command used to export decoded extrinsics and events.
+#[derive(Debug, StructOpt)]
+pub struct ExportEventsCmd {
+	/// Output file name or stdout if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Specify starting block number.
+	///
+	/// Default is 1.
+	#[structopt(long = "from", value_name = "BLOCK")]
+	pub from: Option<BlockNumber>,
+
+	/// Specify last block number.
+	///
+	/// Default is the best block.
+	#[structopt(long = "to", value_name = "BLOCK")]
+	pub to: Option<BlockNumber>,
+
+	/// The output format.
+	#[structopt(
+		long = "format",
+		value_name = "FORMAT",
+		possible_values = &["jsonl", "csv"],
+		default_value = "jsonl",
+	)]
+	pub format: ExportFormat,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// An extrinsic or an event of a block.
+struct Record {
+	block: BlockNumber,
+	hash: Hash,
+	kind: &'static str,
+	index: u32,
+	/// The extrinsic that deposited an event.
+	extrinsic: Option<u32>,
+	signer: Option<String>,
+	description: describe::Description,
+	/// Whether an extrinsic succeeded.
+	success: Option<bool>,
+}
+
+const CSV_HEADER: &str = "block,hash,kind,index,extrinsic,signer,pallet,name,args,success";
+
+impl Record {
+	fn to_json(&self) -> serde_json::Value {
+		let mut record = json!({
+			"block": self.block,
+			"hash": format!("{:?}", self.hash),
+			"kind": self.kind,
+			"index": self.index,
+			"pallet": self.description.pallet,
+			"name": self.description.name,
+			"args": self.description.args,
+		});
+		if self.kind == "extrinsic" {
+			record["signer"] = json!(self.signer);
+			record["success"] = json!(self.success);
+		} else {
+			record["extrinsic"] = json!(self.extrinsic);
+		}
+		record
+	}
+
+	fn to_csv(&self) -> String {
+		fn optional<T: ToString>(value: &Option<T>) -> String {
+			value.as_ref().map(ToString::to_string).unwrap_or_default()
+		}
+
+		[
+			self.block.to_string(),
+			format!("{:?}", self.hash),
+			self.kind.to_string(),
+			self.index.to_string(),
+			optional(&self.extrinsic),
+			optional(&self.signer),
+			self.description.pallet.clone(),
+			self.description.name.clone(),
+			json!(self.description.args).to_string(),
+			optional(&self.success),
+		].iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",")
+	}
+}
+
+/// Quote a CSV field if needed.
+fn csv_field(field: &str) -> String {
+	if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_string()
+	}
+}
+
+/// The records of a block: its extrinsics, then its events.
+fn records(
+	block: BlockNumber,
+	hash: Hash,
+	extrinsics: &[<Block as sp_runtime::traits::Block>::Extrinsic],
+	events: &[storage::EventRecord],
+) -> Vec<Record> {
+	let mut outcomes = HashMap::new();
+	let mut event_records = Vec::with_capacity(events.len());
+	for (index, record) in events.iter().enumerate() {
+		let extrinsic = match record.phase {
+			Phase::ApplyExtrinsic(extrinsic) => Some(extrinsic),
+			_ => None,
+		};
+		match (&record.event, extrinsic) {
+			(Event::frame_system(SystemEvent::ExtrinsicSuccess(..)), Some(xt)) => {
+				outcomes.insert(xt, true);
+			},
+			(Event::frame_system(SystemEvent::ExtrinsicFailed(..)), Some(xt)) => {
+				outcomes.insert(xt, false);
+			},
+			_ => {},
+		}
+		event_records.push(Record {
+			block,
+			hash,
+			kind: "event",
+			index: index as u32,
+			extrinsic,
+			signer: None,
+			description: describe::event(&record.event),
+			success: None,
+		});
+	}
+
+	let mut records = Vec::with_capacity(extrinsics.len() + events.len());
+	for (index, extrinsic) in extrinsics.iter().enumerate() {
+		let index = index as u32;
+		let xt = match UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..]) {
+			Ok(xt) => xt,
+			Err(e) => {
+				log::warn!("Unable to decode extrinsic {}-{}: {:?}", block, index, e);
+				continue;
+			},
+		};
+		records.push(Record {
+			block,
+			hash,
+			kind: "extrinsic",
+			index,
+			extrinsic: None,
+			signer: xt.signature.as_ref().map(|(address, _, _)| describe::address(address)),
+			description: describe::call(&xt.function),
+			success: outcomes.get(&index).copied(),
+		});
+	}
+	records.extend(event_records);
+	records
+}
+
+impl ExportEventsCmd {
+	/// Run the export-events command
+	pub async fn run<C, BE>(&self, client: Arc<C>) -> sc_cli::Result<()> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, BE>,
+	{
+		let from = self.from.unwrap_or(1);
+		let to = self.to.unwrap_or_else(|| client.info().best_number);
+
+		let mut output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+			None => Box::new(io::stdout()),
+		};
+		if self.format == ExportFormat::Csv {
+			writeln!(output, "{}", CSV_HEADER)?;
+		}
+
+		for number in from..=to {
+			let hash = client.hash(number)?
+				.ok_or_else(|| format!("Block #{} is not in the database", number))?;
+			let id = BlockId::hash(hash);
+			let extrinsics = client.block_body(&id)?.unwrap_or_default();
+			let events = storage::events(&*client, &id)
+				.ok_or_else(|| format!("The state of block #{} is not available", number))?;
+
+			for record in records(number, hash, &extrinsics, &events) {
+				match self.format {
+					ExportFormat::Jsonl => writeln!(output, "{}", record.to_json())?,
+					ExportFormat::Csv => writeln!(output, "{}", record.to_csv())?,
+				}
+			}
+		}
+		output.flush()?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportEventsCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
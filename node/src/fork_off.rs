@@ -0,0 +1,236 @@
+//! The `fork-off` command, building a development chain from the state of a live one.
+//!
+//! The state is taken from a chain spec written by `export-state`, or read from the local
+//! database. The runtime code is replaced by the one of this node, and the block authoring,
+//! GRANDPA and sudo keys by the ones of Alice, so that a single development node can author and
+//! finalize blocks on top of the real state. Alice is endowed so that she can pay for transactions.
+
+use std::{fs, path::PathBuf, sync::Arc};
+use codec::{Decode, Encode};
+use node_template_runtime::{opaque::Block, Balance, Index, Runtime, WASM_BINARY};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::{StorageProvider, UsageProvider, backend::Backend};
+use sc_service::{ChainSpec, ChainType};
+use sp_core::storage::{Storage, well_known_keys};
+use sp_finality_grandpa::{AuthorityList, VersionedAuthorityList, GRANDPA_AUTHORITIES_KEY};
+use sp_runtime::{BuildStorage, generic::BlockId};
+use structopt::StructOpt;
+use crate::{chain_spec, storage::value_key};
+
+/// The `fork-off` command used to build a development chain spec from the state of a chain.
+#[derive(Debug, StructOpt)]
+pub struct ForkOffCmd {
+	/// A raw chain spec written by `export-state`, rather than the local database.
+	#[structopt(long = "state", value_name = "PATH", parse(from_os_str))]
+	pub state: Option<PathBuf>,
+
+	/// Block hash or number to fork off from the local database.
+	///
+	/// Default is the best block.
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	/// The ID of the new chain.
+	#[structopt(long = "chain-id", value_name = "ID", default_value = "fork")]
+	pub chain_id: String,
+
+	/// Output file name or stdout if unspecified.
+	#[structopt(long = "output", value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// The account of a user, with its balance.
+type AccountInfo = frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+
+/// Hand the chain over to Alice, running the runtime of this node.
+fn fork(storage: &mut Storage) -> Result<(), String> {
+	let code = WASM_BINARY.ok_or("Development wasm binary not available".to_string())?;
+	let (block_author, grandpa) = chain_spec::authority_keys_from_seed("Alice");
+	let sudo = chain_spec::get_account_id_from_seed::<sp_core::sr25519::Public>("Alice");
+	let top = &mut storage.top;
+
+	top.insert(well_known_keys::CODE.to_vec(), code.to_vec());
+
+	#[cfg(not(feature = "babe"))]
+	top.insert(value_key(b"Aura", b"Authorities").0, vec![block_author].encode());
+
+	// The epochs of the new chain start over from its first block.
+	#[cfg(feature = "babe")]
+	{
+		let authorities = vec![(block_author, 1u64)].encode();
+		top.insert(value_key(b"Babe", b"Authorities").0, authorities.clone());
+		top.insert(value_key(b"Babe", b"NextAuthorities").0, authorities);
+		for item in &[&b"GenesisSlot"[..], b"CurrentSlot", b"EpochIndex"] {
+			top.remove(&value_key(b"Babe", item).0);
+		}
+	}
+
+	// GRANDPA starts over from the first set, with no pending change.
+	let grandpa_authorities: AuthorityList = vec![(grandpa, 1)];
+	top.insert(
+		GRANDPA_AUTHORITIES_KEY.to_vec(),
+		VersionedAuthorityList::from(grandpa_authorities).encode(),
+	);
+	top.insert(value_key(b"GrandpaFinality", b"CurrentSetId").0, 0u64.encode());
+	for item in &[&b"State"[..], b"PendingChange", b"NextForced", b"Stalled"] {
+		top.remove(&value_key(b"GrandpaFinality", item).0);
+	}
+
+	top.insert(value_key(b"Sudo", b"Key").0, sudo.encode());
+
+	// The total issuance follows the balance of Alice, so that the chain stays consistent.
+	let account_key = frame_system::Account::<Runtime>::hashed_key_for(&sudo);
+	let mut account = top.get(&account_key)
+		.and_then(|data| AccountInfo::decode(&mut &data[..]).ok())
+		.unwrap_or_default();
+	let issuance_key = value_key(b"Balances", b"TotalIssuance").0;
+	let issuance = top.get(&issuance_key)
+		.and_then(|data| Balance::decode(&mut &data[..]).ok())
+		.unwrap_or_default()
+		.saturating_sub(account.data.free)
+		.saturating_add(chain_spec::ENDOWMENT);
+	account.data.free = chain_spec::ENDOWMENT;
+	top.insert(account_key, account.encode());
+	top.insert(issuance_key, issuance.encode());
+	Ok(())
+}
+
+impl ForkOffCmd {
+	/// Fork off the chain spec given with `--state`.
+	pub fn run_from_file(&self) -> sc_cli::Result<()> {
+		let path = self.state.as_ref().ok_or("No state file given")?;
+		let spec = chain_spec::ChainSpec::from_json_file(path.clone())?;
+		let storage = spec.build_storage()?;
+		self.write(spec.name(), storage)
+	}
+
+	/// Fork off the state of the local database.
+	pub async fn run_from_database<C, BE>(
+		&self,
+		client: Arc<C>,
+		spec: Box<dyn ChainSpec>,
+	) -> sc_cli::Result<()> where
+		BE: Backend<Block>,
+		C: UsageProvider<Block> + StorageProvider<Block, BE>,
+	{
+		let block_id = match &self.input {
+			Some(input) => input.parse()?,
+			None => BlockId::Hash(client.usage_info().chain.best_hash),
+		};
+		let storage = sc_service::chain_ops::export_raw_state(client, &block_id)?;
+		self.write(spec.name(), storage)
+	}
+
+	fn write(&self, name: &str, mut storage: Storage) -> sc_cli::Result<()> {
+		fork(&mut storage)?;
+
+		let mut spec = chain_spec::ChainSpec::from_genesis(
+			&format!("Fork of {}", name),
+			&self.chain_id,
+			ChainType::Development,
+			// The genesis is the forked-off state set below.
+			|| unreachable!("The genesis storage is set right after; qed"),
+			vec![],
+			None,
+			Some(self.chain_id.as_str()),
+			None,
+			None,
+		);
+		spec.set_storage(storage);
+		let json = spec.as_json(true)?;
+
+		match &self.output {
+			Some(path) => fs::write(path, json)?,
+			None => println!("{}", json),
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ForkOffCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::sr25519;
+
+	#[test]
+	fn the_chain_is_handed_over_to_alice() {
+		let alice = chain_spec::get_account_id_from_seed::<sr25519::Public>("Alice");
+		let bob = chain_spec::get_account_id_from_seed::<sr25519::Public>("Bob");
+		let (alice_author, alice_grandpa) = chain_spec::authority_keys_from_seed("Alice");
+		let (bob_author, bob_grandpa) = chain_spec::authority_keys_from_seed("Bob");
+		let account_key = frame_system::Account::<Runtime>::hashed_key_for(&alice);
+		let issuance_key = value_key(b"Balances", b"TotalIssuance").0;
+
+		let mut storage = Storage::default();
+		let top = &mut storage.top;
+		top.insert(well_known_keys::CODE.to_vec(), vec![1, 2, 3]);
+		#[cfg(not(feature = "babe"))]
+		top.insert(value_key(b"Aura", b"Authorities").0, vec![bob_author].encode());
+		#[cfg(feature = "babe")]
+		top.insert(value_key(b"Babe", b"Authorities").0, vec![(bob_author, 1u64)].encode());
+		let grandpa_authorities: AuthorityList = vec![(bob_grandpa, 1)];
+		top.insert(
+			GRANDPA_AUTHORITIES_KEY.to_vec(),
+			VersionedAuthorityList::from(grandpa_authorities).encode(),
+		);
+		top.insert(value_key(b"GrandpaFinality", b"CurrentSetId").0, 5u64.encode());
+		top.insert(value_key(b"Sudo", b"Key").0, bob.encode());
+		let mut account = AccountInfo::default();
+		account.nonce = 3;
+		account.data.free = 7;
+		top.insert(account_key.clone(), account.encode());
+		top.insert(issuance_key.clone(), (10 as Balance).encode());
+
+		fork(&mut storage).unwrap();
+		let top = &storage.top;
+		assert_eq!(top[well_known_keys::CODE], WASM_BINARY.unwrap().to_vec());
+		#[cfg(not(feature = "babe"))]
+		assert_eq!(
+			top[&value_key(b"Aura", b"Authorities").0],
+			vec![alice_author].encode(),
+		);
+		#[cfg(feature = "babe")]
+		assert_eq!(
+			top[&value_key(b"Babe", b"Authorities").0],
+			vec![(alice_author, 1u64)].encode(),
+		);
+		let grandpa_authorities: AuthorityList = vec![(alice_grandpa, 1)];
+		assert_eq!(
+			top[GRANDPA_AUTHORITIES_KEY],
+			VersionedAuthorityList::from(grandpa_authorities).encode(),
+		);
+		assert_eq!(top[&value_key(b"GrandpaFinality", b"CurrentSetId").0], 0u64.encode());
+		assert_eq!(top[&value_key(b"Sudo", b"Key").0], alice.encode());
+
+		let account = AccountInfo::decode(&mut &top[&account_key][..]).unwrap();
+		assert_eq!(account.nonce, 3);
+		assert_eq!(account.data.free, chain_spec::ENDOWMENT);
+		assert_eq!(top[&issuance_key], (3 + chain_spec::ENDOWMENT).encode());
+	}
+}
@@ -0,0 +1,296 @@
+//! HTTP health and readiness endpoints for orchestrators.
+//!
+//! `/health` answers whether the node follows the chain: it has peers when it needs them, it is
+//! not major-syncing and its best block is recent. `/ready` answers whether it can serve
+//! finalized data: the finalized block is close enough to the best one. Both answer `200` when
+//! the check passes and `503` with the reason otherwise, so probes need not speak JSON-RPC.
+//!
+//! The age of the best block is taken from its timestamp. Light clients hold no state to read it
+//! from, so they age the best block from the time they imported it instead.
+
+use std::{
+	convert::Infallible, net::SocketAddr, sync::Arc,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use futures::StreamExt;
+use hyper::{Body, Request, Response, Server, StatusCode, service::{make_service_fn, service_fn}};
+use node_template_runtime::{opaque::Block, BlockNumber, Hash};
+use parking_lot::RwLock;
+use sc_client_api::{BlockchainEvents, StorageProvider, backend::Backend};
+use sc_network::SyncState;
+use sc_service::NetworkStatusSinks;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::generic::BlockId;
+use crate::storage;
+
+/// How often the network status is refreshed.
+const NETWORK_STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The thresholds of the checks.
+#[derive(Debug, Clone)]
+pub struct Config {
+	/// The address to serve the endpoints on.
+	pub addr: SocketAddr,
+	/// Whether the node must be connected to peers to be healthy.
+	pub require_peers: bool,
+	/// The maximum age of the best block for the node to be healthy.
+	pub max_block_age: Duration,
+	/// The maximum number of blocks between the best and the finalized block for the node to be
+	/// ready.
+	pub max_finality_lag: BlockNumber,
+}
+
+/// The state of the node the checks are evaluated against.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+	/// The number of connected peers and whether the node is major-syncing, or `None` without
+	/// networking.
+	pub network: Option<(usize, bool)>,
+	/// The number of the best block.
+	pub best_number: BlockNumber,
+	/// The number of the finalized block.
+	pub finalized_number: BlockNumber,
+	/// The age of the best block according to its timestamp, if it has one.
+	pub best_block_age: Option<Duration>,
+}
+
+impl Snapshot {
+	/// Whether the node follows the chain, or why it does not.
+	pub fn health(&self, config: &Config) -> Result<(), String> {
+		if let Some((peers, major_syncing)) = self.network {
+			if config.require_peers && peers == 0 {
+				return Err("no peers".into());
+			}
+			if major_syncing {
+				return Err("major-syncing".into());
+			}
+		}
+		match self.best_block_age {
+			Some(age) if age > config.max_block_age =>
+				Err(format!("best block #{} is {}s old", self.best_number, age.as_secs())),
+			_ => Ok(()),
+		}
+	}
+
+	/// Whether the node can serve finalized data, or why it cannot.
+	pub fn ready(&self, config: &Config) -> Result<(), String> {
+		let lag = self.best_number.saturating_sub(self.finalized_number);
+		if lag > config.max_finality_lag {
+			return Err(format!("finality lags {} blocks behind", lag));
+		}
+		Ok(())
+	}
+}
+
+/// The time since the Unix epoch.
+fn now() -> Duration {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+/// Reads the [`Snapshot`] of the node.
+struct Probe<C> {
+	client: Arc<C>,
+	network: Option<Arc<RwLock<Option<(usize, bool)>>>>,
+	/// The last best block imported, and when.
+	imported: Arc<RwLock<(Hash, Duration)>>,
+}
+
+impl<C> Probe<C> {
+	fn snapshot<BE>(&self) -> Snapshot where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + StorageProvider<Block, BE>,
+	{
+		let info = self.client.info();
+		let now = now();
+		let key = storage::value_key(b"Timestamp", b"Now");
+		let at = BlockId::hash(info.best_hash);
+		let best_block_age = match storage::read_value::<u64, _, _>(&*self.client, &at, key) {
+			// The genesis block has no timestamp.
+			Some(0) => None,
+			Some(moment) =>
+				Some(now.checked_sub(Duration::from_millis(moment)).unwrap_or_default()),
+			// The state is not available, as on light clients.
+			None => {
+				let (hash, imported) = *self.imported.read();
+				let since = if hash == info.best_hash { imported } else { now };
+				Some(now.checked_sub(since).unwrap_or_default())
+			},
+		};
+
+		Snapshot {
+			// The node counts as disconnected until the network reported its first status.
+			network: self.network.as_ref().map(|status| (*status.read()).unwrap_or((0, true))),
+			best_number: info.best_number,
+			finalized_number: info.finalized_number,
+			best_block_age,
+		}
+	}
+}
+
+fn respond(check: Option<Result<(), String>>) -> Response<Body> {
+	let (status, body) = match check {
+		Some(Ok(())) => (StatusCode::OK, "OK".to_string()),
+		Some(Err(reason)) => (StatusCode::SERVICE_UNAVAILABLE, reason),
+		None => (StatusCode::NOT_FOUND, "Not found".to_string()),
+	};
+	let mut response = Response::new(Body::from(body));
+	*response.status_mut() = status;
+	response
+}
+
+/// Serve the endpoints, with the network checks when `network_status_sinks` is given, or in
+/// standalone mode otherwise.
+pub async fn run<C, BE>(
+	config: Config,
+	client: Arc<C>,
+	network_status_sinks: Option<NetworkStatusSinks<Block>>,
+) -> Result<(), hyper::Error> where
+	BE: Backend<Block> + 'static,
+	C: HeaderBackend<Block> + StorageProvider<Block, BE> + BlockchainEvents<Block>,
+	C: Send + Sync + 'static,
+{
+	let status = Arc::new(RwLock::new(None));
+	let network = network_status_sinks.as_ref().map(|_| status.clone());
+	// Without networking there is nothing to follow, and the checks are skipped.
+	let updates = async move {
+		if let Some(sinks) = network_status_sinks {
+			let mut updates = sinks.network_status(NETWORK_STATUS_INTERVAL);
+			while let Some((network, _)) = updates.next().await {
+				let major_syncing = network.sync_state == SyncState::Downloading;
+				*status.write() = Some((network.num_connected_peers, major_syncing));
+			}
+		}
+	};
+	let imported = Arc::new(RwLock::new((client.info().best_hash, now())));
+	let mut notifications = client.import_notification_stream();
+	let imports = {
+		let imported = imported.clone();
+		async move {
+			while let Some(notification) = notifications.next().await {
+				if notification.is_new_best {
+					*imported.write() = (notification.hash, now());
+				}
+			}
+		}
+	};
+	let probe = Arc::new(Probe { client, network, imported });
+	let config = Arc::new(config);
+	let addr = config.addr;
+
+	let service = make_service_fn(move |_| {
+		let probe = probe.clone();
+		let config = config.clone();
+		async move {
+			Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+				let snapshot = probe.snapshot();
+				let check = match request.uri().path() {
+					"/health" => Some(snapshot.health(&config)),
+					"/ready" => Some(snapshot.ready(&config)),
+					_ => None,
+				};
+				futures::future::ready(Ok::<_, Infallible>(respond(check)))
+			}))
+		}
+	});
+
+	log::info!("Health endpoints listening on http://{}", addr);
+	let server = Server::try_bind(&addr)?.serve(service);
+	let ((), (), result) = futures::join!(updates, imports, server);
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::FutureExt;
+	use node_template_runtime::RuntimeApi;
+	use crate::{service::Executor, testing};
+
+	fn config() -> Config {
+		Config {
+			addr: ([127, 0, 0, 1], 0).into(),
+			require_peers: true,
+			max_block_age: Duration::from_secs(60),
+			max_finality_lag: 10,
+		}
+	}
+
+	#[test]
+	fn standalone_nodes_skip_the_network_checks() {
+		let snapshot = Snapshot {
+			best_block_age: Some(Duration::from_secs(6)),
+			..Default::default()
+		};
+		assert_eq!(snapshot.health(&config()), Ok(()));
+		assert_eq!(snapshot.ready(&config()), Ok(()));
+	}
+
+	#[test]
+	fn health_requires_peers_sync_and_a_recent_block() {
+		let healthy = Snapshot {
+			network: Some((3, false)),
+			best_number: 100,
+			finalized_number: 98,
+			best_block_age: Some(Duration::from_secs(6)),
+		};
+		assert_eq!(healthy.health(&config()), Ok(()));
+
+		let no_peers = Snapshot { network: Some((0, false)), ..healthy.clone() };
+		assert!(no_peers.health(&config()).is_err());
+		assert_eq!(no_peers.health(&Config { require_peers: false, ..config() }), Ok(()));
+
+		let syncing = Snapshot { network: Some((3, true)), ..healthy.clone() };
+		assert!(syncing.health(&config()).is_err());
+
+		let stale = Snapshot { best_block_age: Some(Duration::from_secs(61)), ..healthy };
+		assert!(stale.health(&config()).is_err());
+	}
+
+	#[test]
+	fn readiness_requires_finality_to_keep_up() {
+		let snapshot = Snapshot { best_number: 100, finalized_number: 90, ..Default::default() };
+		assert_eq!(snapshot.ready(&config()), Ok(()));
+
+		let lagging = Snapshot { finalized_number: 89, ..snapshot };
+		assert_eq!(lagging.ready(&config()), Err("finality lags 11 blocks behind".into()));
+	}
+
+	/// Request `url`, waiting for the server to listen.
+	async fn get(url: String) -> (StatusCode, String) {
+		for _ in 0..50 {
+			match hyper::Client::new().get(url.parse().unwrap()).await {
+				Ok(response) => {
+					let status = response.status();
+					let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+					return (status, String::from_utf8(body.to_vec()).unwrap());
+				},
+				Err(_) => tokio::time::delay_for(Duration::from_millis(100)).await,
+			}
+		}
+		panic!("{} was not served", url);
+	}
+
+	#[test]
+	fn endpoints_are_served() {
+		let mut runtime = tokio::runtime::Builder::new()
+			.threaded_scheduler()
+			.enable_all()
+			.build()
+			.unwrap();
+		let node_config = testing::dev_config(&[], runtime.handle().clone());
+		let (client, _, _, _task_manager) =
+			sc_service::new_full_parts::<Block, RuntimeApi, Executor>(&node_config).unwrap();
+
+		let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+		let config = Config { addr: ([127, 0, 0, 1], port).into(), ..config() };
+		let url = format!("http://{}", config.addr);
+		runtime.spawn(run(config, client, None).map(drop));
+
+		runtime.block_on(async {
+			// A standalone node at genesis has nothing to catch up with.
+			assert_eq!(get(format!("{}/health", url)).await, (StatusCode::OK, "OK".into()));
+			assert_eq!(get(format!("{}/ready", url)).await, (StatusCode::OK, "OK".into()));
+			assert_eq!(get(format!("{}/metrics", url)).await.0, StatusCode::NOT_FOUND);
+		});
+	}
+}
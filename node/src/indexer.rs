@@ -0,0 +1,587 @@
+//! An index of the best chain in SQLite.
+//!
+//! Dashboards query the history of the chain, which the client can only answer by replaying
+//! blocks. The indexer follows the best chain and records its blocks, extrinsics and events,
+//! along with tables of balance transfers and `TemplateModule::SomethingStored` events.
+//!
+//! Every new best block is indexed along with its ancestors that are not indexed yet, since
+//! blocks imported during the major sync come without notifications. Rows of blocks that are not
+//! finalized yet are deleted when a reorg retracts their block, and all of them are rolled back
+//! on start since the node may have switched forks while stopped. Rows of finalized blocks are
+//! never touched again. The indexer stops at the first block whose state is pruned, rather than
+//! leaving a hole in the index.
+
+use std::{collections::HashMap, fmt, path::Path, sync::Arc};
+use codec::{Decode, Encode};
+use frame_system::{Phase, RawEvent as SystemEvent};
+use futures::{stream, StreamExt};
+use node_template_runtime::{
+	opaque::Block, pallet_template::RawEvent as TemplateEvent, BlockNumber, Event, Hash,
+	Header, UncheckedExtrinsic,
+};
+use pallet_balances::RawEvent as BalancesEvent;
+use rusqlite::{params, Connection, Row};
+use sc_client_api::{BlockBackend, BlockchainEvents, StorageProvider, backend::Backend};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT}};
+use crate::{describe, storage::{self, EventRecord}};
+
+const SCHEMA: &str = "
+	PRAGMA foreign_keys = ON;
+
+	CREATE TABLE IF NOT EXISTS blocks (
+		hash TEXT PRIMARY KEY,
+		number INTEGER NOT NULL,
+		parent_hash TEXT NOT NULL,
+		finalized INTEGER NOT NULL DEFAULT 0
+	);
+	CREATE INDEX IF NOT EXISTS blocks_by_number ON blocks (number);
+
+	CREATE TABLE IF NOT EXISTS extrinsics (
+		block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+		block_number INTEGER NOT NULL,
+		idx INTEGER NOT NULL,
+		signer TEXT,
+		pallet TEXT NOT NULL,
+		call TEXT NOT NULL,
+		success INTEGER,
+		PRIMARY KEY (block_hash, idx)
+	);
+
+	CREATE TABLE IF NOT EXISTS events (
+		block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+		block_number INTEGER NOT NULL,
+		idx INTEGER NOT NULL,
+		extrinsic INTEGER,
+		pallet TEXT NOT NULL,
+		name TEXT NOT NULL,
+		data TEXT NOT NULL,
+		PRIMARY KEY (block_hash, idx)
+	);
+	CREATE INDEX IF NOT EXISTS events_by_name ON events (pallet, name);
+
+	CREATE TABLE IF NOT EXISTS transfers (
+		block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+		block_number INTEGER NOT NULL,
+		event INTEGER NOT NULL,
+		sender TEXT NOT NULL,
+		recipient TEXT NOT NULL,
+		amount TEXT NOT NULL,
+		PRIMARY KEY (block_hash, event)
+	);
+	CREATE INDEX IF NOT EXISTS transfers_by_sender ON transfers (sender);
+	CREATE INDEX IF NOT EXISTS transfers_by_recipient ON transfers (recipient);
+
+	CREATE TABLE IF NOT EXISTS something_stored (
+		block_hash TEXT NOT NULL REFERENCES blocks (hash) ON DELETE CASCADE,
+		block_number INTEGER NOT NULL,
+		event INTEGER NOT NULL,
+		value INTEGER NOT NULL,
+		who TEXT NOT NULL,
+		PRIMARY KEY (block_hash, event)
+	);
+";
+
+/// An error of the indexer.
+#[derive(Debug)]
+pub enum Error {
+	/// The database failed.
+	Database(rusqlite::Error),
+	/// The client failed.
+	Client(sp_blockchain::Error),
+	/// The state of a block to index was pruned, so its events are unknown.
+	StatePruned(BlockNumber),
+}
+
+impl From<rusqlite::Error> for Error {
+	fn from(e: rusqlite::Error) -> Self {
+		Error::Database(e)
+	}
+}
+
+impl From<sp_blockchain::Error> for Error {
+	fn from(e: sp_blockchain::Error) -> Self {
+		Error::Client(e)
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Database(e) => write!(f, "Index database error: {}", e),
+			Error::Client(e) => write!(f, "Client error: {}", e),
+			Error::StatePruned(number) => write!(
+				f,
+				"The state of block #{} is pruned; run with `--pruning archive` to index it",
+				number,
+			),
+		}
+	}
+}
+
+/// A balance transfer recorded in the index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transfer {
+	/// The number of the block the transfer happened in.
+	pub block_number: BlockNumber,
+	/// The hash of the block the transfer happened in.
+	pub block_hash: String,
+	/// The account the funds were transferred from.
+	pub sender: String,
+	/// The account the funds were transferred to.
+	pub recipient: String,
+	/// The amount transferred.
+	pub amount: String,
+}
+
+/// A `TemplateModule::SomethingStored` event recorded in the index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SomethingStored {
+	/// The number of the block the value was stored in.
+	pub block_number: BlockNumber,
+	/// The hash of the block the value was stored in.
+	pub block_hash: String,
+	/// The stored value.
+	pub value: u32,
+	/// The account that stored the value.
+	pub who: String,
+}
+
+/// An event recorded in the index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedEvent {
+	/// The number of the block the event was deposited in.
+	pub block_number: BlockNumber,
+	/// The hash of the block the event was deposited in.
+	pub block_hash: String,
+	/// The index of the extrinsic that deposited the event, if any.
+	pub extrinsic: Option<u32>,
+	/// The pallet that deposited the event.
+	pub pallet: String,
+	/// The name of the event.
+	pub name: String,
+	/// The fields of the event, as a JSON array.
+	pub data: String,
+}
+
+/// The SQLite database of the index.
+pub struct Index {
+	connection: Connection,
+}
+
+impl Index {
+	/// Open the index at `path`, creating it if needed.
+	pub fn open(path: &Path) -> rusqlite::Result<Self> {
+		Self::new(Connection::open(path)?)
+	}
+
+	fn new(connection: Connection) -> rusqlite::Result<Self> {
+		connection.execute_batch(SCHEMA)?;
+		Ok(Index { connection })
+	}
+
+	/// Record a block of the best chain, unless it is already indexed.
+	fn insert(
+		&mut self,
+		header: &Header,
+		extrinsics: &[<Block as BlockT>::Extrinsic],
+		events: &[EventRecord],
+	) -> rusqlite::Result<()> {
+		let transaction = self.connection.transaction()?;
+		let hash = format!("{:?}", header.hash());
+		let number = header.number;
+
+		let inserted = transaction.execute(
+			"INSERT OR IGNORE INTO blocks (hash, number, parent_hash) VALUES (?1, ?2, ?3)",
+			params![hash, number, format!("{:?}", header.parent_hash)],
+		)?;
+		if inserted == 0 {
+			return Ok(());
+		}
+
+		let mut outcomes = HashMap::new();
+		for (idx, record) in events.iter().enumerate() {
+			let extrinsic = match record.phase {
+				Phase::ApplyExtrinsic(extrinsic) => Some(extrinsic),
+				_ => None,
+			};
+			let idx = idx as u32;
+			let description = describe::event(&record.event);
+			let data = serde_json::Value::Array(description.args).to_string();
+			transaction.execute(
+				"INSERT INTO events (block_hash, block_number, idx, extrinsic, pallet, name, data)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+				params![hash, number, idx, extrinsic, description.pallet, description.name, data],
+			)?;
+
+			match (&record.event, extrinsic) {
+				(Event::frame_system(SystemEvent::ExtrinsicSuccess(..)), Some(xt)) => {
+					outcomes.insert(xt, true);
+				},
+				(Event::frame_system(SystemEvent::ExtrinsicFailed(..)), Some(xt)) => {
+					outcomes.insert(xt, false);
+				},
+				(Event::pallet_balances(BalancesEvent::Transfer(from, to, amount)), _) => {
+					transaction.execute(
+						"INSERT INTO transfers
+						(block_hash, block_number, event, sender, recipient, amount)
+						VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+						params![
+							hash, number, idx, from.to_string(), to.to_string(), amount.to_string(),
+						],
+					)?;
+				},
+				(Event::pallet_template(TemplateEvent::SomethingStored(value, who)), _) => {
+					transaction.execute(
+						"INSERT INTO something_stored (block_hash, block_number, event, value, who)
+						VALUES (?1, ?2, ?3, ?4, ?5)",
+						params![hash, number, idx, value, who.to_string()],
+					)?;
+				},
+				_ => {},
+			}
+		}
+
+		for (idx, extrinsic) in extrinsics.iter().enumerate() {
+			let idx = idx as u32;
+			let xt = match UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..]) {
+				Ok(xt) => xt,
+				Err(e) => {
+					log::debug!(
+						target: "indexer",
+						"Unable to decode extrinsic {}-{}: {:?}", number, idx, e,
+					);
+					continue;
+				},
+			};
+			let signer = xt.signature.as_ref().map(|(address, _, _)| describe::address(address));
+			let call = describe::call(&xt.function);
+			let success = outcomes.get(&idx).copied();
+			transaction.execute(
+				"INSERT INTO extrinsics
+				(block_hash, block_number, idx, signer, pallet, call, success)
+				VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+				params![hash, number, idx, signer, call.pallet, call.name, success],
+			)?;
+		}
+
+		transaction.commit()
+	}
+
+	/// Delete the rows of a retracted block, unless it is finalized.
+	fn retract(&self, hash: &Hash) -> rusqlite::Result<()> {
+		self.connection.execute(
+			"DELETE FROM blocks WHERE hash = ?1 AND finalized = 0",
+			params![format!("{:?}", hash)],
+		).map(|_| ())
+	}
+
+	/// Delete the rows of all the blocks that are not finalized.
+	fn rollback_unfinalized(&self) -> rusqlite::Result<()> {
+		self.connection.execute("DELETE FROM blocks WHERE finalized = 0", params![]).map(|_| ())
+	}
+
+	/// Mark the blocks up to `number` as finalized.
+	fn finalize(&self, number: BlockNumber) -> rusqlite::Result<()> {
+		self.connection.execute(
+			"UPDATE blocks SET finalized = 1 WHERE finalized = 0 AND number <= ?1",
+			params![number],
+		).map(|_| ())
+	}
+
+	/// Whether the block `hash` is indexed.
+	fn contains(&self, hash: &Hash) -> rusqlite::Result<bool> {
+		self.connection.query_row(
+			"SELECT EXISTS (SELECT 1 FROM blocks WHERE hash = ?1)",
+			params![format!("{:?}", hash)],
+			|row| row.get(0),
+		)
+	}
+
+	/// The block `hash` and its ancestors that are not indexed yet, oldest first, reading the
+	/// headers of the chain with `header`.
+	fn missing<F>(&self, hash: Hash, header: F) -> Result<Vec<Hash>, Error> where
+		F: Fn(Hash) -> sp_blockchain::Result<Option<Header>>,
+	{
+		let mut missing = Vec::new();
+		let mut next = Some(hash);
+		while let Some(hash) = next {
+			if self.contains(&hash)? {
+				break;
+			}
+			let header = match header(hash)? {
+				Some(header) => header,
+				None => break,
+			};
+			next = if header.number > 0 { Some(header.parent_hash) } else { None };
+			missing.push(hash);
+		}
+		missing.reverse();
+		Ok(missing)
+	}
+
+	/// The last `limit` transfers, from or to `account` if given.
+	pub fn transfers(&self, account: Option<&str>, limit: u32) -> rusqlite::Result<Vec<Transfer>> {
+		let mut statement = self.connection.prepare(
+			"SELECT block_number, block_hash, sender, recipient, amount FROM transfers
+			WHERE ?1 IS NULL OR sender = ?1 OR recipient = ?1
+			ORDER BY block_number DESC, event DESC LIMIT ?2",
+		)?;
+		let rows = statement.query_map(params![account, limit], |row: &Row| Ok(Transfer {
+			block_number: row.get(0)?,
+			block_hash: row.get(1)?,
+			sender: row.get(2)?,
+			recipient: row.get(3)?,
+			amount: row.get(4)?,
+		}))?;
+		rows.collect()
+	}
+
+	/// The last `limit` values stored in the template pallet.
+	pub fn something_stored(&self, limit: u32) -> rusqlite::Result<Vec<SomethingStored>> {
+		let mut statement = self.connection.prepare(
+			"SELECT block_number, block_hash, value, who FROM something_stored
+			ORDER BY block_number DESC, event DESC LIMIT ?1",
+		)?;
+		let rows = statement.query_map(params![limit], |row: &Row| Ok(SomethingStored {
+			block_number: row.get(0)?,
+			block_hash: row.get(1)?,
+			value: row.get(2)?,
+			who: row.get(3)?,
+		}))?;
+		rows.collect()
+	}
+
+	/// The last `limit` events, of `pallet` if given.
+	pub fn events(&self, pallet: Option<&str>, limit: u32) -> rusqlite::Result<Vec<IndexedEvent>> {
+		let mut statement = self.connection.prepare(
+			"SELECT block_number, block_hash, extrinsic, pallet, name, data FROM events
+			WHERE ?1 IS NULL OR pallet = ?1
+			ORDER BY block_number DESC, idx DESC LIMIT ?2",
+		)?;
+		let rows = statement.query_map(params![pallet, limit], |row: &Row| Ok(IndexedEvent {
+			block_number: row.get(0)?,
+			block_hash: row.get(1)?,
+			extrinsic: row.get(2)?,
+			pallet: row.get(3)?,
+			name: row.get(4)?,
+			data: row.get(5)?,
+		}))?;
+		rows.collect()
+	}
+}
+
+/// Records the blocks of the best chain of a client.
+struct Indexer<C> {
+	client: Arc<C>,
+	index: Index,
+}
+
+impl<C> Indexer<C> {
+	fn index_block<BE>(&mut self, hash: Hash) -> Result<(), Error> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, BE>,
+	{
+		let id = BlockId::hash(hash);
+		let header = match self.client.header(id)? {
+			Some(header) => header,
+			None => return Ok(()),
+		};
+		let extrinsics = self.client.block_body(&id)?.unwrap_or_default();
+		// Indexing the block without its events would record it as empty for good.
+		let events = storage::events(&*self.client, &id)
+			.ok_or(Error::StatePruned(header.number))?;
+		Ok(self.index.insert(&header, &extrinsics, &events)?)
+	}
+
+	/// Index the block `hash` along with its ancestors that are not indexed yet.
+	///
+	/// Stops at the first block whose state is pruned, so that no block is skipped.
+	fn index_chain<BE>(&mut self, hash: Hash) -> Result<(), Error> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, BE>,
+	{
+		let client = &self.client;
+		let missing = self.index.missing(hash, |hash| client.header(BlockId::hash(hash)))?;
+		for hash in missing {
+			self.index_block(hash)?;
+		}
+		Ok(())
+	}
+
+	/// Index the best chain from the last finalized block indexed before a restart.
+	fn catch_up<BE>(&mut self) -> Result<(), Error> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, BE>,
+	{
+		self.index.rollback_unfinalized()?;
+		let info = self.client.info();
+		self.index_chain(info.best_hash)?;
+		Ok(self.index.finalize(info.finalized_number)?)
+	}
+
+	fn on_new_best<BE>(
+		&mut self,
+		hash: Hash,
+		tree_route: Option<&sp_blockchain::TreeRoute<Block>>,
+	) -> Result<(), Error> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, BE>,
+	{
+		// Without a route the new best block extends the previous one, but the blocks in between
+		// may have come without notifications.
+		for retracted in tree_route.map_or(&[][..], |tree_route| tree_route.retracted()) {
+			self.index.retract(&retracted.hash)?;
+		}
+		self.index_chain(hash)
+	}
+}
+
+enum Notification {
+	Import(sc_client_api::BlockImportNotification<Block>),
+	Finality(sc_client_api::FinalityNotification<Block>),
+}
+
+/// Follow the best and the finalized chain of `client` and record them in `index`.
+pub async fn run<C, BE>(client: Arc<C>, index: Index) where
+	BE: Backend<Block>,
+	C: BlockchainEvents<Block> + HeaderBackend<Block> + BlockBackend<Block>,
+	C: StorageProvider<Block, BE>,
+{
+	// Subscribe before catching up so that no block is missed in between.
+	let mut notifications = stream::select(
+		client.import_notification_stream().map(Notification::Import),
+		client.finality_notification_stream().map(Notification::Finality),
+	);
+	let mut indexer = Indexer { client, index };
+	if let Err(e) = indexer.catch_up() {
+		log::error!(target: "indexer", "Unable to catch up with the chain, stopping: {}", e);
+		return;
+	}
+
+	while let Some(notification) = notifications.next().await {
+		let result = match notification {
+			Notification::Import(notification) if notification.is_new_best =>
+				indexer.on_new_best(notification.hash, notification.tree_route.as_deref()),
+			Notification::Import(_) => Ok(()),
+			Notification::Finality(notification) =>
+				indexer.index.finalize(*notification.header.number()).map_err(Into::into),
+		};
+		match result {
+			// Blocks whose state is pruned cannot be indexed later on either.
+			Err(e @ Error::StatePruned(_)) => {
+				log::error!(target: "indexer", "{}, stopping", e);
+				return;
+			},
+			// Missing blocks are indexed along with the next best block.
+			Err(e) => log::warn!(target: "indexer", "{}", e),
+			Ok(()) => {},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_template_runtime::AccountId;
+	use sp_runtime::Digest;
+
+	fn header(number: BlockNumber, parent_hash: Hash) -> Header {
+		Header::new(number, Default::default(), Default::default(), parent_hash, Digest::default())
+	}
+
+	fn stored(value: u32) -> Vec<EventRecord> {
+		let who = AccountId::from([1; 32]);
+		vec![EventRecord {
+			phase: Phase::Finalization,
+			event: Event::pallet_template(TemplateEvent::SomethingStored(value, who)),
+			topics: Vec::new(),
+		}]
+	}
+
+	fn values(index: &Index) -> Vec<u32> {
+		index.something_stored(10).unwrap().into_iter().map(|stored| stored.value).collect()
+	}
+
+	/// The numbers of the indexed blocks.
+	fn numbers(index: &Index) -> Vec<BlockNumber> {
+		let mut statement = index.connection.prepare("SELECT number FROM blocks ORDER BY number")
+			.unwrap();
+		let rows = statement.query_map(params![], |row| row.get(0)).unwrap();
+		rows.collect::<rusqlite::Result<_>>().unwrap()
+	}
+
+	/// An index of blocks #1 and #2, where #1 stored 1 and #2 stored 2.
+	fn index() -> (Index, Header, Header) {
+		let mut index = Index::new(Connection::open_in_memory().unwrap()).unwrap();
+		let first = header(1, Default::default());
+		let second = header(2, first.hash());
+		index.insert(&first, &[], &stored(1)).unwrap();
+		index.insert(&second, &[], &stored(2)).unwrap();
+		(index, first, second)
+	}
+
+	#[test]
+	fn retracted_blocks_are_deleted() {
+		let (mut index, first, second) = index();
+		index.retract(&second.hash()).unwrap();
+		assert_eq!(values(&index), vec![1]);
+		assert_eq!(index.events(None, 10).unwrap().len(), 1);
+		assert_eq!(numbers(&index), vec![1]);
+
+		let mut fork = header(2, first.hash());
+		fork.extrinsics_root = Hash::repeat_byte(1);
+		index.insert(&fork, &[], &stored(3)).unwrap();
+		assert_eq!(values(&index), vec![3, 1]);
+	}
+
+	#[test]
+	fn finalized_blocks_survive_a_retract() {
+		let (index, first, second) = index();
+		index.finalize(1).unwrap();
+		index.retract(&first.hash()).unwrap();
+		index.retract(&second.hash()).unwrap();
+		assert_eq!(values(&index), vec![1]);
+		assert_eq!(numbers(&index), vec![1]);
+	}
+
+	#[test]
+	fn unfinalized_blocks_are_rolled_back_on_restart() {
+		let (index, _, _) = index();
+		index.finalize(1).unwrap();
+		index.rollback_unfinalized().unwrap();
+		assert_eq!(values(&index), vec![1]);
+		assert_eq!(index.events(Some("TemplateModule"), 10).unwrap().len(), 1);
+		assert_eq!(numbers(&index), vec![1]);
+	}
+
+	#[test]
+	fn blocks_without_notifications_are_indexed_with_the_next_one() {
+		let mut chain = vec![header(0, Default::default())];
+		for number in 1..=6 {
+			let parent = chain.last().unwrap().hash();
+			chain.push(header(number, parent));
+		}
+		let headers: HashMap<_, _> = chain.iter().map(|header| (header.hash(), header)).collect();
+		let read = |hash: Hash| -> sp_blockchain::Result<Option<Header>> {
+			Ok(headers.get(&hash).map(|header| (*header).clone()))
+		};
+		let hashes = |headers: &[Header]| headers.iter().map(|h| h.hash()).collect::<Vec<_>>();
+
+		// a fresh index misses the whole chain.
+		let mut index = Index::new(Connection::open_in_memory().unwrap()).unwrap();
+		assert_eq!(index.missing(chain[2].hash(), read).unwrap(), hashes(&chain[..=2]));
+		for header in &chain[..=2] {
+			index.insert(header, &[], &[]).unwrap();
+		}
+
+		// the notifications of #3 to #5 were skipped.
+		let missing = index.missing(chain[6].hash(), read).unwrap();
+		assert_eq!(missing, hashes(&chain[3..]));
+		for hash in missing {
+			index.insert(headers[&hash], &[], &[]).unwrap();
+		}
+		assert_eq!(numbers(&index), (0..=6).collect::<Vec<_>>());
+		assert!(index.missing(chain[6].hash(), read).unwrap().is_empty());
+	}
+}
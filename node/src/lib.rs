@@ -1,3 +1,11 @@
 pub mod chain_spec;
 pub mod service;
 pub mod rpc;
+pub mod metrics;
+pub mod health;
+pub mod storage;
+pub mod indexer;
+pub mod describe;
+pub mod snapshot;
+pub mod tx;
+pub mod testing;
@@ -7,6 +7,19 @@ mod service;
 mod cli;
 mod command;
 mod rpc;
+mod metrics;
+mod health;
+mod storage;
+mod indexer;
+mod describe;
+mod export;
+mod fork_off;
+mod snapshot;
+mod tx;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "try-runtime")]
+mod try_runtime;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
@@ -0,0 +1,406 @@
+//! Node-level Prometheus metrics.
+//!
+//! Built-in components only report on themselves. These metrics follow the chain from the point
+//! of view of this node: the blocks it authored, the slots nobody authored in, the activity of
+//! the template pallet, and the transactions its pool turned away.
+
+use std::{collections::HashMap, pin::Pin, sync::Arc};
+use futures::{Future, FutureExt, StreamExt};
+use node_template_runtime::{opaque::Block, Event};
+use pallet_rate_limit::RATE_LIMITED;
+use parity_util_mem::{MallocSizeOf, MallocSizeOfOps};
+use prometheus_endpoint::{
+	register, Counter, CounterVec, Gauge, Opts, PrometheusError, Registry, U64,
+};
+use sc_client_api::{BlockchainEvents, BlockImportNotification, StorageProvider, backend::Backend};
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_runtime::{
+	generic::BlockId,
+	traits::NumberFor,
+	transaction_validity::{InvalidTransaction, TransactionSource},
+};
+use sp_transaction_pool::{
+	ChainEvent, ImportNotificationStream, MaintainedTransactionPool, PoolFuture, PoolStatus,
+	TransactionFor, TransactionPool, TransactionStatusStreamFor, TxHash,
+	error::{Error as PoolError, IntoPoolError},
+};
+use crate::storage;
+
+/// The metrics updated by [`run`] and by the [`MeteredPool`].
+#[derive(Clone)]
+pub struct Metrics {
+	blocks_authored: Counter<U64>,
+	#[cfg(not(feature = "babe"))]
+	skipped_slots: Counter<U64>,
+	template_events: Gauge<U64>,
+	something: Gauge<U64>,
+	pool_rejects: CounterVec<U64>,
+}
+
+impl Metrics {
+	/// Create the metrics and register them in `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			blocks_authored: register(
+				Counter::new(
+					"node_template_blocks_authored_total",
+					"Number of blocks authored by this node",
+				)?,
+				registry,
+			)?,
+			#[cfg(not(feature = "babe"))]
+			skipped_slots: register(
+				Counter::new(
+					"node_template_aura_skipped_slots_total",
+					"Number of Aura slots without a block on the best chain",
+				)?,
+				registry,
+			)?,
+			template_events: register(
+				Gauge::new(
+					"node_template_template_events",
+					"Number of TemplateModule events in the best block",
+				)?,
+				registry,
+			)?,
+			something: register(
+				Gauge::new(
+					"node_template_something",
+					"Value of TemplateModule::Something in the best block",
+				)?,
+				registry,
+			)?,
+			pool_rejects: register(
+				CounterVec::new(
+					Opts::new(
+						"node_template_pool_rejects_total",
+						"Number of transactions rejected by the transaction pool, by reason",
+					),
+					&["reason"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	fn on_import<C, BE>(&self, client: &C, notification: BlockImportNotification<Block>) where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + StorageProvider<Block, BE>,
+	{
+		if notification.origin == BlockOrigin::Own {
+			self.blocks_authored.inc();
+		}
+
+		// The remaining metrics describe the chain the node follows.
+		if !notification.is_new_best {
+			return;
+		}
+
+		#[cfg(not(feature = "babe"))]
+		{
+			use sp_runtime::traits::Header as _;
+
+			let parent = client.header(BlockId::hash(*notification.header.parent_hash())).ok()
+				.flatten();
+			if let Some(skipped) = parent.and_then(|p| skipped_slots(&p, &notification.header)) {
+				self.skipped_slots.inc_by(skipped);
+			}
+		}
+
+		let at = BlockId::hash(notification.hash);
+		if let Some(events) = storage::events(client, &at) {
+			let count = events.iter()
+				.filter(|record| matches!(record.event, Event::pallet_template(_)))
+				.count();
+			self.template_events.set(count as u64);
+		}
+		let key = storage::value_key(b"TemplateModule", b"Something");
+		if let Some(something) = storage::read_value::<u32, _, _>(client, &at, key) {
+			self.something.set(something.into());
+		}
+	}
+}
+
+/// The number of slots between `header` and its `parent`.
+#[cfg(not(feature = "babe"))]
+fn skipped_slots(
+	parent: &<Block as sp_runtime::traits::Block>::Header,
+	header: &<Block as sp_runtime::traits::Block>::Header,
+) -> Option<u64> {
+	let slot = aura_slot(header)?;
+	// The genesis block has no slot, so nothing is skipped before the first block.
+	let parent_slot = aura_slot(parent)?;
+	Some(slot.saturating_sub(parent_slot).saturating_sub(1))
+}
+
+/// The Aura slot a block was authored in.
+#[cfg(not(feature = "babe"))]
+fn aura_slot(header: &<Block as sp_runtime::traits::Block>::Header) -> Option<u64> {
+	header.digest.logs().iter()
+		.find_map(|log| log.pre_runtime_try_to::<u64>(&sp_consensus_aura::AURA_ENGINE_ID))
+}
+
+/// Follow the imported blocks and update `metrics`.
+pub async fn run<C, BE>(client: Arc<C>, metrics: Metrics) where
+	BE: Backend<Block>,
+	C: BlockchainEvents<Block> + HeaderBackend<Block> + StorageProvider<Block, BE>,
+{
+	let mut imports = client.import_notification_stream();
+	while let Some(notification) = imports.next().await {
+		metrics.on_import(&*client, notification);
+	}
+}
+
+/// The label of the reason a transaction was rejected for.
+fn reject_reason(error: &PoolError) -> &'static str {
+	match error {
+		PoolError::InvalidTransaction(InvalidTransaction::Payment) => "payment",
+		PoolError::InvalidTransaction(InvalidTransaction::Stale) => "stale",
+		PoolError::InvalidTransaction(InvalidTransaction::BadProof) => "bad_proof",
+		PoolError::InvalidTransaction(InvalidTransaction::ExhaustsResources) =>
+			"exhausts_resources",
+		PoolError::InvalidTransaction(InvalidTransaction::Custom(RATE_LIMITED)) => "rate_limited",
+		PoolError::InvalidTransaction(_) => "invalid",
+		PoolError::UnknownTransaction(_) => "unknown",
+		PoolError::TemporarilyBanned => "banned",
+		PoolError::AlreadyImported(_) => "already_imported",
+		PoolError::TooLowPriority { .. } => "too_low_priority",
+		PoolError::ImmediatelyDropped => "dropped",
+		_ => "other",
+	}
+}
+
+/// Counts the rejected submissions of a [`MeteredPool`].
+#[derive(Clone)]
+struct Rejects(Option<CounterVec<U64>>);
+
+impl Rejects {
+	fn record<T, E: From<PoolError> + IntoPoolError>(&self, result: Result<T, E>) -> Result<T, E> {
+		let rejects = match &self.0 {
+			Some(rejects) => rejects,
+			None => return result,
+		};
+		result.map_err(|e| match e.into_pool_error() {
+			Ok(e) => {
+				rejects.with_label_values(&[reject_reason(&e)]).inc();
+				e.into()
+			},
+			Err(e) => {
+				rejects.with_label_values(&["other"]).inc();
+				e
+			},
+		})
+	}
+}
+
+/// A transaction pool counting the transactions it rejects on submission, by reason.
+///
+/// Transactions are submitted through the RPC and gossiped by peers, so the pool handed to the
+/// network and to the RPC is wrapped, while the block authoring reads the inner pool directly.
+pub struct MeteredPool<P> {
+	inner: Arc<P>,
+	rejects: Rejects,
+}
+
+impl<P> MeteredPool<P> {
+	/// Wrap `inner`, counting its rejects in `metrics` when they are enabled.
+	pub fn new(inner: Arc<P>, metrics: Option<&Metrics>) -> Self {
+		MeteredPool { inner, rejects: Rejects(metrics.map(|m| m.pool_rejects.clone())) }
+	}
+}
+
+impl<P: TransactionPool> TransactionPool for MeteredPool<P> {
+	type Block = P::Block;
+	type Hash = P::Hash;
+	type InPoolTransaction = P::InPoolTransaction;
+	type Error = P::Error;
+
+	fn submit_at(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+		let rejects = self.rejects.clone();
+		self.inner.submit_at(at, source, xts)
+			.map(move |results| results.map(|results| results.into_iter()
+				.map(|result| rejects.record(result))
+				.collect()
+			))
+			.boxed()
+	}
+
+	fn submit_one(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		let rejects = self.rejects.clone();
+		self.inner.submit_one(at, source, xt).map(move |result| rejects.record(result)).boxed()
+	}
+
+	fn submit_and_watch(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Box<TransactionStatusStreamFor<Self>>, Self::Error> {
+		let rejects = self.rejects.clone();
+		self.inner.submit_and_watch(at, source, xt)
+			.map(move |result| rejects.record(result))
+			.boxed()
+	}
+
+	fn ready_at(
+		&self,
+		at: NumberFor<Self::Block>,
+	) -> Pin<Box<dyn Future<
+		Output = Box<dyn Iterator<Item = Arc<Self::InPoolTransaction>> + Send>
+	> + Send>> {
+		self.inner.ready_at(at)
+	}
+
+	fn ready(&self) -> Box<dyn Iterator<Item = Arc<Self::InPoolTransaction>> + Send> {
+		self.inner.ready()
+	}
+
+	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.inner.remove_invalid(hashes)
+	}
+
+	fn status(&self) -> PoolStatus {
+		self.inner.status()
+	}
+
+	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+		self.inner.import_notification_stream()
+	}
+
+	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+		self.inner.on_broadcasted(propagations)
+	}
+
+	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+		self.inner.hash_of(xt)
+	}
+
+	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.inner.ready_transaction(hash)
+	}
+}
+
+impl<P: MaintainedTransactionPool> MaintainedTransactionPool for MeteredPool<P> {
+	fn maintain(&self, event: ChainEvent<Self::Block>) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+		self.inner.maintain(event)
+	}
+}
+
+impl<P: MallocSizeOf> MallocSizeOf for MeteredPool<P> {
+	fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+		(*self.inner).size_of(ops)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::{Decode, Encode};
+	use node_template_runtime::{Address, BalancesCall, Call, UncheckedExtrinsic};
+	use sp_keyring::AccountKeyring;
+	use sp_runtime::{generic::Era, transaction_validity::UnknownTransaction};
+	use crate::testing;
+
+	#[test]
+	fn rejects_are_labelled_by_reason() {
+		let invalid = PoolError::InvalidTransaction;
+		let reasons = vec![
+			(invalid(InvalidTransaction::Payment), "payment"),
+			(invalid(InvalidTransaction::Stale), "stale"),
+			(invalid(InvalidTransaction::BadProof), "bad_proof"),
+			(invalid(InvalidTransaction::ExhaustsResources), "exhausts_resources"),
+			(invalid(InvalidTransaction::Custom(RATE_LIMITED)), "rate_limited"),
+			(invalid(InvalidTransaction::Custom(0)), "invalid"),
+			(invalid(InvalidTransaction::Future), "invalid"),
+			(PoolError::UnknownTransaction(UnknownTransaction::NoUnsignedValidator), "unknown"),
+			(PoolError::TemporarilyBanned, "banned"),
+			(PoolError::AlreadyImported(Box::new(())), "already_imported"),
+			(PoolError::TooLowPriority { old: 1, new: 0 }, "too_low_priority"),
+			(PoolError::ImmediatelyDropped, "dropped"),
+			(PoolError::CycleDetected, "other"),
+		];
+		for (error, reason) in reasons {
+			assert_eq!(reject_reason(&error), reason, "{:?}", error);
+		}
+	}
+
+	/// A header authored in `slot`, or without a slot like the genesis block.
+	#[cfg(not(feature = "babe"))]
+	fn header(slot: Option<u64>) -> <Block as sp_runtime::traits::Block>::Header {
+		use sp_runtime::{DigestItem, traits::Header as _};
+
+		let mut header = <Block as sp_runtime::traits::Block>::Header::new(
+			1, Default::default(), Default::default(), Default::default(), Default::default(),
+		);
+		if let Some(slot) = slot {
+			header.digest.push(
+				DigestItem::PreRuntime(sp_consensus_aura::AURA_ENGINE_ID, slot.encode()),
+			);
+		}
+		header
+	}
+
+	#[test]
+	#[cfg(not(feature = "babe"))]
+	fn skipped_slots_are_those_between_a_block_and_its_parent() {
+		assert_eq!(skipped_slots(&header(Some(5)), &header(Some(6))), Some(0));
+		assert_eq!(skipped_slots(&header(Some(5)), &header(Some(9))), Some(3));
+		// nothing is skipped before the first block, nor counted for blocks without a slot.
+		assert_eq!(skipped_slots(&header(None), &header(Some(9))), None);
+		assert_eq!(skipped_slots(&header(Some(5)), &header(None)), None);
+		// a block cannot be in an earlier slot than its parent, but that must not underflow.
+		assert_eq!(skipped_slots(&header(Some(9)), &header(Some(5))), Some(0));
+	}
+
+	#[test]
+	fn metered_pool_counts_the_rejected_submissions() {
+		let runtime = tokio::runtime::Builder::new()
+			.threaded_scheduler()
+			.enable_all()
+			.build()
+			.unwrap();
+		let config = testing::dev_config(&[], runtime.handle().clone());
+		let components = crate::service::new_partial(&config).unwrap();
+		let metrics = Metrics::register(&Registry::new()).unwrap();
+		let pool = MeteredPool::new(components.transaction_pool, Some(&metrics));
+		let rejects = |reason| metrics.pool_rejects.with_label_values(&[reason]).get();
+
+		let call = Call::Balances(BalancesCall::transfer(
+			Address::Id(AccountKeyring::Bob.to_account_id()),
+			1,
+		));
+		let extra = node_template_runtime::signed_extra(Era::Immortal, 0, 0);
+		// Alice's signature does not cover this extrinsic, whatever its payload is.
+		let bad_proof = UncheckedExtrinsic::new_signed(
+			call.clone(),
+			Address::Id(AccountKeyring::Alice.to_account_id()),
+			AccountKeyring::Alice.sign(b"").into(),
+			extra,
+		);
+		// no pallet validates unsigned transfers.
+		let unsigned = UncheckedExtrinsic::new_unsigned(call);
+		let submit = |uxt: UncheckedExtrinsic| {
+			let xt = Decode::decode(&mut &uxt.encode()[..]).unwrap();
+			let submission = pool.submit_one(&BlockId::number(0), TransactionSource::External, xt);
+			futures::executor::block_on(submission)
+		};
+
+		assert!(submit(bad_proof).is_err());
+		assert!(submit(unsigned.clone()).is_err());
+		assert!(submit(unsigned).is_err());
+		assert_eq!(rejects("bad_proof"), 1);
+		assert_eq!(rejects("unknown"), 2);
+		assert_eq!(rejects("other"), 0);
+	}
+}
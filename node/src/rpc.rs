@@ -7,13 +7,21 @@
 
 use std::sync::Arc;
 
-use node_template_runtime::{opaque::Block, AccountId, Balance, Index};
+use node_template_runtime::{
+	opaque::Block, AccountId, AssetId, Balance, BlockNumber, CollectionId, EthReceipt, Hash,
+	Index, TokenId,
+};
+use sc_client_api::{
+	StorageProvider, backend::Backend,
+	light::{Fetcher, RemoteBlockchain},
+};
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
 use sp_block_builder::BlockBuilder;
 pub use sc_rpc_api::DenyUnsafe;
 use sp_transaction_pool::TransactionPool;
 
+mod light_payment;
 
 /// Full client dependencies.
 pub struct FullDeps<C, P> {
@@ -25,20 +33,44 @@ pub struct FullDeps<C, P> {
 	pub deny_unsafe: DenyUnsafe,
 }
 
+/// Light client dependencies.
+pub struct LightDeps<C, F, P> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Remote access to the blockchain (async).
+	pub remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	/// Fetcher instance.
+	pub fetcher: Arc<F>,
+}
+
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P>(
+pub fn create_full<C, P, B>(
 	deps: FullDeps<C, P>,
 ) -> jsonrpc_core::IoHandler<sc_rpc::Metadata> where
-	C: ProvideRuntimeApi<Block>,
+	B: Backend<Block> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block> + StorageProvider<Block, B>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	C::Api: pallet_assets_rpc::AssetsRuntimeApi<Block, AccountId, AssetId, Balance>,
+	C::Api: pallet_nft_rpc::NftRuntimeApi<Block, AccountId, CollectionId, TokenId>,
+	C::Api: pallet_eth_rpc::EthRuntimeApi<Block, EthReceipt>,
+	C::Api: pallet_identity_rpc::IdentityRuntimeApi<Block, AccountId>,
 	C::Api: BlockBuilder<Block>,
-	P: TransactionPool + 'static,
+	P: TransactionPool<Block = Block> + 'static,
 {
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use pallet_contracts_rpc::{Contracts, ContractsApi};
+	use pallet_assets_rpc::{Assets, AssetsApi};
+	use pallet_nft_rpc::{Nft, NftApi};
+	use pallet_eth_rpc::{Eth, EthApi};
+	use pallet_identity_rpc::{Identity, IdentityApi};
+	use pallet_template_rpc::{FullTemplate, TemplateApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
@@ -48,13 +80,37 @@ pub fn create_full<C, P>(
 	} = deps;
 
 	io.extend_with(
-		SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe))
+		SystemApi::to_delegate(FullSystem::new(client.clone(), pool.clone(), deny_unsafe))
 	);
 
 	io.extend_with(
 		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
 
+	io.extend_with(
+		ContractsApi::to_delegate(Contracts::new(client.clone()))
+	);
+
+	io.extend_with(
+		AssetsApi::to_delegate(Assets::new(client.clone()))
+	);
+
+	io.extend_with(
+		NftApi::to_delegate(Nft::new(client.clone()))
+	);
+
+	io.extend_with(
+		EthApi::to_delegate(Eth::new(client.clone(), pool))
+	);
+
+	io.extend_with(
+		IdentityApi::to_delegate(Identity::new(client.clone()))
+	);
+
+	io.extend_with(
+		TemplateApi::to_delegate(FullTemplate::new(client.clone()))
+	);
+
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
 	// to call into the runtime.
@@ -62,3 +118,47 @@ pub fn create_full<C, P>(
 
 	io
 }
+
+/// Instantiate the RPC extensions served by light clients.
+///
+/// Light clients hold no state, so every method answers from proofs fetched from full nodes
+/// through the on-demand fetcher.
+pub fn create_light<C, P, M, F>(
+	deps: LightDeps<C, F, P>,
+) -> jsonrpc_core::IoHandler<M> where
+	C: HeaderBackend<Block>,
+	C: Send + Sync + 'static,
+	F: Fetcher<Block> + Send + Sync + 'static,
+	P: TransactionPool + 'static,
+	M: jsonrpc_core::Metadata + Default,
+{
+	use substrate_frame_rpc_system::{LightSystem, SystemApi};
+	use pallet_template_rpc::{LightTemplate, TemplateApi};
+	use light_payment::{LightTransactionPayment, LightTransactionPaymentApi};
+
+	let LightDeps {
+		client,
+		pool,
+		remote_blockchain,
+		fetcher,
+	} = deps;
+	let mut io = jsonrpc_core::IoHandler::default();
+
+	io.extend_with(
+		SystemApi::<Hash, AccountId, Index>::to_delegate(
+			LightSystem::new(client.clone(), remote_blockchain.clone(), fetcher.clone(), pool)
+		)
+	);
+
+	io.extend_with(
+		LightTransactionPaymentApi::to_delegate(
+			LightTransactionPayment::new(client.clone(), remote_blockchain.clone(), fetcher.clone())
+		)
+	);
+
+	io.extend_with(
+		TemplateApi::to_delegate(LightTemplate::new(client, remote_blockchain, fetcher))
+	);
+
+	io
+}
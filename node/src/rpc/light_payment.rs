@@ -0,0 +1,97 @@
+//! `payment_queryInfo` for light clients.
+//!
+//! Light clients cannot execute runtime calls themselves, so the fee is computed by a full node
+//! through a remote call, and the result is checked against a proof of the state it was computed
+//! on.
+
+use std::sync::Arc;
+use codec::{Decode, Encode};
+use futures::{future, FutureExt, TryFutureExt};
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode};
+use jsonrpc_derive::rpc;
+use node_template_runtime::{opaque::Block, Balance, Hash};
+use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
+use sc_client_api::light::{future_header, Fetcher, RemoteBlockchain, RemoteCallRequest};
+use sp_blockchain::{Error as ClientError, HeaderBackend};
+use sp_core::Bytes;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Transaction payment RPC methods served by light clients.
+#[rpc]
+pub trait LightTransactionPaymentApi<BlockHash, ResponseType> {
+	/// The weight, dispatch class and fee of an encoded extrinsic at the given block, or at the
+	/// best block.
+	#[rpc(name = "payment_queryInfo")]
+	fn query_info(&self, encoded_xt: Bytes, at: Option<BlockHash>) -> BoxFuture<ResponseType>;
+}
+
+/// An implementation of the [`LightTransactionPaymentApi`] fetching fees from full nodes.
+pub struct LightTransactionPayment<C, F> {
+	client: Arc<C>,
+	remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	fetcher: Arc<F>,
+}
+
+impl<C, F> LightTransactionPayment<C, F> {
+	/// Create new `LightTransactionPayment` with the given references to the client, the remote
+	/// blockchain and the fetcher.
+	pub fn new(
+		client: Arc<C>,
+		remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+		fetcher: Arc<F>,
+	) -> Self {
+		LightTransactionPayment { client, remote_blockchain, fetcher }
+	}
+}
+
+fn error(message: &str, e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, F> LightTransactionPaymentApi<Hash, RuntimeDispatchInfo<Balance>>
+	for LightTransactionPayment<C, F>
+where
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + 'static,
+{
+	fn query_info(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<Hash>,
+	) -> BoxFuture<RuntimeDispatchInfo<Balance>> {
+		// If the block hash is not supplied assume the best block.
+		let hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let encoded_len = encoded_xt.len() as u32;
+		let uxt = match <Block as BlockT>::Extrinsic::decode(&mut &*encoded_xt) {
+			Ok(uxt) => uxt,
+			Err(e) => return Box::new(
+				future::err(error("Unable to query dispatch info.", e)).compat()
+			),
+		};
+		let fetcher = self.fetcher.clone();
+
+		let header = future_header(&*self.remote_blockchain, &*self.fetcher, BlockId::hash(hash))
+			.and_then(move |header| future::ready(
+				header.ok_or_else(|| ClientError::UnknownBlock(format!("{}", hash)))
+			));
+		let info = header
+			.and_then(move |header| fetcher.remote_call(RemoteCallRequest {
+				block: hash,
+				header,
+				method: "TransactionPaymentApi_query_info".into(),
+				call_data: (uxt, encoded_len).encode(),
+				retry_count: None,
+			}))
+			.and_then(|info| future::ready(
+				RuntimeDispatchInfo::<Balance>::decode(&mut &info[..])
+					.map_err(|e| ClientError::CallResultDecode("Cannot decode dispatch info", e))
+			))
+			.map_err(|e| error("Unable to query dispatch info.", e));
+
+		Box::new(info.boxed().compat())
+	}
+}
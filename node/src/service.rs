@@ -1,13 +1,19 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 use std::time::Duration;
+#[cfg(not(feature = "babe"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+use futures::FutureExt;
 use sc_client_api::{ExecutorProvider, RemoteBackend};
 use node_template_runtime::{self, opaque::Block, RuntimeApi};
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
 use sp_inherents::InherentDataProviders;
+#[cfg(not(feature = "babe"))]
+use sp_inherents::{InherentData, InherentIdentifier, ProvideInherentData};
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
+#[cfg(not(feature = "babe"))]
 use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
 use sc_finality_grandpa::{FinalityProofProvider as GrandpaFinalityProofProvider, SharedVoterState};
 
@@ -22,21 +28,48 @@ native_executor_instance!(
 type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
+type FullGrandpaBlockImport =
+	sc_finality_grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>;
+
+/// The block import of the block authoring engine, wrapping the GRANDPA block import.
+#[cfg(not(feature = "babe"))]
+type ConsensusParts =
+	sc_consensus_aura::AuraBlockImport<Block, FullClient, FullGrandpaBlockImport, AuraPair>;
+
+/// The block import of the block authoring engine, wrapping the GRANDPA block import, and the
+/// link to the BABE epoch data shared with the authoring worker.
+#[cfg(feature = "babe")]
+type ConsensusParts = (
+	sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
+	sc_consensus_babe::BabeLink<Block>,
+);
+
+/// How a full node imports and authors blocks.
+pub enum Engine {
+	/// The block authoring engine and GRANDPA.
+	Consensus(ConsensusParts, sc_finality_grandpa::LinkHalf<Block, FullClient, FullSelectChain>),
+	/// A block is authored as soon as a transaction enters the pool, without consensus and
+	/// finality.
+	#[cfg(not(feature = "babe"))]
+	InstantSeal,
+}
 
-pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponents<
+type FullComponents = sc_service::PartialComponents<
 	FullClient, FullBackend, FullSelectChain,
 	sp_consensus::DefaultImportQueue<Block, FullClient>,
 	sc_transaction_pool::FullPool<Block, FullClient>,
-	(
-		sc_consensus_aura::AuraBlockImport<
-			Block,
-			FullClient,
-			sc_finality_grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>,
-			AuraPair
-		>,
-		sc_finality_grandpa::LinkHalf<Block, FullClient, FullSelectChain>
-	)
->, ServiceError> {
+	Engine,
+>;
+
+pub fn new_partial(config: &Configuration) -> Result<FullComponents, ServiceError> {
+	new_components(config, false)
+}
+
+/// The components of a full node, importing blocks with instant seal or with consensus.
+fn new_components(
+	config: &Configuration,
+	instant_seal: bool,
+) -> Result<FullComponents, ServiceError> {
 	let inherent_data_providers = sp_inherents::InherentDataProviders::new();
 
 	let (client, backend, keystore, task_manager) =
@@ -52,55 +85,193 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		client.clone(),
 	);
 
+	#[cfg(not(feature = "babe"))]
+	if instant_seal {
+		inherent_data_providers.register_provider(InstantSealTimestamp::default())
+			.map_err(sp_consensus::Error::InherentData)?;
+		let import_queue = sc_consensus_manual_seal::import_queue(
+			Box::new(client.clone()),
+			&task_manager.spawn_handle(),
+			config.prometheus_registry(),
+		);
+		return Ok(sc_service::PartialComponents {
+			client, backend, task_manager, import_queue, keystore, select_chain, transaction_pool,
+			inherent_data_providers,
+			other: Engine::InstantSeal,
+		});
+	}
+	#[cfg(feature = "babe")]
+	if instant_seal {
+		return Err(ServiceError::Other("Instant seal is not supported with BABE".into()));
+	}
+
 	let (grandpa_block_import, grandpa_link) = sc_finality_grandpa::block_import(
 		client.clone(), &(client.clone() as Arc<_>), select_chain.clone(),
 	)?;
 
-	let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
-		grandpa_block_import.clone(), client.clone(),
-	);
+	#[cfg(not(feature = "babe"))]
+	let (import_queue, consensus) = {
+		let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
+			grandpa_block_import.clone(), client.clone(),
+		);
 
-	let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _, _>(
-		sc_consensus_aura::slot_duration(&*client)?,
-		aura_block_import.clone(),
-		Some(Box::new(grandpa_block_import.clone())),
-		None,
-		client.clone(),
-		inherent_data_providers.clone(),
-		&task_manager.spawn_handle(),
-		config.prometheus_registry(),
-		sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
-	)?;
+		let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _, _>(
+			sc_consensus_aura::slot_duration(&*client)?,
+			aura_block_import.clone(),
+			Some(Box::new(grandpa_block_import.clone())),
+			None,
+			client.clone(),
+			inherent_data_providers.clone(),
+			&task_manager.spawn_handle(),
+			config.prometheus_registry(),
+			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
+		)?;
+
+		(import_queue, aura_block_import)
+	};
+
+	#[cfg(feature = "babe")]
+	let (import_queue, consensus) = {
+		let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
+			sc_consensus_babe::Config::get_or_compute(&*client)?,
+			grandpa_block_import.clone(),
+			client.clone(),
+		)?;
+
+		let import_queue = sc_consensus_babe::import_queue(
+			babe_link.clone(),
+			babe_block_import.clone(),
+			Some(Box::new(grandpa_block_import.clone())),
+			None,
+			client.clone(),
+			select_chain.clone(),
+			inherent_data_providers.clone(),
+			&task_manager.spawn_handle(),
+			config.prometheus_registry(),
+			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
+		)?;
+
+		(import_queue, (babe_block_import, babe_link))
+	};
 
 	Ok(sc_service::PartialComponents {
 		client, backend, task_manager, import_queue, keystore, select_chain, transaction_pool,
 		inherent_data_providers,
-		other: (aura_block_import, grandpa_link),
+		other: Engine::Consensus(consensus, grandpa_link),
 	})
 }
 
+/// The node services configured on the command line besides the `Configuration`.
+#[derive(Debug, Clone, Default)]
+pub struct NodeOptions {
+	/// The health endpoints to serve, if any.
+	pub health: Option<crate::health::Config>,
+	/// The SQLite database to index the best chain into, if any.
+	pub index_db: Option<PathBuf>,
+	/// Whether to author a block as soon as a transaction enters the pool, instead of running
+	/// the block authoring engine and GRANDPA.
+	pub instant_seal: bool,
+}
+
+/// Spawn the health endpoints of a node.
+fn spawn_health<C, BE>(
+	task_manager: &TaskManager,
+	health: Option<crate::health::Config>,
+	client: Arc<C>,
+	network_status_sinks: &sc_service::NetworkStatusSinks<Block>,
+) where
+	BE: sc_client_api::Backend<Block> + 'static,
+	C: sp_blockchain::HeaderBackend<Block> + sc_client_api::StorageProvider<Block, BE>,
+	C: sc_client_api::BlockchainEvents<Block> + Send + Sync + 'static,
+{
+	if let Some(health) = health {
+		let health = crate::health::run(health, client, Some(network_status_sinks.clone()))
+			.map(|result| if let Err(e) = result {
+				log::error!("Health endpoints failed: {}", e);
+			});
+		task_manager.spawn_handle().spawn("health", health);
+	}
+}
+
+/// Spawn the indexer of the best chain of a node.
+fn spawn_indexer(
+	task_manager: &TaskManager,
+	index_db: Option<PathBuf>,
+	client: Arc<FullClient>,
+) -> Result<(), ServiceError> {
+	if let Some(path) = index_db {
+		let index = crate::indexer::Index::open(&path).map_err(|e| ServiceError::Other(
+			format!("Unable to open the index at {}: {}", path.display(), e)
+		))?;
+		task_manager.spawn_handle().spawn_blocking("indexer", crate::indexer::run(client, index));
+	}
+	Ok(())
+}
+
+/// Timestamps of instant-seal blocks.
+///
+/// The runtime allows one block per Aura slot even when blocks are not authored by Aura, so
+/// blocks authored within a slot of each other get timestamps in later slots.
+#[cfg(not(feature = "babe"))]
+#[derive(Default)]
+struct InstantSealTimestamp(parking_lot::Mutex<u64>);
+
+#[cfg(not(feature = "babe"))]
+impl ProvideInherentData for InstantSealTimestamp {
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&sp_timestamp::INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(&self, data: &mut InherentData) -> Result<(), sp_inherents::Error> {
+		let now = SystemTime::now().duration_since(UNIX_EPOCH)
+			.map_err(|_| "Current time is before the Unix epoch")?
+			.as_millis() as u64;
+		let mut last = self.0.lock();
+		*last = now.max(*last + node_template_runtime::SLOT_DURATION);
+		data.put_data(sp_timestamp::INHERENT_IDENTIFIER, &*last)
+	}
+
+	fn error_to_string(&self, error: &[u8]) -> Option<String> {
+		sp_timestamp::InherentError::try_from(&sp_timestamp::INHERENT_IDENTIFIER, error)
+			.map(|e| format!("{:?}", e))
+	}
+}
+
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_full(config: Configuration, options: NodeOptions) -> Result<TaskManager, ServiceError> {
 	let sc_service::PartialComponents {
 		client, backend, mut task_manager, import_queue, keystore, select_chain, transaction_pool,
 		inherent_data_providers,
-		other: (block_import, grandpa_link),
-	} = new_partial(&config)?;
+		other: engine,
+	} = new_components(&config, options.instant_seal)?;
+
+	let finality_proof_provider = match &engine {
+		Engine::Consensus(..) =>
+			Some(GrandpaFinalityProofProvider::new_for_service(backend.clone(), client.clone())),
+		#[cfg(not(feature = "babe"))]
+		Engine::InstantSeal => None,
+	};
 
-	let finality_proof_provider =
-		GrandpaFinalityProofProvider::new_for_service(backend.clone(), client.clone());
+	let metrics = config.prometheus_registry()
+		.map(crate::metrics::Metrics::register)
+		.transpose()?;
+
+	// Count the transactions rejected on submission, whether from the RPC or from peers.
+	let metered_pool = Arc::new(
+		crate::metrics::MeteredPool::new(transaction_pool.clone(), metrics.as_ref())
+	);
 
 	let (network, network_status_sinks, system_rpc_tx, network_starter) =
 		sc_service::build_network(sc_service::BuildNetworkParams {
 			config: &config,
 			client: client.clone(),
-			transaction_pool: transaction_pool.clone(),
+			transaction_pool: metered_pool.clone(),
 			spawn_handle: task_manager.spawn_handle(),
 			import_queue,
 			on_demand: None,
 			block_announce_validator_builder: None,
 			finality_proof_request_builder: None,
-			finality_proof_provider: Some(finality_proof_provider.clone()),
+			finality_proof_provider,
 		})?;
 
 	if config.offchain_worker.enabled {
@@ -116,9 +287,20 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 	let prometheus_registry = config.prometheus_registry().cloned();
 	let telemetry_connection_sinks = sc_service::TelemetryConnectionSinks::default();
 
+	spawn_health(&task_manager, options.health, client.clone(), &network_status_sinks);
+
+	spawn_indexer(&task_manager, options.index_db, client.clone())?;
+
+	if let Some(metrics) = metrics {
+		task_manager.spawn_handle().spawn(
+			"node-template-metrics",
+			crate::metrics::run(client.clone(), metrics),
+		);
+	}
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
-		let pool = transaction_pool.clone();
+		let pool = metered_pool.clone();
 
 		Box::new(move |deny_unsafe, _| {
 			let deps = crate::rpc::FullDeps {
@@ -136,7 +318,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 		client: client.clone(),
 		keystore: keystore.clone(),
 		task_manager: &mut task_manager,
-		transaction_pool: transaction_pool.clone(),
+		transaction_pool: metered_pool,
 		telemetry_connection_sinks: telemetry_connection_sinks.clone(),
 		rpc_extensions_builder: rpc_extensions_builder,
 		on_demand: None,
@@ -144,6 +326,37 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 		backend, network_status_sinks, system_rpc_tx, config,
 	})?;
 
+	let (consensus, grandpa_link) = match engine {
+		Engine::Consensus(consensus, grandpa_link) => (consensus, grandpa_link),
+		#[cfg(not(feature = "babe"))]
+		Engine::InstantSeal => {
+			let proposer = sc_basic_authorship::ProposerFactory::new(
+				client.clone(),
+				transaction_pool.clone(),
+				prometheus_registry.as_ref(),
+			);
+
+			let authorship = sc_consensus_manual_seal::run_instant_seal(
+				sc_consensus_manual_seal::InstantSealParams {
+					block_import: client.clone(),
+					env: proposer,
+					client,
+					pool: transaction_pool.pool().clone(),
+					select_chain,
+					consensus_data_provider: None,
+					inherent_data_providers,
+				}
+			);
+
+			// the authoring task is considered essential, i.e. if it
+			// fails we take down the service with it.
+			task_manager.spawn_essential_handle().spawn_blocking("instant-seal", authorship);
+
+			network_starter.start_network();
+			return Ok(task_manager);
+		},
+	};
+
 	if role.is_authority() {
 		let proposer = sc_basic_authorship::ProposerFactory::new(
 			client.clone(),
@@ -154,22 +367,46 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
-		let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _>(
-			sc_consensus_aura::slot_duration(&*client)?,
-			client.clone(),
-			select_chain,
-			block_import,
-			proposer,
-			network.clone(),
-			inherent_data_providers.clone(),
-			force_authoring,
-			keystore.clone(),
-			can_author_with,
-		)?;
-
-		// the AURA authoring task is considered essential, i.e. if it
-		// fails we take down the service with it.
-		task_manager.spawn_essential_handle().spawn_blocking("aura", aura);
+		#[cfg(not(feature = "babe"))]
+		{
+			let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _>(
+				sc_consensus_aura::slot_duration(&*client)?,
+				client.clone(),
+				select_chain,
+				consensus,
+				proposer,
+				network.clone(),
+				inherent_data_providers.clone(),
+				force_authoring,
+				keystore.clone(),
+				can_author_with,
+			)?;
+
+			// the AURA authoring task is considered essential, i.e. if it
+			// fails we take down the service with it.
+			task_manager.spawn_essential_handle().spawn_blocking("aura", aura);
+		}
+
+		#[cfg(feature = "babe")]
+		{
+			let (block_import, babe_link) = consensus;
+			let babe = sc_consensus_babe::start_babe(sc_consensus_babe::BabeParams {
+				keystore: keystore.clone(),
+				client: client.clone(),
+				select_chain,
+				env: proposer,
+				block_import,
+				sync_oracle: network.clone(),
+				inherent_data_providers: inherent_data_providers.clone(),
+				force_authoring,
+				babe_link,
+				can_author_with,
+			})?;
+
+			// the BABE authoring task is considered essential, i.e. if it
+			// fails we take down the service with it.
+			task_manager.spawn_essential_handle().spawn_blocking("babe-proposer", babe);
+		}
 	}
 
 	// if the node isn't actively participating in consensus then it doesn't
@@ -227,7 +464,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 }
 
 /// Builds a new service for a light client.
-pub fn new_light(config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_light(config: Configuration, options: NodeOptions) -> Result<TaskManager, ServiceError> {
 	let (client, backend, keystore, mut task_manager, on_demand) =
 		sc_service::new_light_parts::<Block, RuntimeApi, Executor>(&config)?;
 
@@ -247,6 +484,7 @@ pub fn new_light(config: Configuration) -> Result<TaskManager, ServiceError> {
 	let finality_proof_request_builder =
 		finality_proof_import.create_finality_proof_request_builder();
 
+	#[cfg(not(feature = "babe"))]
 	let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _, _>(
 		sc_consensus_aura::slot_duration(&*client)?,
 		grandpa_block_import,
@@ -259,6 +497,29 @@ pub fn new_light(config: Configuration) -> Result<TaskManager, ServiceError> {
 		sp_consensus::NeverCanAuthor,
 	)?;
 
+	#[cfg(feature = "babe")]
+	let import_queue = {
+		let select_chain = sc_consensus::LongestChain::new(backend.clone());
+		let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
+			sc_consensus_babe::Config::get_or_compute(&*client)?,
+			grandpa_block_import,
+			client.clone(),
+		)?;
+
+		sc_consensus_babe::import_queue(
+			babe_link,
+			babe_block_import,
+			None,
+			Some(Box::new(finality_proof_import)),
+			client.clone(),
+			select_chain,
+			InherentDataProviders::new(),
+			&task_manager.spawn_handle(),
+			config.prometheus_registry(),
+			sp_consensus::NeverCanAuthor,
+		)?
+	};
+
 	let finality_proof_provider =
 		GrandpaFinalityProofProvider::new_for_service(backend.clone(), client.clone());
 
@@ -281,12 +542,27 @@ pub fn new_light(config: Configuration) -> Result<TaskManager, ServiceError> {
 		);
 	}
 
+	spawn_health(&task_manager, options.health, client.clone(), &network_status_sinks);
+
+	if options.index_db.is_some() {
+		log::warn!("Light clients hold neither state nor block bodies, ignoring `--index-db`.");
+	}
+
+	let light_deps = crate::rpc::LightDeps {
+		remote_blockchain: backend.remote_blockchain(),
+		fetcher: on_demand.clone(),
+		client: client.clone(),
+		pool: transaction_pool.clone(),
+	};
+
+	let rpc_extensions = crate::rpc::create_light(light_deps);
+
 	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
 		remote_blockchain: Some(backend.remote_blockchain()),
 		transaction_pool,
 		task_manager: &mut task_manager,
 		on_demand: Some(on_demand),
-		rpc_extensions_builder: Box::new(|_, _| ()),
+		rpc_extensions_builder: Box::new(sc_service::NoopRpcExtensionBuilder(rpc_extensions)),
 		telemetry_connection_sinks: sc_service::TelemetryConnectionSinks::default(),
 		config,
 		client,
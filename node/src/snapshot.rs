@@ -0,0 +1,322 @@
+//! Snapshots of the state of a block, so that offline tools and new nodes can do without the
+//! blocks before it.
+//!
+//! A snapshot file is a magic number and a version byte, followed by the gzip-compressed SCALE
+//! encoding of [`Snapshot`]. The `snapshot create` command writes the state of a finalized block
+//! together with the headers and justifications of the chain up to it, and `snapshot restore`
+//! bootstraps an empty database from such a file, see [`restore`].
+
+use std::{fs, io::{Read, Write}, path::{Path, PathBuf}, sync::Arc};
+use codec::{Decode, Encode};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use node_template_runtime::opaque::{Block, Header};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::{BlockBackend, StorageProvider, UsageProvider, backend::Backend};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::{ChildInfo, Storage, StorageChild};
+use sp_runtime::{Justification, generic::BlockId, traits::Header as HeaderT};
+use structopt::StructOpt;
+
+pub mod restore;
+
+/// The magic number snapshot files start with.
+const MAGIC: &[u8; 4] = b"ntss";
+
+/// The version of the format of snapshot files.
+const VERSION: u8 = 2;
+
+/// Key-value pairs of a trie.
+pub type Pairs = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// The state of a block.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct Snapshot {
+	/// The header of the block.
+	pub header: Header,
+	/// The GRANDPA justification of the block, in snapshots to restore from.
+	pub justification: Option<Justification>,
+	/// The headers from block #1 to the parent of the block, with the justifications the
+	/// database has for them, in snapshots to restore from.
+	pub ancestors: Vec<(Header, Option<Justification>)>,
+	/// The pairs of the main trie.
+	pub top: Pairs,
+	/// The pairs of the default child tries, by storage key.
+	pub children: Vec<(Vec<u8>, Pairs)>,
+}
+
+impl Snapshot {
+	/// The snapshot of block `at` in the database.
+	pub fn at<C, BE>(client: Arc<C>, at: &BlockId<Block>) -> sc_cli::Result<Self> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + UsageProvider<Block> + StorageProvider<Block, BE>,
+	{
+		let header = client.header(*at)?.ok_or_else(|| format!("Unknown block {:?}", at))?;
+		let storage = sc_service::chain_ops::export_raw_state(client, at)?;
+		Ok(Self::new(header, storage))
+	}
+
+	/// The snapshot of block `at` in the database, with the chain up to it.
+	pub fn with_ancestry<C, BE>(client: Arc<C>, at: &BlockId<Block>) -> sc_cli::Result<Self> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + BlockBackend<Block> + UsageProvider<Block>
+			+ StorageProvider<Block, BE>,
+	{
+		let mut snapshot = Self::at(client.clone(), at)?;
+		let number = *snapshot.header.number();
+		snapshot.justification = Some(client.justification(at)?.ok_or_else(|| format!(
+			"Block #{} has no GRANDPA justification; pick the last block of an authority set, \
+			or a multiple of the justification period",
+			number,
+		))?);
+
+		for ancestor in 1..number {
+			let id = BlockId::Number(ancestor);
+			let header = client.header(id)?
+				.ok_or_else(|| format!("Block #{} is not in the database", ancestor))?;
+			snapshot.ancestors.push((header, client.justification(&id)?));
+		}
+		Ok(snapshot)
+	}
+
+	/// The snapshot of `storage`, the state of the block of `header`.
+	pub fn new(header: Header, storage: Storage) -> Self {
+		let children = storage.children_default.into_iter()
+			.map(|(key, child)| (key, child.data.into_iter().collect()))
+			.collect();
+		Snapshot {
+			header,
+			justification: None,
+			ancestors: Vec::new(),
+			top: storage.top.into_iter().collect(),
+			children,
+		}
+	}
+
+	/// The state in the snapshot.
+	pub fn into_storage(self) -> Storage {
+		let children_default = self.children.into_iter()
+			.map(|(key, data)| {
+				let child_info = ChildInfo::new_default(&key);
+				(key, StorageChild { data: data.into_iter().collect(), child_info })
+			})
+			.collect();
+		Storage { top: self.top.into_iter().collect(), children_default }
+	}
+
+	/// Read the snapshot file at `path`.
+	pub fn read(path: &Path) -> Result<Self, String> {
+		let bytes = fs::read(path)
+			.map_err(|e| format!("Unable to read snapshot {}: {}", path.display(), e))?;
+		Self::from_bytes(&bytes).map_err(|e| format!("Invalid snapshot {}: {}", path.display(), e))
+	}
+
+	/// Write the snapshot to a file at `path`.
+	pub fn write(&self, path: &Path) -> Result<(), String> {
+		self.to_bytes()
+			.and_then(|bytes| fs::write(path, bytes))
+			.map_err(|e| format!("Unable to write snapshot {}: {}", path.display(), e))
+	}
+
+	fn to_bytes(&self) -> std::io::Result<Vec<u8>> {
+		let mut bytes = MAGIC.to_vec();
+		bytes.push(VERSION);
+		let mut encoder = GzEncoder::new(bytes, Compression::default());
+		encoder.write_all(&self.encode())?;
+		encoder.finish()
+	}
+
+	fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+		if !bytes.starts_with(MAGIC) {
+			return Err("not a snapshot".into());
+		}
+		match bytes.get(MAGIC.len()) {
+			Some(&VERSION) => {},
+			Some(version) => return Err(format!("unsupported version {}", version)),
+			None => return Err("truncated".into()),
+		}
+		let mut encoded = Vec::new();
+		GzDecoder::new(&bytes[MAGIC.len() + 1..])
+			.read_to_end(&mut encoded)
+			.map_err(|e| e.to_string())?;
+		Self::decode(&mut &encoded[..]).map_err(|e| e.to_string())
+	}
+}
+
+/// The `snapshot` commands.
+#[derive(Debug, StructOpt)]
+pub enum SnapshotCmd {
+	/// Write the state of a finalized block, with the headers and justifications of the chain up
+	/// to it, to a file.
+	Create(CreateCmd),
+
+	/// Bootstrap an empty database from a snapshot file.
+	Restore(RestoreCmd),
+}
+
+/// The `snapshot create` command.
+#[derive(Debug, StructOpt)]
+pub struct CreateCmd {
+	/// The snapshot file to write.
+	#[structopt(parse(from_os_str))]
+	pub output: PathBuf,
+
+	/// Block hash or number of a finalized block with a GRANDPA justification.
+	///
+	/// Default is the last such block.
+	#[structopt(long = "at", value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl CreateCmd {
+	/// Run the `snapshot create` command.
+	pub async fn run<C, BE>(&self, client: Arc<C>) -> sc_cli::Result<()> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + BlockBackend<Block> + UsageProvider<Block>
+			+ StorageProvider<Block, BE>,
+	{
+		let info = client.info();
+		let block_id = match &self.at {
+			Some(at) => {
+				let hash = client.expect_block_hash_from_id(&at.parse()?)?;
+				let number = client.number(hash)?.unwrap_or_default();
+				if number > info.finalized_number || client.hash(number)? != Some(hash) {
+					return Err(format!("Block {:?} is not finalized", hash).into());
+				}
+				BlockId::Hash(hash)
+			},
+			None => {
+				let mut number = info.finalized_number;
+				while number > 0 && client.justification(&BlockId::Number(number))?.is_none() {
+					number -= 1;
+				}
+				if number == 0 {
+					return Err("No finalized block has a GRANDPA justification yet".into());
+				}
+				BlockId::Number(number)
+			},
+		};
+
+		let snapshot = Snapshot::with_ancestry(client, &block_id)?;
+		snapshot.write(&self.output)?;
+		log::info!(
+			"Wrote the snapshot of block #{} ({}) to {}",
+			snapshot.header.number(),
+			snapshot.header.hash(),
+			self.output.display(),
+		);
+		Ok(())
+	}
+}
+
+/// The `snapshot restore` command.
+#[derive(Debug, StructOpt)]
+pub struct RestoreCmd {
+	/// The snapshot file to read.
+	#[structopt(parse(from_os_str))]
+	pub input: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl RestoreCmd {
+	/// Run the `snapshot restore` command.
+	pub async fn run<C, BE>(&self, client: Arc<C>, backend: Arc<BE>) -> sc_cli::Result<()> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + StorageProvider<Block, BE>,
+	{
+		let snapshot = Snapshot::read(&self.input)?;
+		let number = *snapshot.header.number();
+		let hash = snapshot.header.hash();
+		restore::restore(&*client, &*backend, snapshot)?;
+		log::info!("Restored the database at block #{} ({})", number, hash);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for CreateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+impl CliConfiguration for RestoreCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::storage::well_known_keys;
+	use sp_runtime::Digest;
+
+	#[test]
+	fn snapshots_round_trip() {
+		let header = Header::new(
+			42,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Digest::default(),
+		);
+		let mut storage = Storage::default();
+		storage.top.insert(well_known_keys::CODE.to_vec(), vec![1, 2, 3]);
+		let child_info = ChildInfo::new_default(b"child");
+		storage.children_default.insert(b"child".to_vec(), StorageChild {
+			data: vec![(vec![4], vec![5])].into_iter().collect(),
+			child_info,
+		});
+
+		let mut snapshot = Snapshot::new(header.clone(), storage.clone());
+		snapshot.justification = Some(vec![6]);
+		snapshot.ancestors.push((header, None));
+		let read = Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+		assert_eq!(read, snapshot);
+		assert_eq!(read.into_storage(), storage);
+	}
+
+	#[test]
+	fn other_files_are_rejected() {
+		assert_eq!(Snapshot::from_bytes(b"{}"), Err("not a snapshot".into()));
+		assert_eq!(Snapshot::from_bytes(b"ntss\x07"), Err("unsupported version 7".into()));
+	}
+}
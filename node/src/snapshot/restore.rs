@@ -0,0 +1,554 @@
+//! Bootstrapping an empty database from a snapshot.
+//!
+//! The headers of the snapshot must descend from the genesis of the chain spec. The GRANDPA
+//! authority set is followed from genesis through the changes the headers signal, each standard
+//! change being enacted only with a justification of its last block signed by the outgoing set,
+//! and each forced change at the block it names, and the justification of the snapshot block must
+//! be signed by the set of its time. The header is thus the one the chain finalized, and the state
+//! is checked against its state root.
+//!
+//! Substrate cannot import a state on its own, so the headers and the state are written with the
+//! block import operations of the backend, like the genesis block is. The ancestors of the
+//! snapshot block have no state, like the blocks of a pruned database. GRANDPA keeps its
+//! authority set and the state of its voter in the auxiliary data of the database, which is
+//! seeded with the current set and a round completed at the snapshot block.
+
+use std::collections::{BTreeMap, HashMap};
+use codec::{Decode, Encode};
+use finality_grandpa::{Commit, Message, round::State as RoundState};
+use node_template_runtime::{BlockNumber, Hash, opaque::{Block, Header}};
+use sc_client_api::{
+	StorageProvider,
+	backend::{Backend, BlockImportOperation, NewBlockState},
+};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::{Storage, StorageKey};
+use sp_finality_grandpa::{
+	AuthorityId, AuthorityList, AuthoritySignature, ConsensusLog, SetId, VersionedAuthorityList,
+	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID,
+};
+use sp_runtime::{generic::{BlockId, OpaqueDigestItemId}, traits::Header as HeaderT};
+use super::Snapshot;
+
+/// The auxiliary key of the version of the GRANDPA data.
+const GRANDPA_VERSION_KEY: &[u8] = b"grandpa_schema_version";
+
+/// The auxiliary key of the GRANDPA authority set.
+const GRANDPA_AUTHORITY_SET_KEY: &[u8] = b"grandpa_voters";
+
+/// The auxiliary key of the state of the GRANDPA voter.
+const GRANDPA_SET_STATE_KEY: &[u8] = b"grandpa_completed_round";
+
+/// The version of the GRANDPA data that [`PersistedAuthoritySet`] and [`VoterSetState`] follow, the
+/// one of `sc-finality-grandpa` 0.8.0, which the node pins.
+const GRANDPA_SCHEMA_VERSION: u32 = 2;
+
+/// The `GrandpaJustification` of `sc-finality-grandpa`, whose fields are private.
+#[derive(Encode, Decode)]
+struct GrandpaJustification {
+	round: u64,
+	commit: Commit<Hash, BlockNumber, AuthoritySignature, AuthorityId>,
+	votes_ancestries: Vec<Header>,
+}
+
+/// The `AuthoritySet` of `sc-finality-grandpa` as version 2 of its auxiliary data stores it,
+/// without pending changes.
+#[derive(Encode)]
+struct PersistedAuthoritySet {
+	current_authorities: AuthorityList,
+	set_id: SetId,
+	/// The roots of the fork tree of pending standard changes, and its best finalized number.
+	pending_standard_changes: (Vec<()>, Option<BlockNumber>),
+	pending_forced_changes: Vec<()>,
+}
+
+/// The `CompletedRound` of `sc-finality-grandpa`, without votes.
+#[derive(Encode)]
+struct CompletedRound {
+	number: u64,
+	state: RoundState<Hash, BlockNumber>,
+	base: (Hash, BlockNumber),
+	votes: Vec<()>,
+}
+
+/// The `HasVoted` of `sc-finality-grandpa`, for a round not voted in yet.
+#[derive(Encode)]
+enum HasVoted {
+	No,
+}
+
+/// The `VoterSetState` of `sc-finality-grandpa` as a new set starts it: live, with the round 0
+/// completed at the base block of the set and the round 1 not voted in yet.
+#[derive(Encode)]
+enum VoterSetState {
+	Live {
+		/// The completed rounds, the ID of the set and its voters.
+		completed_rounds: (Vec<CompletedRound>, SetId, Vec<AuthorityId>),
+		current_rounds: BTreeMap<u64, HasVoted>,
+	},
+}
+
+impl VoterSetState {
+	fn live(set_id: SetId, authorities: &AuthorityList, base: (Hash, BlockNumber)) -> Self {
+		let round = CompletedRound {
+			number: 0,
+			state: RoundState::genesis(base),
+			base,
+			votes: Vec::new(),
+		};
+		let voters = authorities.iter().map(|(id, _)| id.clone()).collect();
+		let mut current_rounds = BTreeMap::new();
+		current_rounds.insert(1, HasVoted::No);
+		VoterSetState::Live { completed_rounds: (vec![round], set_id, voters), current_rounds }
+	}
+}
+
+/// The GRANDPA authority set along a chain of headers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthoritySet {
+	/// The authorities of the set.
+	pub authorities: AuthorityList,
+	/// The ID of the set.
+	pub set_id: SetId,
+	/// The standard changes signalled so far, by the block they take effect after.
+	pending: Vec<(BlockNumber, AuthorityList)>,
+	/// The forced change signalled so far, by the block it takes effect at, with the median of the
+	/// last blocks the voters finalized when it was signalled. There is at most one, like in
+	/// `sc-finality-grandpa`, whose `pending_forced_changes` it follows.
+	pending_forced: Option<(BlockNumber, BlockNumber, AuthorityList)>,
+}
+
+impl AuthoritySet {
+	/// The set of the genesis block.
+	pub fn genesis(authorities: AuthorityList) -> Self {
+		AuthoritySet { authorities, set_id: 0, pending: Vec::new(), pending_forced: None }
+	}
+
+	/// Import `header` with its `justification`, if any: see [`signal`](Self::signal) and
+	/// [`finalize`](Self::finalize).
+	pub fn import(&mut self, header: &Header, justification: Option<&[u8]>) -> Result<(), String> {
+		self.signal(header)?;
+		self.finalize(header, justification)
+	}
+
+	/// Note the changes `header` signals, and enact the forced change due at its block, as
+	/// importing the block does.
+	///
+	/// A forced change takes over from a set which stopped finalizing, without a justification:
+	/// the new set finalizes the blocks after the median of the last ones the voters finalized,
+	/// and the standard changes pending after it are dropped. Those due by the median must have
+	/// been enacted, since the old set finalized it.
+	pub fn signal(&mut self, header: &Header) -> Result<(), String> {
+		let number = *header.number();
+		let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+		for log in header.digest().logs() {
+			match log.try_to::<ConsensusLog<BlockNumber>>(id) {
+				Some(ConsensusLog::ScheduledChange(change)) =>
+					self.pending.push((number + change.delay, change.next_authorities)),
+				Some(ConsensusLog::ForcedChange(median, change)) => {
+					if self.pending_forced.is_some() {
+						return Err(format!(
+							"Block #{} forces a GRANDPA authority set change while another one \
+							is pending",
+							number,
+						));
+					}
+					let at = number + change.delay;
+					self.pending_forced = Some((at, median, change.next_authorities));
+				},
+				_ => {},
+			}
+		}
+		self.pending.sort_by_key(|(at, _)| *at);
+
+		match self.pending_forced.take() {
+			Some((at, median, authorities)) if at == number => {
+				if let Some((due, _)) = self.pending.first().filter(|(due, _)| *due <= median) {
+					return Err(format!(
+						"Block #{} forces a GRANDPA authority set change after block #{}, before \
+						which the change of block #{} is not enacted",
+						number,
+						median,
+						due,
+					));
+				}
+				self.pending.clear();
+				self.authorities = authorities;
+				self.set_id += 1;
+			},
+			pending_forced => self.pending_forced = pending_forced,
+		}
+		Ok(())
+	}
+
+	/// Enact the standard changes due at the block of `header`, which is then finalized by the
+	/// outgoing set. That set must have signed its `justification`, as the headers alone could
+	/// otherwise hand the chain over to any set; without one, the changes stay pending, until a
+	/// later block is finalized or a forced change replaces them.
+	pub fn finalize(
+		&mut self,
+		header: &Header,
+		justification: Option<&[u8]>,
+	) -> Result<(), String> {
+		let (hash, number) = (header.hash(), *header.number());
+		let due = self.pending.iter().take_while(|(at, _)| *at <= number).count();
+		let justification = match justification {
+			Some(justification) if due > 0 => justification,
+			_ => return Ok(()),
+		};
+		self.verify(justification, (hash, number)).map_err(|e| format!(
+			"Block #{} enacts a GRANDPA authority set change: {}",
+			number,
+			e,
+		))?;
+		for (_, authorities) in self.pending.drain(..due) {
+			self.authorities = authorities;
+			self.set_id += 1;
+		}
+		Ok(())
+	}
+
+	/// Check that `justification` proves the finality of `target`.
+	pub fn verify(&self, justification: &[u8], target: (Hash, BlockNumber)) -> Result<(), String> {
+		let justification = GrandpaJustification::decode(&mut &justification[..])
+			.map_err(|e| format!("Invalid justification: {}", e))?;
+		let commit = &justification.commit;
+		if (commit.target_hash, commit.target_number) != target {
+			return Err("The justification is for another block".into());
+		}
+
+		let ancestry: HashMap<_, _> = justification.votes_ancestries.iter()
+			.map(|header| (header.hash(), *header.parent_hash()))
+			.collect();
+		let mut signers = Vec::new();
+		let mut weight = 0;
+		for signed in &commit.precommits {
+			let voter_weight = self.authorities.iter()
+				.find(|(id, _)| *id == signed.id)
+				.map(|(_, weight)| *weight)
+				.ok_or_else(|| format!("{:?} is not a GRANDPA authority", signed.id))?;
+			if signers.contains(&signed.id) {
+				return Err(format!("{:?} precommitted twice", signed.id));
+			}
+			let message = Message::Precommit(signed.precommit.clone());
+			let round = justification.round;
+			if !sp_finality_grandpa::check_message_signature(
+				&message,
+				&signed.id,
+				&signed.signature,
+				round,
+				self.set_id,
+			) {
+				return Err(format!("Invalid precommit signature of {:?}", signed.id));
+			}
+
+			// Precommits are for the target or for its descendants.
+			let mut hash = signed.precommit.target_hash;
+			for _ in 0..=ancestry.len() {
+				if hash == commit.target_hash {
+					break;
+				}
+				hash = *ancestry.get(&hash)
+					.ok_or("A precommit is not for a descendant of the target")?;
+			}
+			if hash != commit.target_hash {
+				return Err("The ancestry of the precommits is cyclic".into());
+			}
+
+			signers.push(signed.id.clone());
+			weight += voter_weight;
+		}
+
+		// As in `finality-grandpa`, more than two thirds of the weight are needed.
+		let total: u64 = self.authorities.iter().map(|(_, weight)| weight).sum();
+		let threshold = total - total.saturating_sub(1) / 3;
+		if weight < threshold {
+			return Err(format!("The precommits weigh {} of the {} needed", weight, threshold));
+		}
+		Ok(())
+	}
+}
+
+/// Check that `header` is the child of `parent`.
+fn check_parent(header: &Header, parent: (Hash, BlockNumber)) -> Result<(), String> {
+	if (*header.parent_hash(), *header.number()) != (parent.0, parent.1 + 1) {
+		return Err(format!(
+			"Block #{} of the snapshot is not the child of block #{} ({:?})",
+			header.number(),
+			parent.1,
+			parent.0,
+		));
+	}
+	Ok(())
+}
+
+/// The GRANDPA authorities at genesis.
+fn genesis_authorities<C, BE>(client: &C, genesis_hash: Hash) -> Result<AuthorityList, String> where
+	BE: Backend<Block>,
+	C: StorageProvider<Block, BE>,
+{
+	let key = StorageKey(GRANDPA_AUTHORITIES_KEY.to_vec());
+	let data = client.storage(&BlockId::Hash(genesis_hash), &key)
+		.map_err(|e| e.to_string())?
+		.ok_or("The genesis has no GRANDPA authorities")?;
+	VersionedAuthorityList::decode(&mut &data.0[..])
+		.map(Into::into)
+		.map_err(|e| format!("Invalid GRANDPA authorities at genesis: {}", e))
+}
+
+/// Write the chain and the state of `snapshot` to the empty database of `backend`.
+pub fn restore<C, BE>(client: &C, backend: &BE, snapshot: Snapshot) -> Result<(), String> where
+	BE: Backend<Block>,
+	C: HeaderBackend<Block> + StorageProvider<Block, BE>,
+{
+	if cfg!(feature = "babe") {
+		return Err("Snapshots cannot be restored with BABE, whose epochs start at genesis".into());
+	}
+	let info = client.info();
+	if info.best_number != 0 {
+		return Err("The database is not empty, purge it first with `purge-chain`".into());
+	}
+
+	let Snapshot { header, justification, ancestors, top, children } = snapshot;
+	let justification = justification
+		.ok_or("The snapshot has no justification, write it with `snapshot create`")?;
+	let mut set = AuthoritySet::genesis(genesis_authorities(client, info.genesis_hash)?);
+	let mut parent = (info.genesis_hash, 0);
+	for (ancestor, ancestor_justification) in &ancestors {
+		check_parent(ancestor, parent)?;
+		set.import(ancestor, ancestor_justification.as_deref())?;
+		parent = (ancestor.hash(), *ancestor.number());
+	}
+	check_parent(&header, parent)?;
+	let (hash, number) = (header.hash(), *header.number());
+	// The snapshot block is finalized by the set in place once a forced change is enacted.
+	set.signal(&header)?;
+	set.verify(&justification, (hash, number))?;
+	set.finalize(&header, Some(&justification))?;
+	if !set.pending.is_empty() || set.pending_forced.is_some() {
+		return Err(format!(
+			"A GRANDPA authority set change is pending at block #{}, restore a later snapshot",
+			number,
+		));
+	}
+
+	let error = |e: sp_blockchain::Error| e.to_string();
+	let mut operation = backend.begin_operation().map_err(error)?;
+	let storage = Snapshot::new(header.clone(), Storage::default());
+	let storage = Snapshot { top, children, ..storage }.into_storage();
+	let state_root = operation.reset_storage(storage).map_err(error)?;
+	if state_root != *header.state_root() {
+		return Err(format!(
+			"The state root of the snapshot is {:?}, but the one of block #{} is {:?}",
+			state_root,
+			number,
+			header.state_root(),
+		));
+	}
+
+	for (ancestor, ancestor_justification) in ancestors {
+		let mut ancestor_operation = backend.begin_operation().map_err(error)?;
+		// The state database tracks every block, and an empty state has no trie nodes to prune.
+		ancestor_operation.reset_storage(Storage::default()).map_err(error)?;
+		ancestor_operation
+			.set_block_data(ancestor, None, ancestor_justification, NewBlockState::Final)
+			.map_err(error)?;
+		backend.commit_operation(ancestor_operation).map_err(error)?;
+	}
+
+	let voter_set_state = VoterSetState::live(set.set_id, &set.authorities, (hash, number));
+	let authority_set = PersistedAuthoritySet {
+		current_authorities: set.authorities,
+		set_id: set.set_id,
+		pending_standard_changes: (Vec::new(), None),
+		pending_forced_changes: Vec::new(),
+	};
+	operation.set_block_data(header, None, Some(justification), NewBlockState::Final)
+		.map_err(error)?;
+	operation.insert_aux(vec![
+		(GRANDPA_VERSION_KEY.to_vec(), Some(GRANDPA_SCHEMA_VERSION.encode())),
+		(GRANDPA_AUTHORITY_SET_KEY.to_vec(), Some(authority_set.encode())),
+		(GRANDPA_SET_STATE_KEY.to_vec(), Some(voter_set_state.encode())),
+	]).map_err(error)?;
+	backend.commit_operation(operation).map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use finality_grandpa::{Precommit, SignedPrecommit};
+	use sp_core::{ed25519, Pair};
+	use sp_finality_grandpa::ScheduledChange;
+	use sp_runtime::{Digest, DigestItem};
+
+	fn pair(name: &str) -> ed25519::Pair {
+		ed25519::Pair::from_string(&format!("//{}", name), None).unwrap()
+	}
+
+	fn authorities(names: &[&str]) -> AuthorityList {
+		names.iter().map(|name| (pair(name).public().into(), 1)).collect()
+	}
+
+	fn header(number: BlockNumber, logs: Vec<DigestItem<Hash>>) -> Header {
+		let digest = Digest { logs };
+		Header::new(number, Default::default(), Default::default(), Default::default(), digest)
+	}
+
+	fn justification(
+		signers: &[&str],
+		target: (Hash, BlockNumber),
+		set_id: SetId,
+	) -> Vec<u8> {
+		let round = 7;
+		let precommit = Precommit { target_hash: target.0, target_number: target.1 };
+		let message = Message::Precommit(precommit.clone());
+		let payload = sp_finality_grandpa::localized_payload(round, set_id, &message);
+		let precommits = signers.iter()
+			.map(|name| SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: pair(name).sign(&payload).into(),
+				id: pair(name).public().into(),
+			})
+			.collect();
+		let commit = Commit { target_hash: target.0, target_number: target.1, precommits };
+		GrandpaJustification { round, commit, votes_ancestries: Vec::new() }.encode()
+	}
+
+	#[test]
+	fn justifications_need_two_thirds_of_the_set() {
+		let set = AuthoritySet::genesis(authorities(&["Alice", "Bob", "Charlie", "Dave"]));
+		let target = (Hash::repeat_byte(1), 10);
+		let verify = |signers: &[&str], signed: (Hash, BlockNumber), set_id| {
+			set.verify(&justification(signers, signed, set_id), target)
+		};
+
+		assert_eq!(verify(&["Alice", "Bob", "Charlie"], target, 0), Ok(()));
+		assert!(verify(&["Alice", "Bob"], target, 0).is_err());
+		assert!(verify(&["Alice", "Bob", "Eve"], target, 0).is_err());
+		assert!(verify(&["Alice", "Alice", "Bob"], target, 0).is_err());
+		// Signed for another set or another block.
+		assert!(verify(&["Alice", "Bob", "Charlie"], target, 1).is_err());
+		assert!(verify(&["Alice", "Bob", "Charlie"], (Hash::repeat_byte(2), 10), 0).is_err());
+	}
+
+	#[test]
+	fn scheduled_changes_are_enacted_after_their_delay() {
+		let mut set = AuthoritySet::genesis(authorities(&["Alice"]));
+		let change = ConsensusLog::ScheduledChange(ScheduledChange {
+			next_authorities: authorities(&["Bob"]),
+			delay: 2,
+		});
+		let log = DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode());
+
+		set.import(&header(5, vec![log]), None).unwrap();
+		set.import(&header(6, vec![]), None).unwrap();
+		assert_eq!((set.authorities.clone(), set.set_id), (authorities(&["Alice"]), 0));
+		let last = header(7, vec![]);
+		let signed = justification(&["Alice"], (last.hash(), 7), 0);
+		set.import(&last, Some(&signed)).unwrap();
+		assert_eq!((set.authorities.clone(), set.set_id), (authorities(&["Bob"]), 1));
+		assert!(set.pending.is_empty());
+	}
+
+	#[test]
+	fn forged_set_changes_are_rejected() {
+		let genesis = AuthoritySet::genesis(authorities(&["Alice"]));
+		let change = ConsensusLog::ScheduledChange(ScheduledChange {
+			next_authorities: authorities(&["Eve"]),
+			delay: 0,
+		});
+		let forged = header(1, vec![DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode())]);
+		let target = (forged.hash(), 1);
+		let import = |justification: Option<&[u8]>| {
+			let mut set = genesis.clone();
+			set.import(&forged, justification).map(|()| set)
+		};
+
+		// Without a justification, the change waits for one.
+		let set = import(None).unwrap();
+		assert_eq!((set.authorities, set.set_id), (authorities(&["Alice"]), 0));
+		assert_eq!(set.pending.len(), 1);
+		assert!(import(Some(&justification(&["Eve"], target, 0))).is_err());
+		assert!(import(Some(&justification(&["Eve"], target, 1))).is_err());
+		let set = import(Some(&justification(&["Alice"], target, 0))).unwrap();
+		assert_eq!((set.authorities, set.set_id), (authorities(&["Eve"]), 1));
+	}
+
+	#[test]
+	fn forced_changes_are_enacted_after_their_delay_without_justification() {
+		let mut set = AuthoritySet::genesis(authorities(&["Alice"]));
+		let scheduled = |delay| DigestItem::Consensus(
+			GRANDPA_ENGINE_ID,
+			ConsensusLog::ScheduledChange(ScheduledChange {
+				next_authorities: authorities(&["Bob"]),
+				delay,
+			}).encode(),
+		);
+		let forced = |median| DigestItem::Consensus(
+			GRANDPA_ENGINE_ID,
+			ConsensusLog::ForcedChange(median, ScheduledChange {
+				next_authorities: authorities(&["Charlie"]),
+				delay: 2,
+			}).encode(),
+		);
+
+		// Alice stops finalizing before the standard change of block 3 is enacted.
+		set.import(&header(3, vec![scheduled(1)]), None).unwrap();
+		set.import(&header(4, vec![]), None).unwrap();
+		let stalled = set.clone();
+		set.import(&header(5, vec![forced(3)]), None).unwrap();
+		assert!(set.clone().import(&header(6, vec![forced(3)]), None).is_err());
+		set.import(&header(6, vec![]), None).unwrap();
+		assert_eq!((set.authorities.clone(), set.set_id), (authorities(&["Alice"]), 0));
+		set.import(&header(7, vec![]), None).unwrap();
+		assert_eq!((set.authorities.clone(), set.set_id), (authorities(&["Charlie"]), 1));
+		assert!(set.pending.is_empty() && set.pending_forced.is_none());
+
+		// The median is past the block enacting the standard change, which Alice finalized.
+		let mut set = stalled;
+		set.import(&header(5, vec![forced(4)]), None).unwrap();
+		set.import(&header(6, vec![]), None).unwrap();
+		assert!(set.import(&header(7, vec![]), None).is_err());
+	}
+
+	#[cfg(not(feature = "babe"))]
+	#[test]
+	fn restored_grandpa_data_is_loaded_by_sc_finality_grandpa() {
+		let runtime = tokio::runtime::Builder::new()
+			.threaded_scheduler()
+			.enable_all()
+			.build()
+			.unwrap();
+		let config = crate::testing::dev_config(&[], runtime.handle().clone());
+		let components = crate::service::new_partial(&config).unwrap();
+		let (client, backend) = (components.client, components.backend);
+
+		// Block #1 hands the chain over from Alice to Bob, and keeps the state of genesis.
+		let genesis = client.header(BlockId::Number(0)).unwrap().unwrap();
+		let change = ConsensusLog::ScheduledChange(ScheduledChange {
+			next_authorities: authorities(&["Bob"]),
+			delay: 0,
+		});
+		let logs = vec![DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode())];
+		let header = Header::new(
+			1,
+			Default::default(),
+			*genesis.state_root(),
+			genesis.hash(),
+			Digest { logs },
+		);
+		let storage = sc_service::chain_ops::export_raw_state(client.clone(), &BlockId::Number(0))
+			.unwrap();
+		let mut snapshot = Snapshot::new(header.clone(), storage);
+		snapshot.justification = Some(justification(&["Alice"], (header.hash(), 1), 0));
+		restore(&*client, &*backend, snapshot).unwrap();
+
+		// Loading fails on data it cannot decode, and falls back to the genesis set without any.
+		let (_, link) = sc_finality_grandpa::block_import(
+			client.clone(),
+			&(client.clone() as std::sync::Arc<_>),
+			components.select_chain,
+		).unwrap();
+		assert_eq!(link.shared_authority_set().set_id(), 1);
+	}
+}
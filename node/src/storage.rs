@@ -0,0 +1,41 @@
+//! Reading runtime storage values from the client.
+
+use codec::Decode;
+use node_template_runtime::{opaque::Block, Event, Hash};
+use sc_client_api::{StorageProvider, backend::Backend};
+use sp_core::{hashing::twox_128, storage::StorageKey};
+use sp_runtime::generic::BlockId;
+
+/// An event deposited in a block, along with the phase it was deposited in.
+pub type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// The storage key of a storage value.
+pub fn value_key(module: &[u8], item: &[u8]) -> StorageKey {
+	StorageKey([twox_128(module), twox_128(item)].concat())
+}
+
+/// Read and decode a storage value at `at`, an absent value being the default one.
+pub fn read_value<T, C, BE>(client: &C, at: &BlockId<Block>, key: StorageKey) -> Option<T> where
+	T: Decode + Default,
+	BE: Backend<Block>,
+	C: StorageProvider<Block, BE>,
+{
+	match client.storage(at, &key) {
+		Ok(Some(data)) => T::decode(&mut &data.0[..])
+			.map_err(|e| log::debug!("Unable to decode {:?} at {:?}: {:?}", key, at, e))
+			.ok(),
+		Ok(None) => Some(T::default()),
+		Err(e) => {
+			log::debug!("Unable to read {:?} at {:?}: {:?}", key, at, e);
+			None
+		},
+	}
+}
+
+/// The events deposited in the block `at`.
+pub fn events<C, BE>(client: &C, at: &BlockId<Block>) -> Option<Vec<EventRecord>> where
+	BE: Backend<Block>,
+	C: StorageProvider<Block, BE>,
+{
+	read_value(client, at, value_key(b"System", b"Events"))
+}
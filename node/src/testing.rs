@@ -0,0 +1,58 @@
+//! Helpers for the tests running an in-process development node, here and in other crates.
+
+use futures::FutureExt;
+use node_template_runtime::VERSION;
+use sc_cli::{CliConfiguration, RunCmd, RuntimeVersion, SubstrateCli};
+use sc_service::{ChainSpec, Configuration, TaskType};
+use structopt::StructOpt;
+use crate::chain_spec;
+
+/// The command line of the tests, which always loads the development chain.
+pub struct TestCli;
+
+impl SubstrateCli for TestCli {
+	fn impl_name() -> String {
+		"Node Template Tests".into()
+	}
+
+	fn impl_version() -> String {
+		env!("CARGO_PKG_VERSION").into()
+	}
+
+	fn description() -> String {
+		env!("CARGO_PKG_DESCRIPTION").into()
+	}
+
+	fn author() -> String {
+		env!("CARGO_PKG_AUTHORS").into()
+	}
+
+	fn support_url() -> String {
+		"support.anonymous.an".into()
+	}
+
+	fn copyright_start_year() -> i32 {
+		2017
+	}
+
+	fn load_spec(&self, _: &str) -> Result<Box<dyn ChainSpec>, String> {
+		Ok(Box::new(chain_spec::development_config()?))
+	}
+
+	fn native_runtime_version(_: &Box<dyn ChainSpec>) -> &'static RuntimeVersion {
+		&VERSION
+	}
+}
+
+/// The configuration of a development node with a temporary database and the extra command line
+/// `args`, which runs its tasks on `handle`.
+pub fn dev_config(args: &[&str], handle: tokio::runtime::Handle) -> Configuration {
+	let task_executor = move |fut, task_type| match task_type {
+		TaskType::Async => handle.spawn(fut).map(drop),
+		TaskType::Blocking =>
+			handle.spawn_blocking(move || futures::executor::block_on(fut)).map(drop),
+	};
+	RunCmd::from_iter(["node-template", "--dev", "--tmp"].iter().chain(args))
+		.create_configuration(&TestCli, task_executor.into())
+		.expect("the command line of the tests is valid; qed")
+}
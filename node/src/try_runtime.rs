@@ -0,0 +1,169 @@
+//! The `try-runtime` command, a dry run of a runtime upgrade on the state of a live chain.
+//!
+//! The state is read from a snapshot file, which is first written from the local database when
+//! it does not exist yet. The code in the state is replaced by the new runtime, which then runs
+//! the migrations of all pallets and checks their invariants through the `TryRuntime` API,
+//! available when the runtime is built with the `try-runtime` feature.
+
+use std::{fs, path::PathBuf, sync::Arc};
+use codec::Decode;
+use node_template_runtime::{opaque::Block, try_runtime::UpgradeReport, WASM_BINARY};
+use sc_cli::{
+	BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, SharedParams,
+	WasmExecutionMethod,
+};
+use sc_client_api::{StorageProvider, UsageProvider, backend::Backend};
+use sc_executor::NativeExecutor;
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::well_known_keys;
+use sp_runtime::{generic::BlockId, traits::{BlakeTwo256, Header as HeaderT, NumberFor}};
+use sp_state_machine::{
+	backend::BackendRuntimeCode, ExecutionStrategy, Extensions, InMemoryBackend,
+	OverlayedChanges, StateMachine,
+};
+use structopt::StructOpt;
+use crate::{service::Executor, snapshot::Snapshot};
+
+/// The `try-runtime` command used to dry-run a runtime upgrade.
+#[derive(Debug, StructOpt)]
+pub struct TryRuntimeCmd {
+	/// The state snapshot to run the upgrade on.
+	///
+	/// It is written from the local database first if it does not exist, or if `--at` is given.
+	#[structopt(long = "snapshot", value_name = "PATH", parse(from_os_str))]
+	pub snapshot: PathBuf,
+
+	/// Block hash or number to snapshot the state of from the local database.
+	///
+	/// Default is the best block.
+	#[structopt(long = "at", value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// The Wasm runtime to upgrade to, rather than the one of this node.
+	#[structopt(long = "runtime", value_name = "PATH", parse(from_os_str))]
+	pub runtime: Option<PathBuf>,
+
+	/// Method for executing the Wasm runtime code.
+	#[structopt(
+		long = "wasm-execution",
+		value_name = "METHOD",
+		possible_values = &WasmExecutionMethod::variants(),
+		case_insensitive = true,
+		default_value = "Interpreted"
+	)]
+	pub wasm_method: WasmExecutionMethod,
+
+	/// The number of 64KB pages to allocate for Wasm execution, when the state does not set it.
+	#[structopt(long = "heap-pages")]
+	pub heap_pages: Option<u64>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl TryRuntimeCmd {
+	/// Whether the snapshot must be written from the local database first.
+	pub fn needs_database(&self) -> bool {
+		self.at.is_some() || !self.snapshot.exists()
+	}
+
+	/// Write the snapshot from the local database, then run the upgrade on it.
+	pub async fn run_from_database<C, BE>(&self, client: Arc<C>) -> sc_cli::Result<()> where
+		BE: Backend<Block>,
+		C: HeaderBackend<Block> + UsageProvider<Block> + StorageProvider<Block, BE>,
+	{
+		let block_id = match &self.at {
+			Some(at) => at.parse()?,
+			None => BlockId::Hash(client.usage_info().chain.best_hash),
+		};
+		let snapshot = Snapshot::at(client, &block_id)?;
+		snapshot.write(&self.snapshot)?;
+		log::info!(
+			"Wrote the snapshot of block #{} to {}",
+			snapshot.header.number(),
+			self.snapshot.display(),
+		);
+		self.upgrade(snapshot)
+	}
+
+	/// Run the upgrade on the existing snapshot.
+	pub fn run_from_file(&self) -> sc_cli::Result<()> {
+		self.upgrade(Snapshot::read(&self.snapshot)?)
+	}
+
+	fn upgrade(&self, snapshot: Snapshot) -> sc_cli::Result<()> {
+		let number = *snapshot.header.number();
+		let code = match &self.runtime {
+			Some(path) => fs::read(path)?,
+			None => WASM_BINARY.ok_or("Development wasm binary not available")?.to_vec(),
+		};
+		let mut storage = snapshot.into_storage();
+		storage.top.insert(well_known_keys::CODE.to_vec(), code);
+
+		let backend = InMemoryBackend::<BlakeTwo256>::from(storage);
+		let runtime_code = BackendRuntimeCode::new(&backend).runtime_code()?;
+		let executor = NativeExecutor::<Executor>::new(self.wasm_method.into(), self.heap_pages, 1);
+		let mut changes = OverlayedChanges::default();
+		let output = StateMachine::<_, _, NumberFor<Block>, _>::new(
+			&backend,
+			None,
+			&mut changes,
+			&executor,
+			"TryRuntime_on_runtime_upgrade",
+			&[],
+			Extensions::default(),
+			&runtime_code,
+			sp_core::testing::TaskExecutor::new(),
+		)
+			// The native runtime is the old one.
+			.execute(ExecutionStrategy::AlwaysWasm)
+			.map_err(|e| format!(
+				"Unable to run the migrations, was the runtime built with `try-runtime`? {}", e,
+			))?;
+		let report = UpgradeReport::decode(&mut &output[..])
+			.map_err(|e| format!("Unable to decode the upgrade report: {}", e))?;
+
+		let share = report.weight as f64 * 100.0 / report.max_block_weight.max(1) as f64;
+		println!(
+			"Migrations on block #{} consumed {} weight, {:.2}% of a block",
+			number,
+			report.weight,
+			share,
+		);
+		for (pallet, reason) in &report.failed_checks {
+			println!("{}: {}", pallet, reason);
+		}
+
+		if report.weight > report.max_block_weight {
+			return Err("The migrations do not fit in a block".into());
+		}
+		if !report.failed_checks.is_empty() {
+			return Err(format!("{} post-upgrade checks failed", report.failed_checks.len()).into());
+		}
+		println!("All post-upgrade checks passed");
+		Ok(())
+	}
+}
+
+impl CliConfiguration for TryRuntimeCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -0,0 +1,471 @@
+//! The `tx` commands, building and signing transactions offline and submitting them to a node.
+//!
+//! `tx build` writes everything a transaction is signed over to a JSON file, so that it can be
+//! carried to an offline machine, where `tx sign` signs it into the hex of an
+//! `UncheckedExtrinsic`. Neither needs a node or a database: the call is encoded with the
+//! metadata of the runtime of this binary, see [`call`], and the genesis hash, versions and era
+//! are given on the command line. `tx submit` then sends the extrinsic to a node over RPC.
+
+use std::{fs, io::{self, BufRead, Read}, path::{Path, PathBuf}};
+use codec::{Decode, Encode};
+use hyper::{Body, Client, Request, body};
+use node_template_runtime::{
+	signed_extra, signed_extra_additional, Address, Balance, BlockNumber, Call, Hash, Index,
+	SignedExtra, UncheckedExtrinsic,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sp_core::{Bytes, Pair, bytes, ed25519, sr25519};
+use sp_runtime::{
+	MultiSignature, MultiSigner, generic::{Era, SignedPayload}, traits::IdentifyAccount,
+};
+use structopt::StructOpt;
+
+pub mod call;
+
+/// A mortal era of a transaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MortalEra {
+	/// The number of blocks the transaction is valid for, rounded up to a power of two.
+	pub period: u64,
+	/// The number of a recent block, from which on the transaction is valid.
+	pub block_number: BlockNumber,
+	/// The hash of that block.
+	pub block_hash: Hash,
+}
+
+/// An unsigned transaction, with everything its signature covers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedTransaction {
+	/// The SCALE-encoded call.
+	pub call: Bytes,
+	/// The nonce of the sender.
+	pub nonce: Index,
+	/// The tip, in decimal as it may not fit in a JSON number.
+	pub tip: String,
+	/// The era of the transaction, or none for an immortal one.
+	pub era: Option<MortalEra>,
+	/// The genesis hash of the chain.
+	pub genesis_hash: Hash,
+	/// The spec version of the runtime.
+	pub spec_version: u32,
+	/// The transaction version of the runtime.
+	pub transaction_version: u32,
+	/// The bytes to sign, for inspection; `tx sign` computes them again.
+	pub signing_payload: Bytes,
+}
+
+impl UnsignedTransaction {
+	/// The transaction for `call`, with its signing payload filled in.
+	pub fn new(
+		call: &Call,
+		nonce: Index,
+		tip: Balance,
+		era: Option<MortalEra>,
+		genesis_hash: Hash,
+		spec_version: u32,
+		transaction_version: u32,
+	) -> Result<Self, String> {
+		let mut tx = UnsignedTransaction {
+			call: call.encode().into(),
+			nonce,
+			tip: tip.to_string(),
+			era,
+			genesis_hash,
+			spec_version,
+			transaction_version,
+			signing_payload: Vec::new().into(),
+		};
+		tx.signing_payload = tx.payload()?.using_encoded(|payload| payload.to_vec()).into();
+		Ok(tx)
+	}
+
+	/// The payload of the signature: the call, the `SignedExtra` of the runtime and the data it
+	/// adds to the signature.
+	pub fn payload(&self) -> Result<SignedPayload<Call, SignedExtra>, String> {
+		let call = Call::decode(&mut &self.call[..]).map_err(|e| format!("Invalid call: {}", e))?;
+		let tip: Balance = self.tip.parse().map_err(|e| format!("Invalid tip: {}", e))?;
+		let (era, birth_hash) = match &self.era {
+			Some(era) => (
+				Era::mortal(era.period, era.block_number.into()),
+				era.block_hash,
+			),
+			None => (Era::Immortal, self.genesis_hash),
+		};
+
+		let additional = signed_extra_additional(
+			self.spec_version,
+			self.transaction_version,
+			self.genesis_hash,
+			birth_hash,
+		);
+		Ok(SignedPayload::from_raw(call, signed_extra(era, self.nonce, tip), additional))
+	}
+
+	/// Sign the transaction with `pair` into an extrinsic sent by the account of the public key.
+	pub fn sign<P>(&self, pair: &P) -> Result<UncheckedExtrinsic, String> where
+		P: Pair,
+		P::Public: Into<MultiSigner>,
+		P::Signature: Into<MultiSignature>,
+	{
+		let payload = self.payload()?;
+		if payload.using_encoded(|payload| payload != &self.signing_payload[..]) {
+			return Err("The signing payload does not match the transaction".into());
+		}
+		let signature = payload.using_encoded(|payload| pair.sign(payload));
+		let signer = pair.public().into().into_account();
+		let (call, extra, _) = payload.deconstruct();
+		Ok(UncheckedExtrinsic::new_signed(call, Address::Id(signer), signature.into(), extra))
+	}
+}
+
+/// The `tx` commands.
+#[derive(Debug, StructOpt)]
+pub enum TxCmd {
+	/// Build an unsigned transaction, written as JSON.
+	Build(BuildCmd),
+
+	/// Sign a transaction written by `tx build`, into the hex of the extrinsic.
+	Sign(SignCmd),
+
+	/// Submit a signed extrinsic to a node.
+	Submit(SubmitCmd),
+}
+
+impl TxCmd {
+	/// Run the command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		match self {
+			TxCmd::Build(cmd) => cmd.run(),
+			TxCmd::Sign(cmd) => cmd.run(),
+			TxCmd::Submit(cmd) => cmd.run(),
+		}
+	}
+}
+
+fn parse_hash(s: &str) -> Result<Hash, String> {
+	let bytes = bytes::from_hex(s).map_err(|e| format!("{:?}", e))?;
+	if bytes.len() != 32 {
+		return Err("expected 32 bytes of hex".into());
+	}
+	Ok(Hash::from_slice(&bytes))
+}
+
+/// The `tx build` command.
+#[derive(Debug, StructOpt)]
+pub struct BuildCmd {
+	/// The pallet of the call, as named in the runtime, e.g. `Balances`.
+	#[structopt(long)]
+	pub pallet: String,
+
+	/// The call, e.g. `transfer`.
+	#[structopt(long)]
+	pub method: String,
+
+	/// The arguments of the call, as a JSON array.
+	///
+	/// Integers may be given as decimal strings, accounts in SS58 or as hex, account indices as
+	/// `"#42"` and bytes as hex or as a string.
+	#[structopt(long, default_value = "[]")]
+	pub args: String,
+
+	/// The nonce of the sender.
+	#[structopt(long)]
+	pub nonce: Index,
+
+	/// The tip for the block author.
+	#[structopt(long, default_value = "0")]
+	pub tip: Balance,
+
+	/// Make the transaction mortal, valid for this number of blocks from `--era-block-number`.
+	#[structopt(
+		long,
+		value_name = "BLOCKS",
+		requires_all = &["era-block-number", "era-block-hash"]
+	)]
+	pub era_period: Option<u64>,
+
+	/// The number of a recent block, from which on a mortal transaction is valid.
+	#[structopt(long, value_name = "NUMBER", requires = "era-period")]
+	pub era_block_number: Option<BlockNumber>,
+
+	/// The hash of the block of `--era-block-number`.
+	#[structopt(
+		long,
+		value_name = "HASH",
+		parse(try_from_str = parse_hash),
+		requires = "era-period"
+	)]
+	pub era_block_hash: Option<Hash>,
+
+	/// The genesis hash of the chain.
+	#[structopt(long, value_name = "HASH", parse(try_from_str = parse_hash))]
+	pub genesis_hash: Hash,
+
+	/// The spec version of the runtime of the chain.
+	#[structopt(long)]
+	pub spec_version: u32,
+
+	/// The transaction version of the runtime of the chain.
+	#[structopt(long = "tx-version")]
+	pub transaction_version: u32,
+
+	/// Output file name or stdout if unspecified.
+	#[structopt(long, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+}
+
+impl BuildCmd {
+	/// Run the `tx build` command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let args: Vec<Value> = serde_json::from_str(&self.args)
+			.map_err(|e| format!("The arguments are not a JSON array: {}", e))?;
+		let call = call::encode(&self.pallet, &self.method, &args)?;
+		let era = match (self.era_period, self.era_block_number, self.era_block_hash) {
+			(Some(period), Some(block_number), Some(block_hash)) =>
+				Some(MortalEra { period, block_number, block_hash }),
+			_ => None,
+		};
+		let tx = UnsignedTransaction::new(
+			&call,
+			self.nonce,
+			self.tip,
+			era,
+			self.genesis_hash,
+			self.spec_version,
+			self.transaction_version,
+		)?;
+
+		let json = serde_json::to_string_pretty(&tx).map_err(|e| e.to_string())?;
+		match &self.output {
+			Some(path) => fs::write(path, json + "\n")?,
+			None => println!("{}", json),
+		}
+		Ok(())
+	}
+}
+
+/// A signature scheme of the keys of accounts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scheme {
+	/// Schnorrkel signatures, the default of Substrate keys.
+	Sr25519,
+	/// Ed25519 signatures.
+	Ed25519,
+}
+
+impl std::str::FromStr for Scheme {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"sr25519" => Ok(Scheme::Sr25519),
+			"ed25519" => Ok(Scheme::Ed25519),
+			_ => Err(format!("Unknown scheme {}, expected sr25519 or ed25519", s)),
+		}
+	}
+}
+
+/// The `tx sign` command.
+#[derive(Debug, StructOpt)]
+pub struct SignCmd {
+	/// The transaction written by `tx build`, or stdin if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub input: Option<PathBuf>,
+
+	/// The secret URI of the key of the sender, e.g. a mnemonic phrase or `//Alice`.
+	///
+	/// Other users of the machine can see the arguments of commands; without this option, the
+	/// secret URI is read from `--suri-file`.
+	#[structopt(long, value_name = "SURI", conflicts_with = "suri-file")]
+	pub suri: Option<String>,
+
+	/// The file to read the secret URI from, or `-` for stdin, the default.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub suri_file: Option<PathBuf>,
+
+	/// The password of the secret URI.
+	#[structopt(long, conflicts_with = "password-file")]
+	pub password: Option<String>,
+
+	/// The file to read the password of the secret URI from, or `-` for stdin.
+	///
+	/// Secrets read from stdin take a line each, the secret URI first.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub password_file: Option<PathBuf>,
+
+	/// The signature scheme of the key.
+	#[structopt(long, default_value = "sr25519", possible_values = &["sr25519", "ed25519"])]
+	pub scheme: Scheme,
+}
+
+impl SignCmd {
+	/// Run the `tx sign` command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let stdin_path = Path::new("-");
+		let suri_from_stdin = self.suri.is_none()
+			&& self.suri_file.as_deref().map_or(true, |path| path == stdin_path);
+		let password_from_stdin = self.password.is_none()
+			&& self.password_file.as_deref() == Some(stdin_path);
+		if self.input.is_none() && (suri_from_stdin || password_from_stdin) {
+			return Err("Give the transaction as a file to read secrets from stdin".into());
+		}
+
+		let json = match &self.input {
+			Some(path) => fs::read_to_string(path)?,
+			None => {
+				let mut json = String::new();
+				io::stdin().read_to_string(&mut json)?;
+				json
+			},
+		};
+		let tx: UnsignedTransaction = serde_json::from_str(&json)
+			.map_err(|e| format!("Invalid transaction: {}", e))?;
+
+		let stdin = io::stdin();
+		let mut stdin = stdin.lock();
+		let suri = match &self.suri {
+			Some(suri) => suri.clone(),
+			None => read_secret(self.suri_file.as_deref().unwrap_or(stdin_path), &mut stdin)?,
+		};
+		let password = match (&self.password, &self.password_file) {
+			(Some(password), _) => Some(password.clone()),
+			(None, Some(path)) => Some(read_secret(path, &mut stdin)?),
+			(None, None) => None,
+		};
+
+		let password = password.as_deref();
+		let invalid_suri = |e| format!("Invalid secret URI: {:?}", e);
+		let extrinsic = match self.scheme {
+			Scheme::Sr25519 => tx.sign(&sr25519::Pair::from_string(&suri, password)
+				.map_err(invalid_suri)?)?,
+			Scheme::Ed25519 => tx.sign(&ed25519::Pair::from_string(&suri, password)
+				.map_err(invalid_suri)?)?,
+		};
+		println!("{}", bytes::to_hex(&extrinsic.encode(), false));
+		Ok(())
+	}
+}
+
+/// Read a secret from the file at `path`, or from the next line of `stdin` if it is `-`, without
+/// the line break ending it.
+fn read_secret(path: &Path, stdin: &mut impl BufRead) -> io::Result<String> {
+	let mut secret = String::new();
+	if path == Path::new("-") {
+		stdin.read_line(&mut secret)?;
+	} else {
+		secret = fs::read_to_string(path)?;
+	}
+	Ok(secret.trim_end_matches(&['\r', '\n'][..]).to_owned())
+}
+
+/// The `tx submit` command.
+#[derive(Debug, StructOpt)]
+pub struct SubmitCmd {
+	/// The hex of the signed extrinsic.
+	pub extrinsic: String,
+
+	/// The HTTP RPC endpoint of the node.
+	#[structopt(long, default_value = "http://localhost:9933")]
+	pub url: String,
+}
+
+impl SubmitCmd {
+	/// Run the `tx submit` command, printing the hash of the extrinsic.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let encoded = bytes::from_hex(&self.extrinsic)
+			.map_err(|e| format!("Invalid extrinsic hex: {:?}", e))?;
+		let mut input = &encoded[..];
+		UncheckedExtrinsic::decode(&mut input)
+			.ok()
+			.filter(|_| input.is_empty())
+			.ok_or("The hex is not an extrinsic of this runtime")?;
+
+		let request = json!({
+			"jsonrpc": "2.0",
+			"id": 1,
+			"method": "author_submitExtrinsic",
+			"params": [bytes::to_hex(&encoded, false)],
+		});
+		let mut runtime = tokio::runtime::Builder::new()
+			.basic_scheduler()
+			.enable_all()
+			.build()?;
+		let response = runtime.block_on(self.post(request.to_string()))?;
+
+		match (response.get("result"), response.get("error")) {
+			(Some(hash), _) => println!("{}", hash.as_str().unwrap_or_default()),
+			(_, Some(error)) =>
+				return Err(format!("The node rejected the extrinsic: {}", error).into()),
+			_ => return Err(format!("Invalid RPC response: {}", response).into()),
+		}
+		Ok(())
+	}
+
+	async fn post(&self, request: String) -> Result<Value, String> {
+		let request = Request::post(&self.url)
+			.header("Content-Type", "application/json")
+			.body(Body::from(request))
+			.map_err(|e| format!("Invalid URL {}: {}", self.url, e))?;
+		let response = Client::new().request(request).await
+			.map_err(|e| format!("Unable to reach {}: {}", self.url, e))?;
+		let body = body::to_bytes(response.into_body()).await.map_err(|e| e.to_string())?;
+		serde_json::from_slice(&body).map_err(|e| format!("Invalid RPC response: {}", e))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_keyring::AccountKeyring;
+	use sp_runtime::traits::Verify;
+
+	fn do_something() -> UnsignedTransaction {
+		let call = call::encode("TemplateModule", "do_something", &[json!(7)]).unwrap();
+		let era = MortalEra { period: 64, block_number: 100, block_hash: Hash::repeat_byte(2) };
+		UnsignedTransaction::new(&call, 3, 10, Some(era), Hash::repeat_byte(1), 1, 1).unwrap()
+	}
+
+	#[test]
+	fn transactions_round_trip_through_json() {
+		let tx = do_something();
+		let json = serde_json::to_string(&tx).unwrap();
+		assert_eq!(serde_json::from_str::<UnsignedTransaction>(&json).unwrap(), tx);
+	}
+
+	#[test]
+	fn signed_extrinsics_carry_the_signed_extra() {
+		let tx = do_something();
+		let alice = AccountKeyring::Alice;
+		let extrinsic = tx.sign(&alice.pair()).unwrap();
+		let decoded = UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..]).unwrap();
+
+		let (address, signature, extra) = decoded.signature.unwrap();
+		assert_eq!(address, Address::Id(alice.to_account_id()));
+		assert_eq!(extra.encode(), tx.payload().unwrap().deconstruct().1.encode());
+		assert!(signature.verify(&tx.signing_payload[..], &alice.to_account_id()));
+	}
+
+	#[test]
+	fn secrets_are_read_from_stdin_line_by_line_or_from_files() {
+		let mut stdin = io::Cursor::new("//Alice\nhunter2 \r\n");
+		assert_eq!(read_secret(Path::new("-"), &mut stdin).unwrap(), "//Alice");
+		assert_eq!(read_secret(Path::new("-"), &mut stdin).unwrap(), "hunter2 ");
+		assert_eq!(read_secret(Path::new("-"), &mut stdin).unwrap(), "");
+
+		let path = std::env::temp_dir().join(format!("suri-{}", std::process::id()));
+		fs::write(&path, "//Bob\n").unwrap();
+		let read = read_secret(&path, &mut stdin);
+		fs::remove_file(&path).unwrap();
+		assert_eq!(read.unwrap(), "//Bob");
+	}
+
+	#[test]
+	fn tampered_transactions_are_not_signed() {
+		let mut tx = do_something();
+		tx.nonce += 1;
+		assert!(tx.sign(&AccountKeyring::Alice.pair()).is_err());
+	}
+}
@@ -0,0 +1,250 @@
+//! Encoding of calls given by pallet, method and JSON arguments.
+//!
+//! The indices of the pallet and of the method, and the types of the arguments, are taken from
+//! the metadata of the runtime of this node. The metadata names the types as they are written in
+//! the pallets, so the arguments are encoded by the type names the pallets of this runtime use.
+
+use codec::{Compact, Decode, Encode};
+use frame_metadata::{
+	DecodeDifferent, FunctionMetadata, ModuleMetadata, RuntimeMetadata, RuntimeMetadataPrefixed,
+};
+use node_template_runtime::{AccountId, Address, Call, Runtime};
+use serde_json::Value;
+use sp_core::{H160, H256, bytes, crypto::Ss58Codec};
+
+/// The metadata lists, which are decoded when the metadata was.
+pub(crate) fn decoded<B, O>(value: DecodeDifferent<B, O>) -> Result<O, String> {
+	match value {
+		DecodeDifferent::Decoded(value) => Ok(value),
+		DecodeDifferent::Encode(_) => Err("The runtime metadata is not decoded".into()),
+	}
+}
+
+/// The pallets of the runtime, in the order they are declared in.
+pub(crate) fn modules() -> Result<Vec<ModuleMetadata>, String> {
+	// Round-trip the metadata, so that its lists are owned rather than built by functions.
+	let metadata = RuntimeMetadataPrefixed::decode(&mut &Runtime::metadata().encode()[..])
+		.map_err(|e| format!("Invalid runtime metadata: {}", e))?;
+	match metadata.1 {
+		RuntimeMetadata::V11(metadata) => decoded(metadata.modules),
+		_ => Err("Unsupported runtime metadata version".into()),
+	}
+}
+
+/// The index of `pallet` in the calls of the runtime, and its calls.
+fn pallet_calls(pallet: &str) -> Result<(u8, Vec<FunctionMetadata>), String> {
+	// Only the pallets with calls have a variant in `Call`.
+	let mut index = 0u8;
+	for module in modules()? {
+		if let Some(calls) = module.calls {
+			if decoded(module.name)? == pallet {
+				return Ok((index, decoded(calls)?));
+			}
+			index += 1;
+		}
+	}
+	Err(format!("The runtime has no pallet {} with calls", pallet))
+}
+
+/// Encode the call `method` of `pallet` with `args`.
+pub fn encode(pallet: &str, method: &str, args: &[Value]) -> Result<Call, String> {
+	let (pallet_index, calls) = pallet_calls(pallet)?;
+	let mut encoded = vec![pallet_index];
+	let mut arguments = None;
+	for (index, call) in calls.into_iter().enumerate() {
+		if decoded(call.name)? == method {
+			encoded.push(index as u8);
+			arguments = Some(decoded(call.arguments)?);
+			break;
+		}
+	}
+	let arguments = arguments.ok_or_else(|| format!("{} has no call {}", pallet, method))?;
+
+	if arguments.len() != args.len() {
+		return Err(format!(
+			"{}.{} takes {} arguments, but {} were given",
+			pallet,
+			method,
+			arguments.len(),
+			args.len(),
+		));
+	}
+	for (argument, value) in arguments.into_iter().zip(args) {
+		let (name, ty) = (decoded(argument.name)?, decoded(argument.ty)?);
+		encode_arg(&normalize(&ty), value, &mut encoded)
+			.map_err(|e| format!("Invalid argument {} of type {}: {}", name, ty, e))?;
+	}
+
+	let mut input = &encoded[..];
+	let call = Call::decode(&mut input).map_err(|e| format!("Invalid call: {}", e))?;
+	if !input.is_empty() {
+		return Err("Invalid call: trailing bytes".into());
+	}
+	Ok(call)
+}
+
+/// The type name without spaces and without the `T::` prefixes of the associated types.
+pub(crate) fn normalize(ty: &str) -> String {
+	let ty: String = ty.chars().filter(|c| !c.is_whitespace()).collect();
+	ty.replace("<TasTrait>::", "").replace("T::", "")
+}
+
+/// The number of bits of the unsigned integer types of the runtime.
+pub(crate) fn uint_bits(ty: &str) -> Option<u32> {
+	match ty {
+		"u8" => Some(8),
+		"u16" => Some(16),
+		"u32" | "BlockNumber" | "Index" | "AccountIndex" | "AssetId" | "CollectionId"
+		| "TokenId" => Some(32),
+		"u64" | "Moment" | "Weight" | "Gas" => Some(64),
+		"u128" | "Balance" | "BalanceOf<T>" => Some(128),
+		_ => None,
+	}
+}
+
+/// An unsigned integer, given as a JSON number or as a decimal string for large ones.
+fn uint(value: &Value, bits: u32) -> Result<u128, String> {
+	let n = match value {
+		Value::Number(n) => n.as_u64().map(u128::from).ok_or("expected an unsigned integer")?,
+		Value::String(s) => s.parse::<u128>().map_err(|e| e.to_string())?,
+		_ => return Err("expected an unsigned integer".into()),
+	};
+	if bits < 128 && n >> bits != 0 {
+		return Err(format!("{} does not fit in {} bits", n, bits));
+	}
+	Ok(n)
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+	value.as_str().ok_or_else(|| "expected a string".into())
+}
+
+/// Bytes, given as `0x`-prefixed hex, or as a string of their UTF-8 encoding.
+fn byte_string(value: &Value) -> Result<Vec<u8>, String> {
+	let s = string(value)?;
+	if s.starts_with("0x") {
+		bytes::from_hex(s).map_err(|e| format!("{:?}", e))
+	} else {
+		Ok(s.as_bytes().to_vec())
+	}
+}
+
+fn fixed_bytes(value: &Value, len: usize) -> Result<Vec<u8>, String> {
+	let bytes = bytes::from_hex(string(value)?).map_err(|e| format!("{:?}", e))?;
+	if bytes.len() != len {
+		return Err(format!("expected {} bytes of hex", len));
+	}
+	Ok(bytes)
+}
+
+/// An account, given in SS58 or as hex.
+fn account(value: &Value) -> Result<AccountId, String> {
+	let s = string(value)?;
+	if s.starts_with("0x") {
+		let mut raw = [0u8; 32];
+		raw.copy_from_slice(&fixed_bytes(value, 32)?);
+		Ok(raw.into())
+	} else {
+		AccountId::from_ss58check(s).map_err(|e| format!("{:?}", e))
+	}
+}
+
+/// An address, given as an account, or as an account index by a number or by `#` and a number.
+fn address(value: &Value) -> Result<Address, String> {
+	match value {
+		Value::Number(_) => Ok(Address::Index(uint(value, 32)? as u32)),
+		Value::String(s) if s.starts_with('#') =>
+			s[1..].parse().map(Address::Index).map_err(|e| format!("{}", e)),
+		_ => account(value).map(Address::Id),
+	}
+}
+
+fn encode_arg(ty: &str, value: &Value, out: &mut Vec<u8>) -> Result<(), String> {
+	if let Some(inner) = ty.strip_prefix("Compact<").and_then(|ty| ty.strip_suffix('>')) {
+		let bits = uint_bits(inner).ok_or_else(|| format!("unsupported type {}", ty))?;
+		let n = uint(value, bits)?;
+		match bits {
+			8 => Compact(n as u8).encode_to(out),
+			16 => Compact(n as u16).encode_to(out),
+			32 => Compact(n as u32).encode_to(out),
+			64 => Compact(n as u64).encode_to(out),
+			_ => Compact(n).encode_to(out),
+		}
+		return Ok(());
+	}
+	if let Some(inner) = ty.strip_prefix("Option<").and_then(|ty| ty.strip_suffix('>')) {
+		if value.is_null() {
+			out.push(0);
+			return Ok(());
+		}
+		out.push(1);
+		return encode_arg(inner, value, out);
+	}
+	if ty == "Vec<u8>" {
+		byte_string(value)?.encode_to(out);
+		return Ok(());
+	}
+	if let Some(inner) = ty.strip_prefix("Vec<").and_then(|ty| ty.strip_suffix('>')) {
+		let items = value.as_array().ok_or("expected an array")?;
+		Compact(items.len() as u32).encode_to(out);
+		for item in items {
+			encode_arg(inner, item, out)?;
+		}
+		return Ok(());
+	}
+	if let Some(bits) = uint_bits(ty) {
+		let n = uint(value, bits)?;
+		match bits {
+			8 => (n as u8).encode_to(out),
+			16 => (n as u16).encode_to(out),
+			32 => (n as u32).encode_to(out),
+			64 => (n as u64).encode_to(out),
+			_ => n.encode_to(out),
+		}
+		return Ok(());
+	}
+
+	match ty {
+		"bool" => value.as_bool().ok_or("expected a boolean")?.encode_to(out),
+		"AccountId" => account(value)?.encode_to(out),
+		"<LookupasStaticLookup>::Source" => address(value)?.encode_to(out),
+		"Hash" | "H256" => H256::from_slice(&fixed_bytes(value, 32)?).encode_to(out),
+		"H160" => H160::from_slice(&fixed_bytes(value, 20)?).encode_to(out),
+		_ => return Err(format!("unsupported type {}", ty)),
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use node_template_runtime::{BalancesCall, pallet_template};
+	use serde_json::json;
+	use sp_keyring::AccountKeyring;
+
+	#[test]
+	fn calls_are_encoded_from_json() {
+		let bob = AccountKeyring::Bob.to_account_id();
+		assert_eq!(
+			encode("Balances", "transfer", &[json!(bob.to_ss58check()), json!("1000")]),
+			Ok(Call::Balances(BalancesCall::transfer(Address::Id(bob), 1000))),
+		);
+		assert_eq!(
+			encode("Balances", "transfer", &[json!("#3"), json!(5)]),
+			Ok(Call::Balances(BalancesCall::transfer(Address::Index(3), 5))),
+		);
+		assert_eq!(
+			encode("TemplateModule", "do_something", &[json!(42)]),
+			Ok(Call::TemplateModule(pallet_template::Call::do_something(42))),
+		);
+	}
+
+	#[test]
+	fn invalid_calls_are_rejected() {
+		assert!(encode("Nope", "transfer", &[]).is_err());
+		assert!(encode("Balances", "nope", &[]).is_err());
+		assert!(encode("TemplateModule", "do_something", &[]).is_err());
+		assert!(encode("TemplateModule", "do_something", &[json!(1u64 << 32)]).is_err());
+		assert!(encode("TemplateModule", "do_something", &[json!("many")]).is_err());
+	}
+}
@@ -0,0 +1,20 @@
+//! Runtime API definition for the assets pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+
+sp_api::decl_runtime_apis! {
+	/// Query the holdings of user-created assets.
+	pub trait AssetsApi<AccountId, AssetId, Balance> where
+		AccountId: Codec,
+		AssetId: Codec,
+		Balance: Codec,
+	{
+		/// The balance of `who` in asset `id`.
+		fn balance(id: AssetId, who: AccountId) -> Balance;
+
+		/// The total supply of asset `id`.
+		fn total_supply(id: AssetId) -> Balance;
+	}
+}
@@ -0,0 +1,100 @@
+//! RPC interface for the assets pallet.
+
+use std::sync::Arc;
+use std::marker::PhantomData;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+pub use pallet_assets_rpc_runtime_api::AssetsApi as AssetsRuntimeApi;
+
+/// Assets RPC methods.
+#[rpc]
+pub trait AssetsApi<BlockHash, AccountId, AssetId, Balance> {
+	/// The balance of `who` in asset `id` at the given block, or at the best block.
+	#[rpc(name = "assets_balance")]
+	fn balance(&self, id: AssetId, who: AccountId, at: Option<BlockHash>) -> Result<Balance>;
+
+	/// The total supply of asset `id` at the given block, or at the best block.
+	#[rpc(name = "assets_totalSupply")]
+	fn total_supply(&self, id: AssetId, at: Option<BlockHash>) -> Result<Balance>;
+}
+
+/// A struct that implements the [`AssetsApi`].
+pub struct Assets<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> Assets<C, B> {
+	/// Create new `Assets` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Assets { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+fn runtime_error(message: &str, e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, AccountId, AssetId, Balance>
+	AssetsApi<<Block as BlockT>::Hash, AccountId, AssetId, Balance> for Assets<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block>,
+	C::Api: AssetsRuntimeApi<Block, AccountId, AssetId, Balance>,
+	AccountId: Codec,
+	AssetId: Codec,
+	Balance: Codec,
+{
+	fn balance(
+		&self,
+		id: AssetId,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		api.balance(&at, id, who).map_err(|e| runtime_error("Unable to query asset balance.", e))
+	}
+
+	fn total_supply(
+		&self,
+		id: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		api.total_supply(&at, id).map_err(|e| runtime_error("Unable to query asset supply.", e))
+	}
+}
@@ -0,0 +1,523 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// A pallet for user-created fungible assets that live next to the native currency.
+///
+/// Anyone can create an asset by reserving a deposit. The owner of an asset can mint and burn it,
+/// freeze single accounts or the whole asset, and attach metadata. Every asset has its own
+/// minimum balance: accounts holding less than it are not kept around.
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, dispatch, ensure, Parameter,
+	traits::{Currency, ReservableCurrency, Get},
+};
+use frame_system::ensure_signed;
+use sp_runtime::{
+	RuntimeDebug,
+	traits::{AtLeast32BitUnsigned, Member, StaticLookup, Zero, Saturating, CheckedAdd},
+};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+type DepositBalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Trait: frame_system::Trait {
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The units in which asset balances are recorded.
+	type Balance: Member + Parameter + AtLeast32BitUnsigned + Default + Copy;
+
+	/// The identifier of an asset.
+	type AssetId: Member + Parameter + AtLeast32BitUnsigned + Default + Copy;
+
+	/// The currency in which deposits are reserved.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The deposit reserved from the creator of an asset.
+	type AssetDeposit: Get<DepositBalanceOf<Self>>;
+
+	/// The base deposit reserved for setting the metadata of an asset.
+	type MetadataDepositBase: Get<DepositBalanceOf<Self>>;
+
+	/// The additional deposit reserved per byte of name and symbol.
+	type MetadataDepositPerByte: Get<DepositBalanceOf<Self>>;
+
+	/// The maximum length of an asset's name or symbol.
+	type StringLimit: Get<u32>;
+}
+
+/// The details of an asset.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct AssetDetails<AccountId, Balance, DepositBalance> {
+	/// Can mint, burn and freeze the asset and set its metadata.
+	pub owner: AccountId,
+	/// The total supply across all accounts.
+	pub supply: Balance,
+	/// The balance reserved from the owner for creating the asset.
+	pub deposit: DepositBalance,
+	/// The minimum balance an account must hold. Smaller balances are not kept.
+	pub min_balance: Balance,
+	/// The number of accounts holding the asset.
+	pub accounts: u32,
+	/// Whether transfers of the asset are suspended.
+	pub is_frozen: bool,
+}
+
+/// The holdings of an account in an asset.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct AssetBalance<Balance> {
+	/// The balance.
+	pub balance: Balance,
+	/// Whether the account may send the asset.
+	pub is_frozen: bool,
+}
+
+/// Human-readable information about an asset.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct AssetMetadata<DepositBalance> {
+	/// The balance reserved from the owner for the metadata.
+	pub deposit: DepositBalance,
+	/// The user friendly name of the asset.
+	pub name: Vec<u8>,
+	/// The ticker symbol of the asset.
+	pub symbol: Vec<u8>,
+	/// The number of decimals the asset uses to represent one unit.
+	pub decimals: u8,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Assets {
+		/// Details of an asset.
+		Asset get(fn asset): map hasher(blake2_128_concat) T::AssetId
+			=> Option<AssetDetails<T::AccountId, T::Balance, DepositBalanceOf<T>>>;
+
+		/// The holdings of an account in an asset. Accounts with no holdings are not stored.
+		Account get(fn account): double_map
+			hasher(blake2_128_concat) T::AssetId,
+			hasher(blake2_128_concat) T::AccountId
+			=> AssetBalance<T::Balance>;
+
+		/// Metadata of an asset.
+		Metadata get(fn metadata): map hasher(blake2_128_concat) T::AssetId
+			=> AssetMetadata<DepositBalanceOf<T>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		Balance = <T as Trait>::Balance,
+		AssetId = <T as Trait>::AssetId,
+	{
+		/// An asset was created. [asset_id, owner]
+		Created(AssetId, AccountId),
+		/// Some assets were issued. [asset_id, beneficiary, amount]
+		Issued(AssetId, AccountId, Balance),
+		/// Some assets were transferred. [asset_id, from, to, amount]
+		Transferred(AssetId, AccountId, AccountId, Balance),
+		/// Some assets were burned. [asset_id, who, amount]
+		Burned(AssetId, AccountId, Balance),
+		/// An account can no longer send the asset. [asset_id, who]
+		Frozen(AssetId, AccountId),
+		/// An account can send the asset again. [asset_id, who]
+		Thawed(AssetId, AccountId),
+		/// All transfers of an asset are suspended. [asset_id]
+		AssetFrozen(AssetId),
+		/// Transfers of an asset are allowed again. [asset_id]
+		AssetThawed(AssetId),
+		/// The metadata of an asset was set. [asset_id, name, symbol, decimals]
+		MetadataSet(AssetId, Vec<u8>, Vec<u8>, u8),
+		/// The metadata of an asset was removed. [asset_id]
+		MetadataCleared(AssetId),
+		/// An asset without holders was removed. [asset_id]
+		Destroyed(AssetId),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The given asset ID is unknown.
+		Unknown,
+		/// The given asset ID is already taken.
+		InUse,
+		/// The asset still has holders.
+		InCirculation,
+		/// The signing account has no permission to do the operation.
+		NoPermission,
+		/// The minimum balance of an asset must be non-zero.
+		MinBalanceZero,
+		/// The amount must be non-zero.
+		AmountZero,
+		/// The account holds none of the asset.
+		BalanceZero,
+		/// The balance is too low to send, or would end up below the minimum balance.
+		BalanceLow,
+		/// The total supply would overflow.
+		Overflow,
+		/// The asset or the sending account is frozen.
+		Frozen,
+		/// The name or symbol is too long.
+		BadMetadata,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The deposit reserved from the creator of an asset.
+		const AssetDeposit: DepositBalanceOf<T> = T::AssetDeposit::get();
+
+		/// The base deposit reserved for setting the metadata of an asset.
+		const MetadataDepositBase: DepositBalanceOf<T> = T::MetadataDepositBase::get();
+
+		/// The additional deposit reserved per byte of name and symbol.
+		const MetadataDepositPerByte: DepositBalanceOf<T> = T::MetadataDepositPerByte::get();
+
+		/// The maximum length of an asset's name or symbol.
+		const StringLimit: u32 = T::StringLimit::get();
+
+		fn deposit_event() = default;
+
+		/// Create a new asset `id` owned by the sender, reserving `AssetDeposit` from them.
+		///
+		/// Accounts holding less than `min_balance` of the asset are not kept.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn create(
+			origin,
+			#[compact] id: T::AssetId,
+			#[compact] min_balance: T::Balance,
+		) -> dispatch::DispatchResult {
+			let owner = ensure_signed(origin)?;
+			ensure!(!Asset::<T>::contains_key(id), Error::<T>::InUse);
+			ensure!(!min_balance.is_zero(), Error::<T>::MinBalanceZero);
+
+			let deposit = T::AssetDeposit::get();
+			T::Currency::reserve(&owner, deposit)?;
+
+			Asset::<T>::insert(id, AssetDetails {
+				owner: owner.clone(),
+				supply: Zero::zero(),
+				deposit,
+				min_balance,
+				accounts: 0,
+				is_frozen: false,
+			});
+			Self::deposit_event(RawEvent::Created(id, owner));
+			Ok(())
+		}
+
+		/// Mint `amount` of asset `id` into the account of `beneficiary`. Only the owner may mint.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn mint(
+			origin,
+			#[compact] id: T::AssetId,
+			beneficiary: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let beneficiary = T::Lookup::lookup(beneficiary)?;
+			ensure!(!amount.is_zero(), Error::<T>::AmountZero);
+
+			let mut details = Self::asset(id).ok_or(Error::<T>::Unknown)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+			details.supply = details.supply.checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+
+			let mut account = Account::<T>::get(id, &beneficiary);
+			// Cannot overflow: an account never holds more than the whole supply.
+			let new_balance = account.balance.saturating_add(amount);
+			ensure!(new_balance >= details.min_balance, Error::<T>::BalanceLow);
+			if account.balance.is_zero() {
+				details.accounts = details.accounts.saturating_add(1);
+			}
+			account.balance = new_balance;
+
+			Account::<T>::insert(id, &beneficiary, account);
+			Asset::<T>::insert(id, details);
+			Self::deposit_event(RawEvent::Issued(id, beneficiary, amount));
+			Ok(())
+		}
+
+		/// Burn up to `amount` of asset `id` from the account of `who`. Only the owner may burn.
+		///
+		/// If the remaining balance would fall below the minimum balance, all of it is burned.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn burn(
+			origin,
+			#[compact] id: T::AssetId,
+			who: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let who = T::Lookup::lookup(who)?;
+
+			let mut details = Self::asset(id).ok_or(Error::<T>::Unknown)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+
+			let mut account = Account::<T>::get(id, &who);
+			ensure!(!account.balance.is_zero(), Error::<T>::BalanceZero);
+
+			let mut burned = amount.min(account.balance);
+			account.balance -= burned;
+			if account.balance < details.min_balance {
+				burned += account.balance;
+				details.accounts = details.accounts.saturating_sub(1);
+				Account::<T>::remove(id, &who);
+			} else {
+				Account::<T>::insert(id, &who, account);
+			}
+			details.supply = details.supply.saturating_sub(burned);
+
+			Asset::<T>::insert(id, details);
+			Self::deposit_event(RawEvent::Burned(id, who, burned));
+			Ok(())
+		}
+
+		/// Move `amount` of asset `id` from the sender to `target`.
+		///
+		/// If the sender would be left with less than the minimum balance, all of it is moved.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3, 3)]
+		pub fn transfer(
+			origin,
+			#[compact] id: T::AssetId,
+			target: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let dest = T::Lookup::lookup(target)?;
+			ensure!(!amount.is_zero(), Error::<T>::AmountZero);
+
+			let mut details = Self::asset(id).ok_or(Error::<T>::Unknown)?;
+			ensure!(!details.is_frozen, Error::<T>::Frozen);
+
+			let mut source = Account::<T>::get(id, &origin);
+			ensure!(!source.is_frozen, Error::<T>::Frozen);
+			ensure!(source.balance >= amount, Error::<T>::BalanceLow);
+
+			if dest == origin {
+				return Ok(())
+			}
+
+			let mut amount = amount;
+			if source.balance - amount < details.min_balance {
+				amount = source.balance;
+			}
+
+			let mut target = Account::<T>::get(id, &dest);
+			let new_balance = target.balance.saturating_add(amount);
+			ensure!(new_balance >= details.min_balance, Error::<T>::BalanceLow);
+			if target.balance.is_zero() {
+				details.accounts = details.accounts.saturating_add(1);
+			}
+			target.balance = new_balance;
+
+			source.balance -= amount;
+			if source.balance.is_zero() {
+				details.accounts = details.accounts.saturating_sub(1);
+				Account::<T>::remove(id, &origin);
+			} else {
+				Account::<T>::insert(id, &origin, source);
+			}
+			Account::<T>::insert(id, &dest, target);
+
+			Asset::<T>::insert(id, details);
+			Self::deposit_event(RawEvent::Transferred(id, origin, dest, amount));
+			Ok(())
+		}
+
+		/// Prevent `who` from sending asset `id`. Only the owner may freeze.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 1)]
+		pub fn freeze(
+			origin,
+			#[compact] id: T::AssetId,
+			who: <T::Lookup as StaticLookup>::Source,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let who = T::Lookup::lookup(who)?;
+			Self::set_account_frozen(origin, id, &who, true)?;
+
+			Self::deposit_event(RawEvent::Frozen(id, who));
+			Ok(())
+		}
+
+		/// Allow `who` to send asset `id` again. Only the owner may thaw.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 1)]
+		pub fn thaw(
+			origin,
+			#[compact] id: T::AssetId,
+			who: <T::Lookup as StaticLookup>::Source,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let who = T::Lookup::lookup(who)?;
+			Self::set_account_frozen(origin, id, &who, false)?;
+
+			Self::deposit_event(RawEvent::Thawed(id, who));
+			Ok(())
+		}
+
+		/// Suspend all transfers of asset `id`. Only the owner may freeze.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+		pub fn freeze_asset(origin, #[compact] id: T::AssetId) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			Self::set_asset_frozen(origin, id, true)?;
+
+			Self::deposit_event(RawEvent::AssetFrozen(id));
+			Ok(())
+		}
+
+		/// Allow transfers of asset `id` again. Only the owner may thaw.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+		pub fn thaw_asset(origin, #[compact] id: T::AssetId) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			Self::set_asset_frozen(origin, id, false)?;
+
+			Self::deposit_event(RawEvent::AssetThawed(id));
+			Ok(())
+		}
+
+		/// Set the metadata of asset `id`. Only the owner may set it.
+		///
+		/// A deposit depending on the length of `name` and `symbol` is reserved from the owner;
+		/// a previous deposit is adjusted.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3, 2)]
+		pub fn set_metadata(
+			origin,
+			#[compact] id: T::AssetId,
+			name: Vec<u8>,
+			symbol: Vec<u8>,
+			decimals: u8,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			ensure!(name.len() <= T::StringLimit::get() as usize, Error::<T>::BadMetadata);
+			ensure!(symbol.len() <= T::StringLimit::get() as usize, Error::<T>::BadMetadata);
+
+			let details = Self::asset(id).ok_or(Error::<T>::Unknown)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+
+			let old_deposit = Self::metadata(id).deposit;
+			let bytes = (name.len() + symbol.len()) as u32;
+			let new_deposit = T::MetadataDepositPerByte::get()
+				.saturating_mul(bytes.into())
+				.saturating_add(T::MetadataDepositBase::get());
+			if new_deposit > old_deposit {
+				T::Currency::reserve(&origin, new_deposit - old_deposit)?;
+			} else {
+				T::Currency::unreserve(&origin, old_deposit - new_deposit);
+			}
+
+			Metadata::<T>::insert(id, AssetMetadata {
+				deposit: new_deposit,
+				name: name.clone(),
+				symbol: symbol.clone(),
+				decimals,
+			});
+			Self::deposit_event(RawEvent::MetadataSet(id, name, symbol, decimals));
+			Ok(())
+		}
+
+		/// Remove the metadata of asset `id` and return its deposit. Only the owner may clear it.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3, 2)]
+		pub fn clear_metadata(origin, #[compact] id: T::AssetId) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			let details = Self::asset(id).ok_or(Error::<T>::Unknown)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+			ensure!(Metadata::<T>::contains_key(id), Error::<T>::Unknown);
+
+			let metadata = Metadata::<T>::take(id);
+			T::Currency::unreserve(&origin, metadata.deposit);
+
+			Self::deposit_event(RawEvent::MetadataCleared(id));
+			Ok(())
+		}
+
+		/// Remove asset `id` once nobody holds it and return all deposits to the owner.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3, 3)]
+		pub fn destroy(origin, #[compact] id: T::AssetId) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			let details = Self::asset(id).ok_or(Error::<T>::Unknown)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+			ensure!(details.accounts == 0, Error::<T>::InCirculation);
+
+			let metadata = Metadata::<T>::take(id);
+			T::Currency::unreserve(&origin, details.deposit.saturating_add(metadata.deposit));
+			Asset::<T>::remove(id);
+
+			Self::deposit_event(RawEvent::Destroyed(id));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The holdings of `who` in asset `id`.
+	pub fn balance(id: T::AssetId, who: T::AccountId) -> T::Balance {
+		Account::<T>::get(id, who).balance
+	}
+
+	/// The total supply of asset `id`.
+	pub fn total_supply(id: T::AssetId) -> T::Balance {
+		Self::asset(id).map(|details| details.supply).unwrap_or_else(Zero::zero)
+	}
+
+	/// Check that the supply and the number of accounts of each asset match its holdings, and
+	/// that holdings and metadata only exist for existing assets.
+	#[cfg(feature = "try-runtime")]
+	pub fn post_upgrade() -> Result<(), &'static str> {
+		use frame_support::storage::{IterableStorageMap, IterableStorageDoubleMap};
+
+		for (id, details) in Asset::<T>::iter() {
+			let mut supply = T::Balance::zero();
+			let mut accounts = 0u32;
+			for (_, account) in Account::<T>::iter_prefix(id) {
+				supply = supply.saturating_add(account.balance);
+				accounts += 1;
+			}
+			ensure!(supply == details.supply, "The supply of an asset differs from its holdings");
+			ensure!(accounts == details.accounts, "The account count of an asset is wrong");
+		}
+		for (id, _, _) in Account::<T>::iter() {
+			ensure!(Asset::<T>::contains_key(id), "Holdings of an unknown asset");
+		}
+		for (id, _) in Metadata::<T>::iter() {
+			ensure!(Asset::<T>::contains_key(id), "Metadata of an unknown asset");
+		}
+		Ok(())
+	}
+
+	fn set_account_frozen(
+		origin: T::AccountId,
+		id: T::AssetId,
+		who: &T::AccountId,
+		is_frozen: bool,
+	) -> dispatch::DispatchResult {
+		let details = Self::asset(id).ok_or(Error::<T>::Unknown)?;
+		ensure!(details.owner == origin, Error::<T>::NoPermission);
+		ensure!(Account::<T>::contains_key(id, who), Error::<T>::BalanceZero);
+
+		Account::<T>::mutate(id, who, |account| account.is_frozen = is_frozen);
+		Ok(())
+	}
+
+	fn set_asset_frozen(
+		origin: T::AccountId,
+		id: T::AssetId,
+		is_frozen: bool,
+	) -> dispatch::DispatchResult {
+		let mut details = Self::asset(id).ok_or(Error::<T>::Unknown)?;
+		ensure!(details.owner == origin, Error::<T>::NoPermission);
+
+		details.is_frozen = is_frozen;
+		Asset::<T>::insert(id, details);
+		Ok(())
+	}
+}
@@ -0,0 +1,152 @@
+use crate::{Error, AssetMetadata, mock::*};
+use frame_support::{assert_ok, assert_noop};
+
+#[test]
+fn create_reserves_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::signed(1), 0, 5));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_eq!(Assets::asset(0).unwrap().owner, 1);
+
+		assert_noop!(Assets::create(Origin::signed(2), 0, 5), Error::<Test>::InUse);
+		assert_noop!(Assets::create(Origin::signed(2), 1, 0), Error::<Test>::MinBalanceZero);
+	});
+}
+
+#[test]
+fn only_owner_can_mint_and_burn() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::signed(1), 0, 5));
+		assert_noop!(Assets::mint(Origin::signed(2), 0, 2, 100), Error::<Test>::NoPermission);
+		assert_noop!(Assets::mint(Origin::signed(1), 1, 2, 100), Error::<Test>::Unknown);
+
+		assert_ok!(Assets::mint(Origin::signed(1), 0, 2, 100));
+		assert_eq!(Assets::balance(0, 2), 100);
+		assert_eq!(Assets::total_supply(0), 100);
+
+		assert_noop!(Assets::burn(Origin::signed(2), 0, 2, 10), Error::<Test>::NoPermission);
+		assert_ok!(Assets::burn(Origin::signed(1), 0, 2, 10));
+		assert_eq!(Assets::balance(0, 2), 90);
+		assert_eq!(Assets::total_supply(0), 90);
+	});
+}
+
+#[test]
+fn minimum_balance_is_enforced() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::signed(1), 0, 5));
+		assert_noop!(Assets::mint(Origin::signed(1), 0, 1, 4), Error::<Test>::BalanceLow);
+		assert_ok!(Assets::mint(Origin::signed(1), 0, 1, 100));
+
+		// the receiver must end up with at least the minimum balance.
+		assert_noop!(Assets::transfer(Origin::signed(1), 0, 2, 4), Error::<Test>::BalanceLow);
+
+		// the sender is not left with dust: the whole balance is sent.
+		assert_ok!(Assets::transfer(Origin::signed(1), 0, 2, 97));
+		assert_eq!(Assets::balance(0, 1), 0);
+		assert_eq!(Assets::balance(0, 2), 100);
+		assert_eq!(Assets::asset(0).unwrap().accounts, 1);
+
+		// burning below the minimum balance burns everything.
+		assert_ok!(Assets::burn(Origin::signed(1), 0, 2, 98));
+		assert_eq!(Assets::balance(0, 2), 0);
+		assert_eq!(Assets::total_supply(0), 0);
+		assert_eq!(Assets::asset(0).unwrap().accounts, 0);
+	});
+}
+
+#[test]
+fn transfer_needs_enough_balance() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::signed(1), 0, 1));
+		assert_ok!(Assets::mint(Origin::signed(1), 0, 1, 100));
+		assert_noop!(Assets::transfer(Origin::signed(1), 0, 2, 101), Error::<Test>::BalanceLow);
+		assert_noop!(Assets::transfer(Origin::signed(2), 0, 1, 1), Error::<Test>::BalanceLow);
+
+		assert_ok!(Assets::transfer(Origin::signed(1), 0, 2, 40));
+		assert_eq!(Assets::balance(0, 1), 60);
+		assert_eq!(Assets::balance(0, 2), 40);
+		assert_eq!(Assets::total_supply(0), 100);
+	});
+}
+
+#[test]
+fn frozen_accounts_and_assets_cannot_send() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::signed(1), 0, 1));
+		assert_ok!(Assets::mint(Origin::signed(1), 0, 1, 100));
+
+		assert_noop!(Assets::freeze(Origin::signed(1), 0, 2), Error::<Test>::BalanceZero);
+		assert_ok!(Assets::freeze(Origin::signed(1), 0, 1));
+		assert_noop!(Assets::transfer(Origin::signed(1), 0, 2, 10), Error::<Test>::Frozen);
+		assert_ok!(Assets::thaw(Origin::signed(1), 0, 1));
+		assert_ok!(Assets::transfer(Origin::signed(1), 0, 2, 10));
+
+		assert_noop!(Assets::freeze_asset(Origin::signed(2), 0), Error::<Test>::NoPermission);
+		assert_ok!(Assets::freeze_asset(Origin::signed(1), 0));
+		assert_noop!(Assets::transfer(Origin::signed(2), 0, 1, 5), Error::<Test>::Frozen);
+		assert_ok!(Assets::thaw_asset(Origin::signed(1), 0));
+		assert_ok!(Assets::transfer(Origin::signed(2), 0, 1, 5));
+	});
+}
+
+#[test]
+fn metadata_deposit_follows_its_length() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::signed(1), 0, 1));
+		assert_noop!(
+			Assets::set_metadata(Origin::signed(1), 0, vec![0; 9], vec![0; 3], 12),
+			Error::<Test>::BadMetadata,
+		);
+		assert_noop!(
+			Assets::set_metadata(Origin::signed(2), 0, vec![0; 4], vec![0; 3], 12),
+			Error::<Test>::NoPermission,
+		);
+
+		assert_ok!(Assets::set_metadata(Origin::signed(1), 0, b"Gold".to_vec(), b"GLD".to_vec(), 12));
+		assert_eq!(Assets::metadata(0), AssetMetadata {
+			deposit: 8,
+			name: b"Gold".to_vec(),
+			symbol: b"GLD".to_vec(),
+			decimals: 12,
+		});
+		assert_eq!(Balances::reserved_balance(1), 10 + 8);
+
+		assert_ok!(Assets::set_metadata(Origin::signed(1), 0, b"Au".to_vec(), b"AU".to_vec(), 12));
+		assert_eq!(Balances::reserved_balance(1), 10 + 5);
+
+		assert_ok!(Assets::clear_metadata(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_noop!(Assets::clear_metadata(Origin::signed(1), 0), Error::<Test>::Unknown);
+	});
+}
+
+#[test]
+fn destroy_returns_deposits() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::signed(1), 0, 1));
+		assert_ok!(Assets::set_metadata(Origin::signed(1), 0, b"Gold".to_vec(), b"GLD".to_vec(), 12));
+		assert_ok!(Assets::mint(Origin::signed(1), 0, 2, 10));
+		assert_noop!(Assets::destroy(Origin::signed(1), 0), Error::<Test>::InCirculation);
+
+		assert_ok!(Assets::burn(Origin::signed(1), 0, 2, 10));
+		assert_ok!(Assets::destroy(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Assets::asset(0), None);
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn post_upgrade_checks_supply_against_holdings() {
+	use frame_support::StorageDoubleMap;
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::create(Origin::signed(1), 0, 5));
+		assert_ok!(Assets::mint(Origin::signed(1), 0, 2, 100));
+		assert_ok!(Assets::post_upgrade());
+
+		crate::Account::<Test>::mutate(0, 2, |account| account.balance = 90);
+		assert!(Assets::post_upgrade().is_err());
+	});
+}
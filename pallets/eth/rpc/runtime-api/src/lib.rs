@@ -0,0 +1,36 @@
+//! Runtime API definition for the Ethereum compatibility pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_core::{H160, H256, U256};
+use sp_runtime::{DispatchError, traits::Block as BlockT};
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Ethereum-style access to the EVM.
+	pub trait EthApi<Receipt> where
+		Receipt: Codec,
+	{
+		/// The chain id transactions have to be signed for.
+		fn chain_id() -> u64;
+
+		/// The balance of `address`.
+		fn balance(address: H160) -> U256;
+
+		/// Execute a call without applying its changes, returning its output.
+		fn call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+		) -> Result<Vec<u8>, DispatchError>;
+
+		/// The receipt of the transaction with the given hash.
+		fn transaction_receipt(hash: H256) -> Option<Receipt>;
+
+		/// Wrap a raw signed Ethereum transaction into an extrinsic.
+		fn transaction_extrinsic(raw: Vec<u8>) -> <Block as BlockT>::Extrinsic;
+	}
+}
@@ -0,0 +1,349 @@
+//! A subset of the Ethereum JSON-RPC interface, served from the EVM.
+//!
+//! Blocks are named like Ethereum tooling does: by hash, by number, or by one of the tags
+//! `latest`, `pending` and `earliest`. Transaction receipts are only kept by the runtime, so
+//! they are always looked up at the best block, and served in the format of Ethereum nodes. The
+//! runtime drops them after `ReceiptRetention` blocks, after which they are unknown.
+
+use std::sync::Arc;
+use std::marker::PhantomData;
+use ethereum_types::{Bloom, BloomInput};
+use futures::{FutureExt, TryFutureExt};
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use pallet_eth::Receipt;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H160, H256, U256, hashing::keccak_256};
+use sp_runtime::{
+	SaturatedConversion,
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor, UniqueSaturatedInto},
+};
+use sp_transaction_pool::{TransactionPool, TransactionSource};
+pub use pallet_eth_rpc_runtime_api::EthApi as EthRuntimeApi;
+
+/// The gas limit of `eth_call` requests that do not name one.
+const DEFAULT_GAS_LIMIT: u64 = 10_000_000;
+
+/// A block, as named by Ethereum tooling.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum BlockRef {
+	/// A block hash.
+	Hash(H256),
+	/// A block number.
+	Number(U256),
+	/// One of `latest`, `pending` or `earliest`.
+	Tag(String),
+}
+
+/// The arguments of an `eth_call`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest {
+	/// The sender of the call, the zero address if not given.
+	pub from: Option<H160>,
+	/// The called contract.
+	pub to: H160,
+	/// The gas limit of the call.
+	pub gas: Option<U256>,
+	/// The value transferred with the call.
+	pub value: Option<U256>,
+	/// The input data of the call.
+	pub data: Option<Bytes>,
+}
+
+/// A log of a transaction receipt.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Log {
+	/// The contract emitting the log.
+	pub address: H160,
+	/// The indexed topics of the log.
+	pub topics: Vec<H256>,
+	/// The data of the log.
+	pub data: Bytes,
+	/// The hash of the block of the transaction.
+	pub block_hash: H256,
+	/// The number of the block of the transaction.
+	pub block_number: U256,
+	/// The hash of the transaction.
+	pub transaction_hash: H256,
+	/// The index of the transaction in its block.
+	pub transaction_index: U256,
+	/// The index of the log in the receipt.
+	pub log_index: U256,
+	/// Whether the log was removed by a reorganization, which receipts never are.
+	pub removed: bool,
+}
+
+/// The receipt of an executed transaction, as Ethereum nodes serve it.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionReceipt {
+	/// The hash of the transaction.
+	pub transaction_hash: H256,
+	/// The index of the transaction in its block.
+	pub transaction_index: U256,
+	/// The hash of the block of the transaction.
+	pub block_hash: H256,
+	/// The number of the block of the transaction.
+	pub block_number: U256,
+	/// The sender of the transaction.
+	pub from: H160,
+	/// The called contract, or `None` for contract creations.
+	pub to: Option<H160>,
+	/// The gas used by the transactions of the block up to and including this one.
+	pub cumulative_gas_used: U256,
+	/// The gas used by the transaction.
+	pub gas_used: U256,
+	/// The address of the created contract, if the transaction created one.
+	pub contract_address: Option<H160>,
+	/// The logs emitted by the transaction.
+	pub logs: Vec<Log>,
+	/// The bloom filter of the addresses and topics of the logs.
+	pub logs_bloom: Bloom,
+	/// `1` if the execution succeeded, `0` otherwise.
+	pub status: U256,
+}
+
+impl TransactionReceipt {
+	/// The receipt of the transaction `hash`, executed in block `block_hash`.
+	pub fn new<BlockNumber: UniqueSaturatedInto<u64>>(
+		transaction_hash: H256,
+		block_hash: H256,
+		receipt: Receipt<BlockNumber>,
+	) -> Self {
+		let block_number = U256::from(receipt.block_number.unique_saturated_into());
+		let transaction_index = U256::from(receipt.transaction_index);
+		let mut logs_bloom = Bloom::default();
+		let logs = receipt.logs.into_iter().enumerate().map(|(index, log)| {
+			logs_bloom.accrue(BloomInput::Raw(&log.address[..]));
+			for topic in &log.topics {
+				logs_bloom.accrue(BloomInput::Raw(&topic[..]));
+			}
+			Log {
+				address: log.address,
+				topics: log.topics,
+				data: Bytes(log.data),
+				block_hash,
+				block_number,
+				transaction_hash,
+				transaction_index,
+				log_index: index.into(),
+				removed: false,
+			}
+		}).collect();
+
+		TransactionReceipt {
+			transaction_hash,
+			transaction_index,
+			block_hash,
+			block_number,
+			from: receipt.from,
+			to: receipt.to,
+			cumulative_gas_used: receipt.cumulative_gas_used,
+			gas_used: receipt.gas_used,
+			contract_address: receipt.contract_address,
+			logs,
+			logs_bloom,
+			status: if receipt.status { U256::one() } else { U256::zero() },
+		}
+	}
+}
+
+/// Ethereum RPC methods.
+#[rpc]
+pub trait EthApi {
+	/// Execute a call at the given block without creating a transaction.
+	#[rpc(name = "eth_call")]
+	fn call(&self, request: CallRequest, block: Option<BlockRef>) -> Result<Bytes>;
+
+	/// Submit a raw signed transaction to the transaction pool, returning its hash.
+	#[rpc(name = "eth_sendRawTransaction")]
+	fn send_raw_transaction(&self, raw: Bytes) -> BoxFuture<H256>;
+
+	/// The balance of `address` at the given block.
+	#[rpc(name = "eth_getBalance")]
+	fn balance(&self, address: H160, block: Option<BlockRef>) -> Result<U256>;
+
+	/// The number of the best block.
+	#[rpc(name = "eth_blockNumber")]
+	fn block_number(&self) -> Result<U256>;
+
+	/// The receipt of an executed transaction.
+	#[rpc(name = "eth_getTransactionReceipt")]
+	fn transaction_receipt(&self, hash: H256) -> Result<Option<TransactionReceipt>>;
+}
+
+/// A struct that implements the [`EthApi`].
+pub struct Eth<C, P, B> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, P, B> Eth<C, P, B> {
+	/// Create new `Eth` with the given reference to the client and the transaction pool.
+	pub fn new(client: Arc<C>, pool: Arc<P>) -> Self {
+		Eth { client, pool, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+	/// The requested block is not known.
+	UnknownBlock,
+	/// The executed call was reverted or failed.
+	ExecutionFailed,
+	/// The transaction pool rejected the transaction.
+	PoolError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::UnknownBlock => 2,
+			Error::ExecutionFailed => 3,
+			Error::PoolError => 4,
+		}
+	}
+}
+
+fn error(kind: Error, message: &str, e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(kind.into()),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, P, Block> Eth<C, P, Block> where
+	Block: BlockT<Hash = H256>,
+	C: HeaderBackend<Block>,
+{
+	fn block_id(&self, block: Option<BlockRef>) -> Result<BlockId<Block>> {
+		let hash = match block {
+			Some(BlockRef::Hash(hash)) => hash,
+			Some(BlockRef::Number(number)) => self.client
+				.hash(number.low_u64().saturated_into())
+				.map_err(|e| error(Error::UnknownBlock, "Unable to look up block.", e))?
+				.ok_or_else(|| error(Error::UnknownBlock, "Unknown block.", number))?,
+			Some(BlockRef::Tag(ref tag)) if tag == "earliest" => self.client.info().genesis_hash,
+			// `latest` and `pending` both refer to the best block.
+			_ => self.client.info().best_hash,
+		};
+		Ok(BlockId::hash(hash))
+	}
+}
+
+impl<C, P, Block> EthApi for Eth<C, P, Block>
+where
+	Block: BlockT<Hash = H256>,
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block>,
+	C::Api: EthRuntimeApi<Block, Receipt<NumberFor<Block>>>,
+	P: TransactionPool<Block = Block> + 'static,
+{
+	fn call(&self, request: CallRequest, block: Option<BlockRef>) -> Result<Bytes> {
+		let at = self.block_id(block)?;
+		let result = self.client.runtime_api().call(
+			&at,
+			request.from.unwrap_or_default(),
+			request.to,
+			request.data.map(|d| d.0).unwrap_or_default(),
+			request.value.unwrap_or_default(),
+			request.gas.unwrap_or_else(|| DEFAULT_GAS_LIMIT.into()),
+		).map_err(|e| error(Error::RuntimeError, "Unable to execute call.", e))?;
+
+		result
+			.map(Bytes)
+			.map_err(|e| error(Error::ExecutionFailed, "Execution failed.", e))
+	}
+
+	fn send_raw_transaction(&self, raw: Bytes) -> BoxFuture<H256> {
+		let hash = H256::from(keccak_256(&raw));
+		let at = BlockId::hash(self.client.info().best_hash);
+		let extrinsic = match self.client.runtime_api().transaction_extrinsic(&at, raw.0) {
+			Ok(extrinsic) => extrinsic,
+			Err(e) => return Box::new(futures::future::err(
+				error(Error::RuntimeError, "Unable to wrap transaction.", e)
+			).compat()),
+		};
+
+		Box::new(self.pool
+			.submit_one(&at, TransactionSource::External, extrinsic)
+			.map_ok(move |_| hash)
+			.map_err(|e| error(Error::PoolError, "Transaction was rejected.", e.to_string()))
+			.boxed()
+			.compat())
+	}
+
+	fn balance(&self, address: H160, block: Option<BlockRef>) -> Result<U256> {
+		let at = self.block_id(block)?;
+		self.client.runtime_api().balance(&at, address)
+			.map_err(|e| error(Error::RuntimeError, "Unable to query balance.", e))
+	}
+
+	fn block_number(&self) -> Result<U256> {
+		let number: u64 = self.client.info().best_number.unique_saturated_into();
+		Ok(number.into())
+	}
+
+	fn transaction_receipt(&self, hash: H256) -> Result<Option<TransactionReceipt>> {
+		let at = BlockId::hash(self.client.info().best_hash);
+		let receipt = match self.client.runtime_api().transaction_receipt(&at, hash)
+			.map_err(|e| error(Error::RuntimeError, "Unable to query receipt.", e))?
+		{
+			Some(receipt) => receipt,
+			None => return Ok(None),
+		};
+		let block_hash = self.client.hash(receipt.block_number)
+			.map_err(|e| error(Error::UnknownBlock, "Unable to look up block.", e))?
+			.ok_or_else(|| error(Error::UnknownBlock, "Unknown block.", receipt.block_number))?;
+		Ok(Some(TransactionReceipt::new(hash, block_hash, receipt)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn receipts_are_served_with_hex_quantities() {
+		let receipt = Receipt {
+			block_number: 7u32,
+			transaction_index: 2,
+			from: H160::repeat_byte(1),
+			to: Some(H160::repeat_byte(2)),
+			contract_address: None,
+			gas_used: 21_000.into(),
+			cumulative_gas_used: 42_000.into(),
+			logs: vec![pallet_eth::Log {
+				address: H160::repeat_byte(2),
+				topics: vec![H256::repeat_byte(3)],
+				data: vec![1, 2],
+			}],
+			status: true,
+		};
+		let receipt = TransactionReceipt::new(H256::repeat_byte(4), H256::repeat_byte(5), receipt);
+		let json = serde_json::to_value(&receipt).unwrap();
+
+		assert_eq!(json["transactionIndex"], "0x2");
+		assert_eq!(json["blockNumber"], "0x7");
+		assert_eq!(json["blockHash"], format!("{:?}", H256::repeat_byte(5)));
+		assert_eq!(json["gasUsed"], "0x5208");
+		assert_eq!(json["cumulativeGasUsed"], "0xa410");
+		assert_eq!(json["contractAddress"], serde_json::Value::Null);
+		assert_eq!(json["status"], "0x1");
+		assert_eq!(json["logs"][0]["data"], "0x0102");
+		assert_eq!(json["logs"][0]["logIndex"], "0x0");
+		assert!(receipt.logs_bloom.contains_input(BloomInput::Raw(&H256::repeat_byte(3)[..])));
+	}
+}
@@ -0,0 +1,354 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// A pallet executing signed Ethereum transactions on top of `pallet_evm`.
+///
+/// Legacy Ethereum transactions are submitted as their raw RLP encoding through the unsigned
+/// `transact` call, so that existing Ethereum tooling can sign them. The sender is recovered from
+/// the signature and mapped to an account by the EVM's `AddressMapping`, which also pays for the
+/// gas. A receipt of every executed transaction is kept for lookups by transaction hash, for the
+/// last `ReceiptRetention` blocks only so that the state does not grow without bound.
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use ethereum::{Transaction, TransactionAction, TransactionMessage};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, dispatch, ensure,
+	traits::{Currency, ExistenceRequirement, Get, WithdrawReason},
+	weights::{Weight, DispatchClass, Pays},
+};
+use frame_system::ensure_none;
+use pallet_evm::{AddressMapping, ExitReason, FeeCalculator};
+use sp_core::{H160, H256, U256};
+use sp_runtime::{
+	DispatchError, RuntimeDebug,
+	traits::{CheckedSub, UniqueSaturatedInto, Zero},
+	transaction_validity::{
+		TransactionValidity, TransactionSource, InvalidTransaction, ValidTransaction,
+	},
+};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// A log emitted by a contract.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Log {
+	/// The contract emitting the log.
+	pub address: H160,
+	/// The indexed topics of the log.
+	pub topics: Vec<H256>,
+	/// The data of the log.
+	pub data: Vec<u8>,
+}
+
+/// The receipt of an executed Ethereum transaction.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Receipt<BlockNumber> {
+	/// The block the transaction was executed in.
+	pub block_number: BlockNumber,
+	/// The index of the extrinsic of the transaction in its block.
+	pub transaction_index: u32,
+	/// The sender of the transaction.
+	pub from: H160,
+	/// The called contract, or `None` for contract creations.
+	pub to: Option<H160>,
+	/// The address of the created contract, if the transaction created one.
+	pub contract_address: Option<H160>,
+	/// The gas used by the execution.
+	pub gas_used: U256,
+	/// The gas used by the Ethereum transactions of the block up to and including this one.
+	pub cumulative_gas_used: U256,
+	/// The logs emitted by a successful execution.
+	pub logs: Vec<Log>,
+	/// Whether the execution succeeded.
+	pub status: bool,
+}
+
+type AccountIdOf<T> = <T as frame_system::Trait>::AccountId;
+type BalanceOf<T> = <<T as pallet_evm::Trait>::Currency as Currency<AccountIdOf<T>>>::Balance;
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Trait: pallet_evm::Trait {
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The weight charged per unit of gas of a transaction's gas limit.
+	type WeightPerGas: Get<Weight>;
+
+	/// The number of blocks the receipts of a block are kept for.
+	type ReceiptRetention: Get<Self::BlockNumber>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Eth {
+		/// Receipts of executed transactions, by transaction hash.
+		Receipts get(fn receipt): map hasher(identity) H256 => Option<Receipt<T::BlockNumber>>;
+		/// The hashes of the transactions executed in a block, whose receipts are kept.
+		BlockReceipts: map hasher(twox_64_concat) T::BlockNumber => Vec<H256>;
+		/// The gas used by the Ethereum transactions of the current block.
+		BlockGasUsed: U256;
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// An Ethereum transaction was executed. [from, hash, succeeded]
+		Executed(H160, H256, bool),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The transaction is not a valid RLP encoded legacy transaction.
+		Undecodable,
+		/// The sender could not be recovered from the signature.
+		InvalidSignature,
+		/// The transaction was signed for a different chain.
+		WrongChainId,
+		/// The execution of a call was reverted or failed.
+		Reverted,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The weight charged per unit of gas of a transaction's gas limit.
+		const WeightPerGas: Weight = T::WeightPerGas::get();
+
+		/// The number of blocks the receipts of a block are kept for.
+		const ReceiptRetention: T::BlockNumber = T::ReceiptRetention::get();
+
+		fn deposit_event() = default;
+
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			// The receipts of a block are as many as its transactions, which their gas bounds.
+			let expired = match n.checked_sub(&T::ReceiptRetention::get()) {
+				Some(expired) if !expired.is_zero() => BlockReceipts::<T>::take(expired),
+				_ => Vec::new(),
+			};
+			for hash in &expired {
+				Receipts::<T>::remove(hash);
+			}
+			T::DbWeight::get().reads_writes(1, 2 + expired.len() as Weight)
+		}
+
+		fn on_finalize() {
+			BlockGasUsed::kill();
+		}
+
+		/// Execute a signed Ethereum transaction, given as its raw RLP encoding.
+		///
+		/// The gas is paid by the sender of the transaction, so the extrinsic itself is free.
+		#[weight = (Module::<T>::transaction_weight(raw), DispatchClass::Normal, Pays::No)]
+		pub fn transact(origin, raw: Vec<u8>) -> dispatch::DispatchResult {
+			ensure_none(origin)?;
+
+			let transaction = Self::decode(&raw).ok_or(Error::<T>::Undecodable)?;
+			ensure!(
+				transaction.signature.chain_id() == Some(T::ChainId::get()),
+				Error::<T>::WrongChainId
+			);
+			let from = Self::recover_signer(&transaction).ok_or(Error::<T>::InvalidSignature)?;
+			let hash = H256::from(sp_io::hashing::keccak_256(&raw));
+			let gas_limit = Self::gas_limit(&transaction);
+			let to = match transaction.action {
+				TransactionAction::Call(target) => Some(target),
+				TransactionAction::Create => None,
+			};
+
+			let executed = match to {
+				Some(target) => pallet_evm::Module::<T>::execute_call(
+					from,
+					target,
+					transaction.input.clone(),
+					transaction.value,
+					gas_limit,
+					transaction.gas_price,
+					Some(transaction.nonce),
+					true,
+				).map(|(reason, _, gas_used, logs)| {
+					(Self::succeeded(&reason), None, gas_used, logs)
+				}),
+				None => pallet_evm::Module::<T>::execute_create(
+					from,
+					transaction.input.clone(),
+					transaction.value,
+					gas_limit,
+					transaction.gas_price,
+					Some(transaction.nonce),
+					true,
+				).map(|(reason, address, gas_used, logs)| {
+					let status = Self::succeeded(&reason);
+					(status, if status { Some(address) } else { None }, gas_used, logs)
+				}),
+			};
+			let (status, contract_address, gas_used, logs) = match executed {
+				Ok((status, contract_address, gas_used, logs)) => {
+					let logs = if status {
+						logs.into_iter().map(|log| Log {
+							address: log.address,
+							topics: log.topics,
+							data: log.data,
+						}).collect()
+					} else {
+						Vec::new()
+					};
+					(status, contract_address, gas_used, logs)
+				},
+				// The EVM refused to execute the transaction, e.g. because the balance of the
+				// sender no longer covers its gas since it was validated. It is included all the
+				// same, so it fails like an execution running out of gas would.
+				Err(_) => {
+					Self::charge_gas_limit(from, &transaction);
+					(false, None, gas_limit.into(), Vec::new())
+				},
+			};
+
+			let cumulative_gas_used = BlockGasUsed::mutate(|used| {
+				*used = used.saturating_add(gas_used);
+				*used
+			});
+			let block_number = frame_system::Module::<T>::block_number();
+			BlockReceipts::<T>::append(block_number, hash);
+			Receipts::<T>::insert(hash, Receipt {
+				block_number,
+				transaction_index: frame_system::Module::<T>::extrinsic_index().unwrap_or_default(),
+				from,
+				to,
+				contract_address,
+				gas_used,
+				cumulative_gas_used,
+				logs,
+				status,
+			});
+			Self::deposit_event(Event::Executed(from, hash, status));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Decode a raw legacy transaction.
+	pub fn decode(raw: &[u8]) -> Option<Transaction> {
+		rlp::decode::<Transaction>(raw).ok()
+	}
+
+	/// The address that signed `transaction`.
+	pub fn recover_signer(transaction: &Transaction) -> Option<H160> {
+		let mut sig = [0u8; 65];
+		sig[0..32].copy_from_slice(&transaction.signature.r()[..]);
+		sig[32..64].copy_from_slice(&transaction.signature.s()[..]);
+		sig[64] = transaction.signature.standard_v();
+		let msg = TransactionMessage::from(transaction.clone()).hash();
+
+		let pubkey = sp_io::crypto::secp256k1_ecdsa_recover(&sig, msg.as_fixed_bytes()).ok()?;
+		Some(H160::from(H256::from(sp_io::hashing::keccak_256(&pubkey))))
+	}
+
+	/// Execute a call without applying its changes, returning its output.
+	pub fn call(
+		from: H160,
+		to: H160,
+		data: Vec<u8>,
+		value: U256,
+		gas_limit: U256,
+	) -> Result<Vec<u8>, DispatchError> {
+		let gas_limit = gas_limit.min(u32::max_value().into()).low_u32();
+		let (reason, output, _, _) = pallet_evm::Module::<T>::execute_call(
+			from,
+			to,
+			data,
+			value,
+			gas_limit,
+			T::FeeCalculator::min_gas_price(),
+			None,
+			false,
+		)?;
+		ensure!(Self::succeeded(&reason), Error::<T>::Reverted);
+		Ok(output)
+	}
+
+	/// The EVM balance of `address`.
+	pub fn balance(address: H160) -> U256 {
+		pallet_evm::Module::<T>::account_basic(&address).balance
+	}
+
+	/// Charge the sender of `transaction` as much of its gas limit as it can pay, and use up the
+	/// nonce of the transaction.
+	fn charge_gas_limit(from: H160, transaction: &Transaction) {
+		let account = T::AddressMapping::into_account_id(from);
+		let fee = transaction.gas_price.saturating_mul(Self::gas_limit(transaction).into());
+		let fee: BalanceOf<T> = fee.min(u128::max_value().into()).low_u128()
+			.unique_saturated_into();
+		let fee = fee.min(T::Currency::free_balance(&account));
+		// a locked balance cannot pay, the transaction still fails.
+		let _ = T::Currency::withdraw(
+			&account,
+			fee,
+			WithdrawReason::Fee.into(),
+			ExistenceRequirement::AllowDeath,
+		);
+		if transaction.nonce == pallet_evm::Module::<T>::account_basic(&from).nonce {
+			frame_system::Module::<T>::inc_account_nonce(&account);
+		}
+	}
+
+	fn gas_limit(transaction: &Transaction) -> u32 {
+		transaction.gas_limit.min(u32::max_value().into()).low_u32()
+	}
+
+	fn succeeded(reason: &ExitReason) -> bool {
+		matches!(reason, ExitReason::Succeed(_))
+	}
+
+	fn transaction_weight(raw: &[u8]) -> Weight {
+		let gas_limit = Self::decode(raw).map(|t| Self::gas_limit(&t)).unwrap_or_default();
+		T::WeightPerGas::get().saturating_mul(gas_limit as Weight)
+			.saturating_add(T::DbWeight::get().reads_writes(3, 5))
+	}
+}
+
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		let raw = match call {
+			Call::transact(raw) => raw,
+			_ => return InvalidTransaction::Call.into(),
+		};
+
+		let transaction = Self::decode(raw).ok_or(InvalidTransaction::Call)?;
+		ensure!(
+			transaction.signature.chain_id() == Some(T::ChainId::get()),
+			InvalidTransaction::BadProof
+		);
+		let from = Self::recover_signer(&transaction).ok_or(InvalidTransaction::BadProof)?;
+
+		let account = pallet_evm::Module::<T>::account_basic(&from);
+		ensure!(transaction.nonce >= account.nonce, InvalidTransaction::Stale);
+		ensure!(
+			transaction.gas_price >= T::FeeCalculator::min_gas_price(),
+			InvalidTransaction::Payment
+		);
+		let fee = transaction.gas_price.saturating_mul(transaction.gas_limit);
+		ensure!(
+			account.balance >= fee.saturating_add(transaction.value),
+			InvalidTransaction::Payment
+		);
+
+		let mut builder = ValidTransaction::with_tag_prefix("Eth")
+			.priority(transaction.gas_price.min(u64::max_value().into()).low_u64())
+			.and_provides((from, transaction.nonce))
+			.propagate(true);
+		// later transactions of the same sender wait in the pool for the earlier ones.
+		if transaction.nonce > account.nonce {
+			builder = builder.and_requires((from, transaction.nonce - U256::one()));
+		}
+		builder.build()
+	}
+}
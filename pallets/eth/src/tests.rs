@@ -0,0 +1,225 @@
+use crate::{Error, Receipt, mock::*};
+use ethereum::{Transaction, TransactionAction, TransactionMessage, TransactionSignature};
+use frame_support::{
+	assert_ok, assert_noop,
+	traits::{Currency, OnFinalize, OnInitialize},
+	unsigned::ValidateUnsigned,
+};
+use sp_core::{H160, H256, U256};
+use sp_runtime::transaction_validity::{TransactionSource, InvalidTransaction};
+
+/// Deploys a contract answering every call with 42, see `fixtures/answer.easm`.
+const ANSWER: &str = include_str!("../fixtures/answer.hex");
+/// Deploys a contract reverting every call, see `fixtures/revert.easm`.
+const REVERT: &str = include_str!("../fixtures/revert.hex");
+
+const GAS_LIMIT: u64 = 1_000_000;
+
+fn bytecode(hex: &str) -> Vec<u8> {
+	sp_core::bytes::from_hex(hex.trim()).expect("fixtures are valid hex; qed")
+}
+
+fn secret(seed: u8) -> secp256k1::SecretKey {
+	secp256k1::SecretKey::parse(&[seed; 32]).expect("seed is a valid secret key; qed")
+}
+
+fn address(secret: &secp256k1::SecretKey) -> H160 {
+	let public = secp256k1::PublicKey::from_secret_key(secret).serialize();
+	H160::from(H256::from(sp_io::hashing::keccak_256(&public[1..])))
+}
+
+/// A raw legacy transaction signed by `secret` for `chain_id`.
+fn signed(
+	secret: &secp256k1::SecretKey,
+	chain_id: u64,
+	nonce: u64,
+	action: TransactionAction,
+	input: Vec<u8>,
+) -> Vec<u8> {
+	let message = TransactionMessage {
+		nonce: nonce.into(),
+		gas_price: 1.into(),
+		gas_limit: GAS_LIMIT.into(),
+		action,
+		value: U256::zero(),
+		input,
+		chain_id: Some(chain_id),
+	};
+	let hash = secp256k1::Message::parse(message.hash().as_fixed_bytes());
+	let (signature, recovery_id) = secp256k1::sign(&hash, secret);
+	let signature = signature.serialize();
+	let transaction = Transaction {
+		nonce: message.nonce,
+		gas_price: message.gas_price,
+		gas_limit: message.gas_limit,
+		action: message.action,
+		value: message.value,
+		input: message.input,
+		signature: TransactionSignature::new(
+			recovery_id.serialize() as u64 + chain_id * 2 + 35,
+			H256::from_slice(&signature[0..32]),
+			H256::from_slice(&signature[32..64]),
+		).expect("signature is valid; qed"),
+	};
+	rlp::encode(&transaction)
+}
+
+fn hash_of(raw: &[u8]) -> H256 {
+	H256::from(sp_io::hashing::keccak_256(raw))
+}
+
+/// Deploy `code` from the account of `secret` and return the contract address.
+fn deploy(secret: &secp256k1::SecretKey, nonce: u64, code: &str) -> H160 {
+	let raw = signed(secret, ChainId::get(), nonce, TransactionAction::Create, bytecode(code));
+	assert_ok!(Eth::transact(Origin::none(), raw.clone()));
+	Eth::receipt(hash_of(&raw)).and_then(|r| r.contract_address).expect("contract was created")
+}
+
+#[test]
+fn signer_is_recovered() {
+	let alice = secret(1);
+	let raw = signed(&alice, ChainId::get(), 0, TransactionAction::Create, vec![]);
+	let transaction = Eth::decode(&raw).unwrap();
+	assert_eq!(Eth::recover_signer(&transaction), Some(address(&alice)));
+}
+
+#[test]
+fn deploy_and_call_a_contract() {
+	let alice = secret(1);
+	new_test_ext(&[address(&alice)]).execute_with(|| {
+		let raw = signed(&alice, ChainId::get(), 0, TransactionAction::Create, bytecode(ANSWER));
+		assert_ok!(Eth::transact(Origin::none(), raw.clone()));
+
+		let receipt = Eth::receipt(hash_of(&raw)).unwrap();
+		let contract = receipt.contract_address.unwrap();
+		assert_eq!(receipt, Receipt {
+			block_number: 1,
+			transaction_index: 0,
+			from: address(&alice),
+			to: None,
+			contract_address: Some(contract),
+			gas_used: receipt.gas_used,
+			cumulative_gas_used: receipt.gas_used,
+			logs: vec![],
+			status: true,
+		});
+		// the gas was paid by the account the sender maps to.
+		assert_eq!(
+			Balances::free_balance(account_of(address(&alice))),
+			10_000_000 - receipt.gas_used.low_u64(),
+		);
+
+		let output = Eth::call(address(&alice), contract, vec![], U256::zero(), GAS_LIMIT.into());
+		assert_eq!(output.map(|o| U256::from_big_endian(&o)), Ok(42.into()));
+	});
+}
+
+#[test]
+fn reverted_call_is_recorded() {
+	let alice = secret(1);
+	new_test_ext(&[address(&alice)]).execute_with(|| {
+		let contract = deploy(&alice, 0, REVERT);
+
+		let raw = signed(&alice, ChainId::get(), 1, TransactionAction::Call(contract), vec![]);
+		assert_ok!(Eth::transact(Origin::none(), raw.clone()));
+		let receipt = Eth::receipt(hash_of(&raw)).unwrap();
+		assert_eq!(receipt.to, Some(contract));
+		assert!(!receipt.status);
+
+		assert_eq!(
+			Eth::call(address(&alice), contract, vec![], U256::zero(), GAS_LIMIT.into()),
+			Err(Error::<Test>::Reverted.into()),
+		);
+	});
+}
+
+#[test]
+fn cumulative_gas_adds_up_within_a_block() {
+	let alice = secret(1);
+	new_test_ext(&[address(&alice)]).execute_with(|| {
+		let contract = deploy(&alice, 0, ANSWER);
+		let raw = signed(&alice, ChainId::get(), 1, TransactionAction::Call(contract), vec![]);
+		assert_ok!(Eth::transact(Origin::none(), raw.clone()));
+
+		let receipt = Eth::receipt(hash_of(&raw)).unwrap();
+		let deployed = receipt.cumulative_gas_used - receipt.gas_used;
+		assert!(!deployed.is_zero());
+
+		Eth::on_finalize(1);
+		System::set_block_number(2);
+		let raw = signed(&alice, ChainId::get(), 2, TransactionAction::Call(contract), vec![]);
+		assert_ok!(Eth::transact(Origin::none(), raw.clone()));
+		let receipt = Eth::receipt(hash_of(&raw)).unwrap();
+		assert_eq!(receipt.cumulative_gas_used, receipt.gas_used);
+	});
+}
+
+#[test]
+fn receipts_expire_after_the_retention() {
+	let alice = secret(1);
+	new_test_ext(&[address(&alice)]).execute_with(|| {
+		let raw = signed(&alice, ChainId::get(), 0, TransactionAction::Create, bytecode(ANSWER));
+		assert_ok!(Eth::transact(Origin::none(), raw.clone()));
+
+		Eth::on_initialize(2);
+		assert!(Eth::receipt(hash_of(&raw)).is_some());
+		Eth::on_initialize(3);
+		assert_eq!(Eth::receipt(hash_of(&raw)), None);
+	});
+}
+
+#[test]
+fn refused_execution_is_recorded_and_charged() {
+	let alice = secret(1);
+	new_test_ext(&[address(&alice)]).execute_with(|| {
+		// the balance covered the gas limit when the transaction was validated, but not anymore.
+		Balances::make_free_balance_be(&account_of(address(&alice)), GAS_LIMIT / 2);
+		let raw = signed(&alice, ChainId::get(), 0, TransactionAction::Create, bytecode(ANSWER));
+		assert_ok!(Eth::transact(Origin::none(), raw.clone()));
+
+		let receipt = Eth::receipt(hash_of(&raw)).unwrap();
+		assert!(!receipt.status);
+		assert_eq!(receipt.contract_address, None);
+		assert_eq!(receipt.gas_used, GAS_LIMIT.into());
+		assert_eq!(Balances::free_balance(account_of(address(&alice))), 0);
+		// the nonce is used up, so the transaction cannot be included again.
+		assert_eq!(pallet_evm::Module::<Test>::account_basic(&address(&alice)).nonce, 1.into());
+	});
+}
+
+#[test]
+fn transaction_for_another_chain_is_rejected() {
+	let alice = secret(1);
+	new_test_ext(&[address(&alice)]).execute_with(|| {
+		let raw = signed(&alice, ChainId::get() + 1, 0, TransactionAction::Create, bytecode(ANSWER));
+		assert_noop!(Eth::transact(Origin::none(), raw.clone()), Error::<Test>::WrongChainId);
+		assert_eq!(
+			Eth::validate_unsigned(TransactionSource::External, &crate::Call::transact(raw)),
+			InvalidTransaction::BadProof.into(),
+		);
+	});
+}
+
+#[test]
+fn validation_checks_nonce_and_funds() {
+	let alice = secret(1);
+	let bob = secret(2);
+	new_test_ext(&[address(&alice)]).execute_with(|| {
+		let validate = |raw: Vec<u8>| {
+			Eth::validate_unsigned(TransactionSource::External, &crate::Call::transact(raw))
+		};
+		let create = |secret, nonce| {
+			signed(secret, ChainId::get(), nonce, TransactionAction::Create, bytecode(ANSWER))
+		};
+
+		let first = validate(create(&alice, 0)).unwrap();
+		assert!(first.requires.is_empty());
+		// a future nonce waits for the one before it.
+		let second = validate(create(&alice, 1)).unwrap();
+		assert_eq!(second.requires, first.provides);
+
+		deploy(&alice, 0, ANSWER);
+		assert_eq!(validate(create(&alice, 0)), InvalidTransaction::Stale.into());
+		assert_eq!(validate(create(&bob, 0)), InvalidTransaction::Payment.into());
+	});
+}
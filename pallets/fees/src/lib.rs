@@ -0,0 +1,97 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// A pallet that owns the treasury pot of the chain. The runtime routes dust and transaction
+/// fees into it (and to block authors) through `OnUnbalanced` handlers, and every distribution
+/// is reported with an event so that it can be followed off-chain.
+
+use frame_support::{
+	decl_module, decl_event,
+	traits::{Currency, Get, Imbalance, OnUnbalanced},
+};
+use sp_runtime::{ModuleId, traits::{AccountIdConversion, Zero}};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Trait: frame_system::Trait {
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency in which dust and fees are collected.
+	type Currency: Currency<Self::AccountId>;
+
+	/// The treasury's module id, used for deriving its sovereign account ID.
+	type ModuleId: Get<ModuleId>;
+}
+
+decl_event!(
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+	{
+		/// Some funds have been deposited into the treasury pot. [deposit]
+		TreasuryDeposit(Balance),
+		/// The author of the current block received a share of the fees. [author, reward]
+		AuthorRewarded(AccountId, Balance),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The treasury's module id, used for deriving its sovereign account ID.
+		const ModuleId: ModuleId = T::ModuleId::get();
+
+		fn deposit_event() = default;
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account ID of the treasury pot.
+	///
+	/// This actually does computation. If you need to keep using it, then make sure you cache the
+	/// value and only call this once.
+	pub fn account_id() -> T::AccountId {
+		T::ModuleId::get().into_account()
+	}
+
+	/// The current balance of the treasury pot.
+	pub fn pot() -> BalanceOf<T> {
+		T::Currency::free_balance(&Self::account_id())
+	}
+
+	/// Hand `amount` to the block `author` and report it.
+	pub fn reward_author(author: &T::AccountId, amount: NegativeImbalanceOf<T>) {
+		let credited = Self::credit(author, amount);
+		if !credited.is_zero() {
+			Self::deposit_event(RawEvent::AuthorRewarded(author.clone(), credited));
+		}
+	}
+
+	/// Credit `amount` to `who` and return what was credited, which is nothing when `who` does
+	/// not exist and `amount` is below the existential deposit. What is not credited is burnt.
+	fn credit(who: &T::AccountId, amount: NegativeImbalanceOf<T>) -> BalanceOf<T> {
+		let deposit = T::Currency::deposit_creating(who, amount.peek());
+		let credited = deposit.peek();
+		// The remainder of `amount`, if any, is dropped and so reduces the total issuance.
+		let _ = amount.offset(deposit);
+		credited
+	}
+}
+
+impl<T: Trait> OnUnbalanced<NegativeImbalanceOf<T>> for Module<T> {
+	fn on_nonzero_unbalanced(amount: NegativeImbalanceOf<T>) {
+		let credited = Self::credit(&Self::account_id(), amount);
+		if !credited.is_zero() {
+			Self::deposit_event(RawEvent::TreasuryDeposit(credited));
+		}
+	}
+}
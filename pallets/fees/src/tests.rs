@@ -0,0 +1,48 @@
+use crate::{RawEvent, mock::*};
+use frame_support::traits::{Currency, OnUnbalanced};
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+#[test]
+fn unbalanced_funds_go_to_the_pot() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		assert_eq!(Fees::pot(), 0);
+
+		Fees::on_unbalanced(Balances::issue(10));
+
+		assert_eq!(Fees::pot(), 10);
+		assert_eq!(Balances::total_issuance(), issuance + 10);
+		assert_eq!(last_event(), TestEvent::fees(RawEvent::TreasuryDeposit(10)));
+	});
+}
+
+#[test]
+fn author_reward_is_credited_and_reported() {
+	new_test_ext().execute_with(|| {
+		Fees::reward_author(&2, Balances::issue(5));
+
+		assert_eq!(Balances::free_balance(2), 105);
+		assert_eq!(Fees::pot(), 0);
+		assert_eq!(last_event(), TestEvent::fees(RawEvent::AuthorRewarded(2, 5)));
+	});
+}
+
+#[test]
+fn dust_is_burnt_without_being_reported() {
+	new_test_ext().execute_with(|| {
+		let issuance = Balances::total_issuance();
+		let events = System::events().len();
+
+		// Both the pot and account 9 do not exist, and 3 is below the existential deposit.
+		Fees::on_unbalanced(Balances::issue(3));
+		Fees::reward_author(&9, Balances::issue(3));
+
+		assert_eq!(Fees::pot(), 0);
+		assert_eq!(Balances::free_balance(9), 0);
+		assert_eq!(Balances::total_issuance(), issuance);
+		assert_eq!(System::events().len(), events);
+	});
+}
@@ -0,0 +1,16 @@
+//! Runtime API definition for resolving on-chain identities.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Resolve accounts to the identities registered with `pallet_identity`.
+	pub trait IdentityApi<AccountId> where
+		AccountId: Codec,
+	{
+		/// The display name of `who`, if it has one.
+		fn display_name(who: AccountId) -> Option<Vec<u8>>;
+	}
+}
@@ -0,0 +1,81 @@
+//! RPC interface for resolving on-chain identities.
+//!
+//! Saves front-ends from decoding `pallet_identity` storage: an account resolves to its display
+//! name in a single call.
+
+use std::sync::Arc;
+use std::marker::PhantomData;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+pub use pallet_identity_rpc_runtime_api::IdentityApi as IdentityRuntimeApi;
+
+/// Identity RPC methods.
+#[rpc]
+pub trait IdentityApi<BlockHash, AccountId> {
+	/// The display name of `who` at the given block, or at the best block.
+	///
+	/// Sub-accounts resolve to `parent/sub`. Names that are not valid UTF-8 are converted
+	/// lossily.
+	#[rpc(name = "identity_displayName")]
+	fn display_name(&self, who: AccountId, at: Option<BlockHash>) -> Result<Option<String>>;
+}
+
+/// A struct that implements the [`IdentityApi`].
+pub struct Identity<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> Identity<C, B> {
+	/// Create new `Identity` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Identity { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+impl<C, Block, AccountId> IdentityApi<<Block as BlockT>::Hash, AccountId> for Identity<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block>,
+	C::Api: IdentityRuntimeApi<Block, AccountId>,
+	AccountId: Codec,
+{
+	fn display_name(
+		&self,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<String>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		let name = api.display_name(&at, who).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query display name.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+		Ok(name.map(|name| String::from_utf8_lossy(&name).into_owned()))
+	}
+}
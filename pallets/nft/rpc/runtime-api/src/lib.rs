@@ -0,0 +1,18 @@
+//! Runtime API definition for the NFT pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Query the non-fungible tokens held by accounts.
+	pub trait NftApi<AccountId, CollectionId, TokenId> where
+		AccountId: Codec,
+		CollectionId: Codec,
+		TokenId: Codec,
+	{
+		/// All tokens owned by `who`, as `(collection, token)` pairs.
+		fn tokens_of(who: AccountId) -> Vec<(CollectionId, TokenId)>;
+	}
+}
@@ -0,0 +1,81 @@
+//! RPC interface for the NFT pallet.
+
+use std::sync::Arc;
+use std::marker::PhantomData;
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+pub use pallet_nft_rpc_runtime_api::NftApi as NftRuntimeApi;
+
+/// NFT RPC methods.
+#[rpc]
+pub trait NftApi<BlockHash, AccountId, CollectionId, TokenId> {
+	/// All tokens owned by `who` at the given block, or at the best block.
+	#[rpc(name = "nft_tokensOf")]
+	fn tokens_of(
+		&self,
+		who: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Vec<(CollectionId, TokenId)>>;
+}
+
+/// A struct that implements the [`NftApi`].
+pub struct Nft<C, B> {
+	client: Arc<C>,
+	_marker: PhantomData<B>,
+}
+
+impl<C, B> Nft<C, B> {
+	/// Create new `Nft` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Nft { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+impl<C, Block, AccountId, CollectionId, TokenId>
+	NftApi<<Block as BlockT>::Hash, AccountId, CollectionId, TokenId> for Nft<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block>,
+	C::Api: NftRuntimeApi<Block, AccountId, CollectionId, TokenId>,
+	AccountId: Codec,
+	CollectionId: Codec,
+	TokenId: Codec,
+{
+	fn tokens_of(
+		&self,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(CollectionId, TokenId)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		api.tokens_of(&at, who).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query tokens.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
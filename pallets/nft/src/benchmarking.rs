@@ -0,0 +1,148 @@
+//! Benchmarks for the NFT pallet.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use sp_std::prelude::*;
+use frame_system::RawOrigin;
+use frame_benchmarking::{benchmarks, account, whitelisted_caller};
+use sp_runtime::traits::{Bounded, StaticLookup};
+
+use crate::Module as Nft;
+
+const SEED: u32 = 0;
+
+fn funded_caller<T: Trait>() -> T::AccountId {
+	let caller: T::AccountId = whitelisted_caller();
+	T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+	caller
+}
+
+fn lookup_of<T: Trait>(who: T::AccountId) -> <T::Lookup as StaticLookup>::Source {
+	T::Lookup::unlookup(who)
+}
+
+/// Create a collection owned by a funded caller, with one token minted to the caller.
+fn collection_with_token<T: Trait>() -> (T::AccountId, CollectionId, TokenId) {
+	let caller = funded_caller::<T>();
+	let collection = Nft::<T>::next_collection_id();
+	let origin: T::Origin = RawOrigin::Signed(caller.clone()).into();
+	Nft::<T>::create_collection(origin.clone()).expect("caller is funded; qed");
+	Nft::<T>::mint(origin, collection, lookup_of::<T>(caller.clone()))
+		.expect("caller owns the collection; qed");
+	(caller, collection, 0)
+}
+
+fn add_metadata<T: Trait>(caller: &T::AccountId, collection: CollectionId, token: TokenId) {
+	let data = vec![0u8; T::StringLimit::get() as usize];
+	Nft::<T>::set_metadata(RawOrigin::Signed(caller.clone()).into(), collection, token, data)
+		.expect("caller owns the collection; qed");
+}
+
+benchmarks! {
+	_ { }
+
+	create_collection {
+		let caller = funded_caller::<T>();
+		let collection = Nft::<T>::next_collection_id();
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert_eq!(Nft::<T>::collection(collection).map(|c| c.owner), Some(caller));
+	}
+
+	destroy_collection {
+		let caller = funded_caller::<T>();
+		let collection = Nft::<T>::next_collection_id();
+		Nft::<T>::create_collection(RawOrigin::Signed(caller.clone()).into())?;
+	}: _(RawOrigin::Signed(caller), collection)
+	verify {
+		assert!(Nft::<T>::collection(collection).is_none());
+	}
+
+	mint {
+		let caller = funded_caller::<T>();
+		let collection = Nft::<T>::next_collection_id();
+		Nft::<T>::create_collection(RawOrigin::Signed(caller.clone()).into())?;
+		let owner: T::AccountId = account("owner", 0, SEED);
+	}: _(RawOrigin::Signed(caller), collection, lookup_of::<T>(owner.clone()))
+	verify {
+		assert!(Nft::<T>::tokens_of(owner).contains(&(collection, 0)));
+	}
+
+	burn {
+		let (caller, collection, token) = collection_with_token::<T>();
+		add_metadata::<T>(&caller, collection, token);
+	}: _(RawOrigin::Signed(caller.clone()), collection, token)
+	verify {
+		assert!(Nft::<T>::token(collection, token).is_none());
+		assert!(Nft::<T>::metadata(collection, token).is_none());
+	}
+
+	transfer {
+		let (caller, collection, token) = collection_with_token::<T>();
+		let dest: T::AccountId = account("dest", 0, SEED);
+	}: _(RawOrigin::Signed(caller), collection, token, lookup_of::<T>(dest.clone()))
+	verify {
+		assert_eq!(Nft::<T>::token(collection, token).map(|t| t.owner), Some(dest));
+	}
+
+	approve {
+		let (caller, collection, token) = collection_with_token::<T>();
+		let delegate: T::AccountId = account("delegate", 0, SEED);
+	}: _(RawOrigin::Signed(caller), collection, token, lookup_of::<T>(delegate.clone()))
+	verify {
+		assert_eq!(Nft::<T>::token(collection, token).and_then(|t| t.approved), Some(delegate));
+	}
+
+	cancel_approval {
+		let (caller, collection, token) = collection_with_token::<T>();
+		let delegate: T::AccountId = account("delegate", 0, SEED);
+		Nft::<T>::approve(
+			RawOrigin::Signed(caller.clone()).into(),
+			collection,
+			token,
+			lookup_of::<T>(delegate),
+		)?;
+	}: _(RawOrigin::Signed(caller), collection, token)
+	verify {
+		assert_eq!(Nft::<T>::token(collection, token).and_then(|t| t.approved), None);
+	}
+
+	set_metadata {
+		let b in 0 .. T::StringLimit::get();
+		let (caller, collection, token) = collection_with_token::<T>();
+	}: _(RawOrigin::Signed(caller), collection, token, vec![0u8; b as usize])
+	verify {
+		assert_eq!(Nft::<T>::metadata(collection, token).map(|m| m.data.len()), Some(b as usize));
+	}
+
+	clear_metadata {
+		let (caller, collection, token) = collection_with_token::<T>();
+		add_metadata::<T>(&caller, collection, token);
+	}: _(RawOrigin::Signed(caller), collection, token)
+	verify {
+		assert!(Nft::<T>::metadata(collection, token).is_none());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{new_test_ext, Test};
+	use frame_support::assert_ok;
+
+	#[test]
+	fn test_benchmarks() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_create_collection::<Test>());
+			assert_ok!(test_benchmark_destroy_collection::<Test>());
+			assert_ok!(test_benchmark_mint::<Test>());
+			assert_ok!(test_benchmark_burn::<Test>());
+			assert_ok!(test_benchmark_transfer::<Test>());
+			assert_ok!(test_benchmark_approve::<Test>());
+			assert_ok!(test_benchmark_cancel_approval::<Test>());
+			assert_ok!(test_benchmark_set_metadata::<Test>());
+			assert_ok!(test_benchmark_clear_metadata::<Test>());
+		});
+	}
+}
@@ -0,0 +1,452 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// A pallet for non-fungible tokens.
+///
+/// Anyone can create a collection by reserving a deposit. The owner of a collection mints tokens
+/// into it and may attach metadata to each token, for which a deposit depending on its length is
+/// reserved. Token owners can transfer and burn their tokens, and approve another account to
+/// transfer a token on their behalf.
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, dispatch, ensure,
+	storage::IterableStorageDoubleMap,
+	traits::{Currency, ReservableCurrency, Get},
+	weights::Weight,
+};
+use frame_system::ensure_signed;
+use sp_runtime::{RuntimeDebug, traits::{StaticLookup, Saturating}};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+mod default_weights;
+
+/// The identifier of a collection.
+pub type CollectionId = u32;
+
+/// The identifier of a token within its collection.
+pub type TokenId = u32;
+
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// Weight functions needed for this pallet.
+pub trait WeightInfo {
+	fn create_collection() -> Weight;
+	fn destroy_collection() -> Weight;
+	fn mint() -> Weight;
+	fn burn() -> Weight;
+	fn transfer() -> Weight;
+	fn approve() -> Weight;
+	fn cancel_approval() -> Weight;
+	fn set_metadata(b: u32) -> Weight;
+	fn clear_metadata() -> Weight;
+}
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Trait: frame_system::Trait {
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency in which deposits are reserved.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The deposit reserved from the creator of a collection.
+	type CollectionDeposit: Get<BalanceOf<Self>>;
+
+	/// The base deposit reserved for the metadata of a token.
+	type MetadataDepositBase: Get<BalanceOf<Self>>;
+
+	/// The additional deposit reserved per byte of token metadata.
+	type MetadataDepositPerByte: Get<BalanceOf<Self>>;
+
+	/// The maximum length of the metadata of a token.
+	type StringLimit: Get<u32>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
+}
+
+/// The details of a collection.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct CollectionDetails<AccountId, Balance> {
+	/// Can mint tokens into the collection and set their metadata.
+	pub owner: AccountId,
+	/// The balance reserved from the owner for creating the collection.
+	pub deposit: Balance,
+	/// The identifier the next minted token gets.
+	pub next_token_id: TokenId,
+	/// The number of tokens in the collection.
+	pub tokens: u32,
+}
+
+/// The details of a token.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct TokenDetails<AccountId> {
+	/// The owner of the token.
+	pub owner: AccountId,
+	/// An account allowed to transfer the token on behalf of the owner.
+	pub approved: Option<AccountId>,
+}
+
+/// The metadata of a token.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct TokenMetadata<Balance> {
+	/// The balance reserved from the collection owner for the metadata.
+	pub deposit: Balance,
+	/// The metadata itself, e.g. a URI or a content hash.
+	pub data: Vec<u8>,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Nft {
+		/// Details of a collection.
+		Collection get(fn collection): map hasher(twox_64_concat) CollectionId
+			=> Option<CollectionDetails<T::AccountId, BalanceOf<T>>>;
+
+		/// The identifier the next created collection gets.
+		NextCollectionId get(fn next_collection_id): CollectionId;
+
+		/// Details of a token.
+		Token get(fn token): double_map
+			hasher(twox_64_concat) CollectionId,
+			hasher(twox_64_concat) TokenId
+			=> Option<TokenDetails<T::AccountId>>;
+
+		/// Metadata of a token.
+		Metadata get(fn metadata): double_map
+			hasher(twox_64_concat) CollectionId,
+			hasher(twox_64_concat) TokenId
+			=> Option<TokenMetadata<BalanceOf<T>>>;
+
+		/// The tokens owned by an account, for listing them cheaply.
+		Account: double_map
+			hasher(blake2_128_concat) T::AccountId,
+			hasher(twox_64_concat) (CollectionId, TokenId)
+			=> Option<()>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId {
+		/// A collection was created. [collection, owner]
+		CollectionCreated(CollectionId, AccountId),
+		/// An empty collection was removed. [collection]
+		CollectionDestroyed(CollectionId),
+		/// A token was minted. [collection, token, owner]
+		Minted(CollectionId, TokenId, AccountId),
+		/// A token was burned. [collection, token, owner]
+		Burned(CollectionId, TokenId, AccountId),
+		/// A token was transferred. [collection, token, from, to]
+		Transferred(CollectionId, TokenId, AccountId, AccountId),
+		/// An account was approved to transfer a token. [collection, token, owner, delegate]
+		Approved(CollectionId, TokenId, AccountId, AccountId),
+		/// The approval of a token was cancelled. [collection, token, owner]
+		ApprovalCancelled(CollectionId, TokenId, AccountId),
+		/// The metadata of a token was set. [collection, token]
+		MetadataSet(CollectionId, TokenId),
+		/// The metadata of a token was removed. [collection, token]
+		MetadataCleared(CollectionId, TokenId),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The given collection is unknown.
+		UnknownCollection,
+		/// The given token is unknown.
+		UnknownToken,
+		/// The token has no metadata.
+		NoMetadata,
+		/// The signing account has no permission to do the operation.
+		NoPermission,
+		/// The collection still contains tokens.
+		CollectionNotEmpty,
+		/// No more collections or tokens can be created.
+		Overflow,
+		/// The metadata is too long.
+		BadMetadata,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The deposit reserved from the creator of a collection.
+		const CollectionDeposit: BalanceOf<T> = T::CollectionDeposit::get();
+
+		/// The base deposit reserved for the metadata of a token.
+		const MetadataDepositBase: BalanceOf<T> = T::MetadataDepositBase::get();
+
+		/// The additional deposit reserved per byte of token metadata.
+		const MetadataDepositPerByte: BalanceOf<T> = T::MetadataDepositPerByte::get();
+
+		/// The maximum length of the metadata of a token.
+		const StringLimit: u32 = T::StringLimit::get();
+
+		fn deposit_event() = default;
+
+		/// Create a new collection owned by the sender, reserving `CollectionDeposit` from them.
+		#[weight = T::WeightInfo::create_collection()]
+		pub fn create_collection(origin) -> dispatch::DispatchResult {
+			let owner = ensure_signed(origin)?;
+
+			let id = Self::next_collection_id();
+			let next_id = id.checked_add(1).ok_or(Error::<T>::Overflow)?;
+
+			let deposit = T::CollectionDeposit::get();
+			T::Currency::reserve(&owner, deposit)?;
+
+			Collection::<T>::insert(id, CollectionDetails {
+				owner: owner.clone(),
+				deposit,
+				next_token_id: 0,
+				tokens: 0,
+			});
+			NextCollectionId::put(next_id);
+			Self::deposit_event(RawEvent::CollectionCreated(id, owner));
+			Ok(())
+		}
+
+		/// Remove an empty collection and return its deposit. Only the owner may destroy it.
+		#[weight = T::WeightInfo::destroy_collection()]
+		pub fn destroy_collection(origin, collection: CollectionId) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			let details = Self::collection(collection).ok_or(Error::<T>::UnknownCollection)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+			ensure!(details.tokens == 0, Error::<T>::CollectionNotEmpty);
+
+			T::Currency::unreserve(&origin, details.deposit);
+			Collection::<T>::remove(collection);
+			Self::deposit_event(RawEvent::CollectionDestroyed(collection));
+			Ok(())
+		}
+
+		/// Mint a new token into `collection`, owned by `owner`. Only the collection owner may mint.
+		#[weight = T::WeightInfo::mint()]
+		pub fn mint(
+			origin,
+			collection: CollectionId,
+			owner: <T::Lookup as StaticLookup>::Source,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+
+			let mut details = Self::collection(collection).ok_or(Error::<T>::UnknownCollection)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+
+			let token = details.next_token_id;
+			details.next_token_id = token.checked_add(1).ok_or(Error::<T>::Overflow)?;
+			details.tokens = details.tokens.saturating_add(1);
+
+			Token::<T>::insert(collection, token, TokenDetails {
+				owner: owner.clone(),
+				approved: None,
+			});
+			Account::<T>::insert(&owner, (collection, token), ());
+			Collection::<T>::insert(collection, details);
+			Self::deposit_event(RawEvent::Minted(collection, token, owner));
+			Ok(())
+		}
+
+		/// Burn a token of the sender, together with its metadata.
+		///
+		/// The metadata deposit is returned to the collection owner.
+		#[weight = T::WeightInfo::burn()]
+		pub fn burn(origin, collection: CollectionId, token: TokenId) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			let mut details = Self::collection(collection).ok_or(Error::<T>::UnknownCollection)?;
+			let token_details = Self::token(collection, token).ok_or(Error::<T>::UnknownToken)?;
+			ensure!(token_details.owner == origin, Error::<T>::NoPermission);
+
+			if let Some(metadata) = Metadata::<T>::take(collection, token) {
+				T::Currency::unreserve(&details.owner, metadata.deposit);
+			}
+			details.tokens = details.tokens.saturating_sub(1);
+
+			Token::<T>::remove(collection, token);
+			Account::<T>::remove(&origin, (collection, token));
+			Collection::<T>::insert(collection, details);
+			Self::deposit_event(RawEvent::Burned(collection, token, origin));
+			Ok(())
+		}
+
+		/// Transfer a token to `dest`. The sender must own the token or be approved for it.
+		///
+		/// Any approval of the token is cleared.
+		#[weight = T::WeightInfo::transfer()]
+		pub fn transfer(
+			origin,
+			collection: CollectionId,
+			token: TokenId,
+			dest: <T::Lookup as StaticLookup>::Source,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let dest = T::Lookup::lookup(dest)?;
+
+			let mut details = Self::token(collection, token).ok_or(Error::<T>::UnknownToken)?;
+			let owner = details.owner.clone();
+			ensure!(
+				owner == origin || details.approved.as_ref() == Some(&origin),
+				Error::<T>::NoPermission
+			);
+
+			details.owner = dest.clone();
+			details.approved = None;
+
+			Account::<T>::remove(&owner, (collection, token));
+			Account::<T>::insert(&dest, (collection, token), ());
+			Token::<T>::insert(collection, token, details);
+			Self::deposit_event(RawEvent::Transferred(collection, token, owner, dest));
+			Ok(())
+		}
+
+		/// Approve `delegate` to transfer a token of the sender, replacing any earlier approval.
+		#[weight = T::WeightInfo::approve()]
+		pub fn approve(
+			origin,
+			collection: CollectionId,
+			token: TokenId,
+			delegate: <T::Lookup as StaticLookup>::Source,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let delegate = T::Lookup::lookup(delegate)?;
+
+			let mut details = Self::token(collection, token).ok_or(Error::<T>::UnknownToken)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+
+			details.approved = Some(delegate.clone());
+			Token::<T>::insert(collection, token, details);
+			Self::deposit_event(RawEvent::Approved(collection, token, origin, delegate));
+			Ok(())
+		}
+
+		/// Cancel the approval of a token of the sender.
+		#[weight = T::WeightInfo::cancel_approval()]
+		pub fn cancel_approval(
+			origin,
+			collection: CollectionId,
+			token: TokenId,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			let mut details = Self::token(collection, token).ok_or(Error::<T>::UnknownToken)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+
+			details.approved = None;
+			Token::<T>::insert(collection, token, details);
+			Self::deposit_event(RawEvent::ApprovalCancelled(collection, token, origin));
+			Ok(())
+		}
+
+		/// Set the metadata of a token. Only the collection owner may set it.
+		///
+		/// A deposit depending on the length of `data` is reserved from the collection owner;
+		/// a previous deposit is adjusted.
+		#[weight = T::WeightInfo::set_metadata(data.len() as u32)]
+		pub fn set_metadata(
+			origin,
+			collection: CollectionId,
+			token: TokenId,
+			data: Vec<u8>,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+			ensure!(data.len() <= T::StringLimit::get() as usize, Error::<T>::BadMetadata);
+
+			let details = Self::collection(collection).ok_or(Error::<T>::UnknownCollection)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+			ensure!(Token::<T>::contains_key(collection, token), Error::<T>::UnknownToken);
+
+			let old_deposit = Self::metadata(collection, token)
+				.map(|m| m.deposit)
+				.unwrap_or_default();
+			let new_deposit = T::MetadataDepositPerByte::get()
+				.saturating_mul((data.len() as u32).into())
+				.saturating_add(T::MetadataDepositBase::get());
+			if new_deposit > old_deposit {
+				T::Currency::reserve(&origin, new_deposit - old_deposit)?;
+			} else {
+				T::Currency::unreserve(&origin, old_deposit - new_deposit);
+			}
+
+			Metadata::<T>::insert(collection, token, TokenMetadata { deposit: new_deposit, data });
+			Self::deposit_event(RawEvent::MetadataSet(collection, token));
+			Ok(())
+		}
+
+		/// Remove the metadata of a token and return its deposit. Only the collection owner may
+		/// clear it.
+		#[weight = T::WeightInfo::clear_metadata()]
+		pub fn clear_metadata(
+			origin,
+			collection: CollectionId,
+			token: TokenId,
+		) -> dispatch::DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			let details = Self::collection(collection).ok_or(Error::<T>::UnknownCollection)?;
+			ensure!(details.owner == origin, Error::<T>::NoPermission);
+
+			let metadata = Metadata::<T>::take(collection, token).ok_or(Error::<T>::NoMetadata)?;
+			T::Currency::unreserve(&origin, metadata.deposit);
+			Self::deposit_event(RawEvent::MetadataCleared(collection, token));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// All tokens owned by `who`, as `(collection, token)` pairs in ascending order.
+	pub fn tokens_of(who: T::AccountId) -> Vec<(CollectionId, TokenId)> {
+		let mut tokens: Vec<_> = Account::<T>::iter_prefix(who).map(|(id, _)| id).collect();
+		tokens.sort();
+		tokens
+	}
+
+	/// Check that collections and tokens have identifiers below the next ones, that the token
+	/// counts of collections are right, and that the index of owned tokens matches the owners.
+	#[cfg(feature = "try-runtime")]
+	pub fn post_upgrade() -> Result<(), &'static str> {
+		use frame_support::storage::IterableStorageMap;
+
+		let next_collection_id = NextCollectionId::get();
+		for (collection, details) in Collection::<T>::iter() {
+			ensure!(collection < next_collection_id, "A collection has an unassigned identifier");
+			let mut tokens = 0u32;
+			for (token, _) in Token::<T>::iter_prefix(collection) {
+				ensure!(token < details.next_token_id, "A token has an unassigned identifier");
+				tokens += 1;
+			}
+			ensure!(tokens == details.tokens, "The token count of a collection is wrong");
+		}
+		for (collection, token, details) in Token::<T>::iter() {
+			ensure!(Collection::<T>::contains_key(collection), "A token of an unknown collection");
+			ensure!(
+				Account::<T>::contains_key(&details.owner, (collection, token)),
+				"An owned token is missing from the index of its owner",
+			);
+		}
+		for (owner, (collection, token), ()) in Account::<T>::iter() {
+			let details = Token::<T>::get(collection, token);
+			ensure!(
+				details.map_or(false, |details| details.owner == owner),
+				"The index of an account lists a token it does not own",
+			);
+		}
+		for (collection, token, _) in Metadata::<T>::iter() {
+			ensure!(Token::<T>::contains_key(collection, token), "Metadata of an unknown token");
+		}
+		Ok(())
+	}
+}
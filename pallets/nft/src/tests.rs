@@ -0,0 +1,148 @@
+use crate::{Error, mock::*};
+use frame_support::{assert_ok, assert_noop};
+
+#[test]
+fn create_and_destroy_collection() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create_collection(Origin::signed(1)));
+		assert_eq!(Nft::collection(0).unwrap().owner, 1);
+		assert_eq!(Nft::next_collection_id(), 1);
+		assert_eq!(Balances::reserved_balance(1), 10);
+
+		assert_ok!(Nft::mint(Origin::signed(1), 0, 2));
+		assert_noop!(
+			Nft::destroy_collection(Origin::signed(1), 0),
+			Error::<Test>::CollectionNotEmpty,
+		);
+		assert_ok!(Nft::burn(Origin::signed(2), 0, 0));
+
+		assert_noop!(Nft::destroy_collection(Origin::signed(2), 0), Error::<Test>::NoPermission);
+		assert_ok!(Nft::destroy_collection(Origin::signed(1), 0));
+		assert_eq!(Nft::collection(0), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn only_collection_owner_can_mint() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create_collection(Origin::signed(1)));
+		assert_noop!(Nft::mint(Origin::signed(2), 0, 2), Error::<Test>::NoPermission);
+		assert_noop!(Nft::mint(Origin::signed(1), 1, 2), Error::<Test>::UnknownCollection);
+
+		assert_ok!(Nft::mint(Origin::signed(1), 0, 2));
+		assert_ok!(Nft::mint(Origin::signed(1), 0, 2));
+		assert_eq!(Nft::token(0, 1).unwrap().owner, 2);
+		assert_eq!(Nft::collection(0).unwrap().tokens, 2);
+		assert_eq!(Nft::tokens_of(2), vec![(0, 0), (0, 1)]);
+	});
+}
+
+#[test]
+fn transfer_moves_ownership() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create_collection(Origin::signed(1)));
+		assert_ok!(Nft::mint(Origin::signed(1), 0, 2));
+
+		assert_noop!(Nft::transfer(Origin::signed(1), 0, 0, 3), Error::<Test>::NoPermission);
+		assert_noop!(Nft::transfer(Origin::signed(2), 0, 1, 3), Error::<Test>::UnknownToken);
+		assert_ok!(Nft::transfer(Origin::signed(2), 0, 0, 3));
+
+		assert_eq!(Nft::token(0, 0).unwrap().owner, 3);
+		assert!(Nft::tokens_of(2).is_empty());
+		assert_eq!(Nft::tokens_of(3), vec![(0, 0)]);
+	});
+}
+
+#[test]
+fn approved_account_can_transfer_once() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create_collection(Origin::signed(1)));
+		assert_ok!(Nft::mint(Origin::signed(1), 0, 2));
+
+		assert_noop!(Nft::approve(Origin::signed(3), 0, 0, 3), Error::<Test>::NoPermission);
+		assert_ok!(Nft::approve(Origin::signed(2), 0, 0, 3));
+		assert_eq!(Nft::token(0, 0).unwrap().approved, Some(3));
+
+		assert_ok!(Nft::transfer(Origin::signed(3), 0, 0, 1));
+		assert_eq!(Nft::token(0, 0).unwrap().owner, 1);
+		// the approval does not survive the transfer.
+		assert_eq!(Nft::token(0, 0).unwrap().approved, None);
+		assert_noop!(Nft::transfer(Origin::signed(3), 0, 0, 3), Error::<Test>::NoPermission);
+	});
+}
+
+#[test]
+fn cancelled_approval_cannot_transfer() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create_collection(Origin::signed(1)));
+		assert_ok!(Nft::mint(Origin::signed(1), 0, 2));
+		assert_ok!(Nft::approve(Origin::signed(2), 0, 0, 3));
+		assert_ok!(Nft::cancel_approval(Origin::signed(2), 0, 0));
+
+		assert_noop!(Nft::transfer(Origin::signed(3), 0, 0, 3), Error::<Test>::NoPermission);
+	});
+}
+
+#[test]
+fn metadata_reserves_deposit_from_collection_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create_collection(Origin::signed(1)));
+		assert_ok!(Nft::mint(Origin::signed(1), 0, 2));
+
+		assert_noop!(
+			Nft::set_metadata(Origin::signed(2), 0, 0, vec![0; 4]),
+			Error::<Test>::NoPermission,
+		);
+		assert_noop!(
+			Nft::set_metadata(Origin::signed(1), 0, 0, vec![0; 9]),
+			Error::<Test>::BadMetadata,
+		);
+		assert_noop!(
+			Nft::set_metadata(Origin::signed(1), 0, 1, vec![0; 4]),
+			Error::<Test>::UnknownToken,
+		);
+
+		assert_ok!(Nft::set_metadata(Origin::signed(1), 0, 0, vec![0; 4]));
+		assert_eq!(Balances::reserved_balance(1), 10 + 5);
+		assert_ok!(Nft::set_metadata(Origin::signed(1), 0, 0, vec![0; 8]));
+		assert_eq!(Balances::reserved_balance(1), 10 + 9);
+		assert_ok!(Nft::set_metadata(Origin::signed(1), 0, 0, vec![0; 2]));
+		assert_eq!(Balances::reserved_balance(1), 10 + 3);
+
+		assert_ok!(Nft::clear_metadata(Origin::signed(1), 0, 0));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_noop!(Nft::clear_metadata(Origin::signed(1), 0, 0), Error::<Test>::NoMetadata);
+	});
+}
+
+#[test]
+fn burn_returns_metadata_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create_collection(Origin::signed(1)));
+		assert_ok!(Nft::mint(Origin::signed(1), 0, 2));
+		assert_ok!(Nft::set_metadata(Origin::signed(1), 0, 0, vec![0; 4]));
+
+		assert_noop!(Nft::burn(Origin::signed(1), 0, 0), Error::<Test>::NoPermission);
+		assert_ok!(Nft::burn(Origin::signed(2), 0, 0));
+		assert_eq!(Nft::token(0, 0), None);
+		assert_eq!(Nft::metadata(0, 0), None);
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert!(Nft::tokens_of(2).is_empty());
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn post_upgrade_checks_the_index_of_owned_tokens() {
+	use frame_support::StorageDoubleMap;
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Nft::create_collection(Origin::signed(1)));
+		assert_ok!(Nft::mint(Origin::signed(1), 0, 2));
+		assert_ok!(Nft::post_upgrade());
+
+		crate::Account::<Test>::remove(2, (0, 0));
+		assert!(Nft::post_upgrade().is_err());
+	});
+}
@@ -0,0 +1,197 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// A pallet limiting the number of transactions an account can submit per period.
+///
+/// Time is cut into windows of `Period` blocks. The `CheckRateLimit` signed extension counts the
+/// transactions of every sender in the current window and rejects those above
+/// `MaxTransactions` while validating them, so that the transaction pool drops them. The
+/// extension carries no data and cannot see the nonce, so the runtime additionally calls
+/// `Module::check_queued` with the nonce of `CheckNonce` when validating a transaction, which
+/// counts the transactions of the sender waiting in the pool as well. The counter of an account
+/// is reset when it is next used in a later window, instead of clearing all counters at once.
+
+use sp_std::{fmt, marker::PhantomData};
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage,
+	traits::Get,
+	weights::DispatchInfo,
+};
+use sp_runtime::{
+	SaturatedConversion,
+	traits::{DispatchInfoOf, Dispatchable, Saturating, SignedExtension, Zero},
+	transaction_validity::{
+		TransactionValidity, TransactionValidityError, InvalidTransaction, ValidTransaction,
+	},
+};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The `InvalidTransaction::Custom` code of transactions above the rate limit.
+pub const RATE_LIMITED: u8 = 1;
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+pub trait Trait: frame_system::Trait {
+	/// The number of blocks in a window.
+	type Period: Get<Self::BlockNumber>;
+
+	/// The maximum number of transactions an account can submit in a window.
+	type MaxTransactions: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as RateLimit {
+		/// The first block of the window an account last submitted a transaction in, and the
+		/// number of transactions it submitted in that window.
+		Counters get(fn counter):
+			map hasher(blake2_128_concat) T::AccountId => (T::BlockNumber, u32);
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The number of blocks in a window.
+		const Period: T::BlockNumber = T::Period::get();
+
+		/// The maximum number of transactions an account can submit in a window.
+		const MaxTransactions: u32 = T::MaxTransactions::get();
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The first block of the window block `n` is in.
+	pub fn window(n: T::BlockNumber) -> T::BlockNumber {
+		let period = T::Period::get();
+		if period.is_zero() {
+			Zero::zero()
+		} else {
+			n - n % period
+		}
+	}
+
+	/// The number of transactions `who` submitted in the current window.
+	pub fn count(who: &T::AccountId) -> u32 {
+		let window = Self::window(frame_system::Module::<T>::block_number());
+		match Counters::<T>::get(who) {
+			(start, count) if start == window => count,
+			_ => 0,
+		}
+	}
+
+	/// Check that `who` can submit the transaction with the given nonce, counting those with a
+	/// lower nonce, which are waiting in the pool, as if they were included already.
+	///
+	/// `CheckRateLimit` cannot see the nonce, which is checked by `CheckNonce`, so the runtime
+	/// calls this as a separate step when validating a transaction.
+	pub fn check_queued(
+		who: &T::AccountId,
+		nonce: T::Index,
+	) -> Result<(), TransactionValidityError> {
+		let account_nonce = frame_system::Module::<T>::account_nonce(who);
+		Self::check(who, nonce.saturating_sub(account_nonce).saturated_into()).map(drop)
+	}
+
+	/// The current window and the number of transactions `who` submitted in it, or an error if
+	/// `who` cannot submit any more on top of `queued` transactions waiting to be included.
+	fn check(
+		who: &T::AccountId,
+		queued: u32,
+	) -> Result<(T::BlockNumber, u32), TransactionValidityError> {
+		let window = Self::window(frame_system::Module::<T>::block_number());
+		let count = Self::count(who);
+		if count.saturating_add(queued) >= T::MaxTransactions::get() {
+			return Err(InvalidTransaction::Custom(RATE_LIMITED).into());
+		}
+		Ok((window, count))
+	}
+
+	/// Check that no counter is of a later window than the current one, e.g. after a change of
+	/// `Period`, which would keep its account from being reset.
+	#[cfg(feature = "try-runtime")]
+	pub fn post_upgrade() -> Result<(), &'static str> {
+		use frame_support::storage::IterableStorageMap;
+
+		let window = Self::window(frame_system::Module::<T>::block_number());
+		if Counters::<T>::iter().any(|(_, (start, _))| start > window) {
+			return Err("Counters of a future window are left");
+		}
+		Ok(())
+	}
+}
+
+/// Limit the transactions of the sender per window.
+///
+/// The transactions of the sender waiting in the pool are counted by `Module::check_queued`,
+/// since this extension does not know the nonce.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckRateLimit<T: Trait + Send + Sync>(PhantomData<T>);
+
+impl<T: Trait + Send + Sync> CheckRateLimit<T> {
+	/// Create new `SignedExtension` to limit the transactions of the sender.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Trait + Send + Sync> Default for CheckRateLimit<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Trait + Send + Sync> fmt::Debug for CheckRateLimit<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "CheckRateLimit")
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + Send + Sync> SignedExtension for CheckRateLimit<T> where
+	T::Call: Dispatchable<Info = DispatchInfo>,
+{
+	const IDENTIFIER: &'static str = "CheckRateLimit";
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> { Ok(()) }
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		_call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		let (window, _) = Module::<T>::check(who, 0)?;
+		// Revalidate the transaction once its window is over.
+		let end = window.saturating_add(T::Period::get());
+		let now = frame_system::Module::<T>::block_number();
+		Ok(ValidTransaction {
+			longevity: end.saturating_sub(now).saturated_into::<u64>().max(1),
+			..Default::default()
+		})
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		_call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> Result<(), TransactionValidityError> {
+		let (window, count) = Module::<T>::check(who, 0)?;
+		Counters::<T>::insert(who, (window, count + 1));
+		Ok(())
+	}
+}
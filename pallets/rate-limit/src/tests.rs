@@ -0,0 +1,104 @@
+use crate::{CheckRateLimit, Counters, RATE_LIMITED, mock::*};
+use codec::Encode;
+use frame_support::assert_ok;
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidity, TransactionValidityError},
+};
+
+fn remark() -> Call {
+	Call::System(frame_system::Call::remark(vec![]))
+}
+
+/// Validate the next transaction of `who`.
+fn validate(who: u64) -> TransactionValidity {
+	CheckRateLimit::<Test>::new().validate(&who, &remark(), &Default::default(), 0)
+}
+
+/// Validate a transaction of `who` with the given nonce, like the runtime does.
+fn validate_nonce(who: u64, nonce: u64) -> TransactionValidity {
+	RateLimit::check_queued(&who, nonce)?;
+	validate(who)
+}
+
+/// Include the next transaction of `who`.
+fn submit(who: u64) -> Result<(), TransactionValidityError> {
+	CheckRateLimit::<Test>::new().pre_dispatch(&who, &remark(), &Default::default(), 0)?;
+	System::inc_account_nonce(who);
+	Ok(())
+}
+
+#[test]
+fn transactions_above_the_limit_are_invalid() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(submit(1));
+		assert_ok!(validate(1));
+		assert_ok!(submit(1));
+
+		assert_eq!(validate(1), Err(InvalidTransaction::Custom(RATE_LIMITED).into()));
+		assert_eq!(submit(1), Err(InvalidTransaction::Custom(RATE_LIMITED).into()));
+		assert_eq!(RateLimit::count(&1), 2);
+
+		// other accounts have their own counters.
+		assert_ok!(submit(2));
+	});
+}
+
+#[test]
+fn transactions_waiting_in_the_pool_count() {
+	new_test_ext().execute_with(|| {
+		// both transactions allowed in the window are waiting in the pool, with nonces 0 and 1.
+		assert_ok!(validate_nonce(1, 0));
+		assert_ok!(validate_nonce(1, 1));
+		assert_eq!(
+			validate_nonce(1, 2),
+			Err(InvalidTransaction::Custom(RATE_LIMITED).into()),
+		);
+
+		// once the first one is included, the third still waits for the second one.
+		assert_ok!(submit(1));
+		assert_eq!(
+			validate_nonce(1, 2),
+			Err(InvalidTransaction::Custom(RATE_LIMITED).into()),
+		);
+	});
+}
+
+#[test]
+fn the_extension_carries_no_data() {
+	new_test_ext().execute_with(|| {
+		// the nonce stays with `CheckNonce`, so that generic signers can encode the extension.
+		assert!(CheckRateLimit::<Test>::new().encode().is_empty());
+		assert_ok!(submit(1));
+		// included transactions are not queued any more.
+		assert_ok!(validate_nonce(1, 0));
+		assert_eq!(RateLimit::count(&1), 1);
+	});
+}
+
+#[test]
+fn counters_reset_with_every_window() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(submit(1));
+		assert_ok!(submit(1));
+
+		System::set_block_number(5);
+		assert_eq!(RateLimit::window(5), 5);
+		assert_eq!(RateLimit::count(&1), 0);
+		// the stale counter is only overwritten once the account is limited again.
+		assert_eq!(Counters::<Test>::get(1), (0, 2));
+		assert_ok!(submit(1));
+		assert_eq!(RateLimit::counter(1), (5, 1));
+	});
+}
+
+#[test]
+fn valid_transactions_expire_with_their_window() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(3);
+		assert_eq!(validate(1).map(|v| v.longevity), Ok(2));
+
+		System::set_block_number(9);
+		assert_eq!(validate(1).map(|v| v.longevity), Ok(1));
+	});
+}
@@ -0,0 +1,435 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// A pallet letting sponsors pay the transaction fees of whitelisted accounts, so that e.g. IoT
+/// devices can submit transactions without holding funds.
+///
+/// A sponsor funds a pot, a sub-account of this pallet derived from the sponsor's account, and
+/// whitelists accounts together with a filter of the calls it pays for. The
+/// `ChargeTransactionPaymentOrSponsor` signed extension replaces `ChargeTransactionPayment` and
+/// draws the fees of whitelisted accounts from their sponsor's pot.
+
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, dispatch, ensure, Parameter,
+	traits::{
+		Currency, ReservableCurrency, ExistenceRequirement, Get, Imbalance, InstanceFilter,
+		OnUnbalanced, WithdrawReason, WithdrawReasons,
+	},
+	weights::{DispatchInfo, PostDispatchInfo, GetDispatchInfo},
+};
+use frame_system::ensure_signed;
+use sp_runtime::{
+	ModuleId, FixedPointOperand,
+	traits::{
+		AccountIdConversion, CheckedSub, DispatchInfoOf, Dispatchable, Member, PostDispatchInfoOf,
+		Saturating, SaturatedConversion, SignedExtension, StaticLookup, Zero,
+	},
+	transaction_validity::{
+		TransactionPriority, TransactionValidity, TransactionValidityError, InvalidTransaction,
+		ValidTransaction,
+	},
+};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+/// Configure the pallet by specifying the parameters and types on which it depends.
+///
+/// The pots pay fees in the currency they are funded in, so the transaction payment pallet must
+/// use the same currency.
+pub trait Trait:
+	pallet_transaction_payment::Trait<Currency = <Self as Trait>::Currency>
+{
+	/// Because this pallet emits events, it depends on the runtime's definition of an event.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency in which transaction fees are paid, pots are funded and deposits are
+	/// reserved.
+	type Currency: ReservableCurrency<Self::AccountId> + Send + Sync;
+
+	/// A filter of the calls a sponsor pays for.
+	type CallFilter: Parameter + Member + Default
+		+ InstanceFilter<<Self as frame_system::Trait>::Call>;
+
+	/// The pallet's module id, used for deriving the pot accounts of sponsors.
+	type ModuleId: Get<ModuleId>;
+
+	/// The deposit reserved from the sponsor for every whitelisted account.
+	type SponseeDeposit: Get<BalanceOf<Self>>;
+
+	/// The maximum number of accounts a sponsor can whitelist.
+	type MaxSponsees: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Sponsor {
+		/// The sponsor of a whitelisted account and the filter of the calls it pays for.
+		SponsorOf get(fn sponsor_of): map hasher(blake2_128_concat) T::AccountId
+			=> Option<(T::AccountId, T::CallFilter)>;
+
+		/// The number of accounts whitelisted by a sponsor.
+		Sponsees get(fn sponsees): map hasher(blake2_128_concat) T::AccountId => u32;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+		CallFilter = <T as Trait>::CallFilter,
+	{
+		/// A sponsor moved funds into its pot. [sponsor, amount]
+		Funded(AccountId, Balance),
+		/// A sponsor took funds out of its pot. [sponsor, amount]
+		Defunded(AccountId, Balance),
+		/// A sponsor whitelisted an account, or changed its filter. [sponsor, who, filter]
+		Sponsored(AccountId, AccountId, CallFilter),
+		/// A sponsor removed an account from its whitelist. [sponsor, who]
+		Unsponsored(AccountId, AccountId),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The account is already whitelisted by another sponsor.
+		AlreadySponsored,
+		/// The account is not whitelisted by the sender.
+		NotSponsored,
+		/// The sender cannot whitelist any more accounts.
+		TooManySponsees,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The pallet's module id, used for deriving the pot accounts of sponsors.
+		const ModuleId: ModuleId = T::ModuleId::get();
+
+		/// The deposit reserved from the sponsor for every whitelisted account.
+		const SponseeDeposit: BalanceOf<T> = T::SponseeDeposit::get();
+
+		/// The maximum number of accounts a sponsor can whitelist.
+		const MaxSponsees: u32 = T::MaxSponsees::get();
+
+		fn deposit_event() = default;
+
+		/// Move `amount` from the sender into its pot.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn fund(origin, #[compact] amount: BalanceOf<T>) -> dispatch::DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+
+			<T as Trait>::Currency::transfer(
+				&sponsor,
+				&Self::pot_of(&sponsor),
+				amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			Self::deposit_event(RawEvent::Funded(sponsor, amount));
+			Ok(())
+		}
+
+		/// Move `amount` from the sender's pot back to the sender.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn defund(origin, #[compact] amount: BalanceOf<T>) -> dispatch::DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+
+			<T as Trait>::Currency::transfer(
+				&Self::pot_of(&sponsor),
+				&sponsor,
+				amount,
+				ExistenceRequirement::AllowDeath,
+			)?;
+			Self::deposit_event(RawEvent::Defunded(sponsor, amount));
+			Ok(())
+		}
+
+		/// Pay the fees of the calls of `who` that pass `filter`.
+		///
+		/// Whitelisting a new account reserves `SponseeDeposit` from the sender. Calling this
+		/// again for an account the sender already sponsors only replaces the filter.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn sponsor(
+			origin,
+			who: <T::Lookup as StaticLookup>::Source,
+			filter: T::CallFilter,
+		) -> dispatch::DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			let who = T::Lookup::lookup(who)?;
+
+			match SponsorOf::<T>::get(&who) {
+				Some((current, _)) => ensure!(current == sponsor, Error::<T>::AlreadySponsored),
+				None => {
+					let count = Sponsees::<T>::get(&sponsor);
+					ensure!(count < T::MaxSponsees::get(), Error::<T>::TooManySponsees);
+					<T as Trait>::Currency::reserve(&sponsor, T::SponseeDeposit::get())?;
+					Sponsees::<T>::insert(&sponsor, count + 1);
+				},
+			}
+
+			SponsorOf::<T>::insert(&who, (sponsor.clone(), filter.clone()));
+			Self::deposit_event(RawEvent::Sponsored(sponsor, who, filter));
+			Ok(())
+		}
+
+		/// Stop paying the fees of `who`, releasing the deposit reserved for it.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn unsponsor(
+			origin,
+			who: <T::Lookup as StaticLookup>::Source,
+		) -> dispatch::DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			let who = T::Lookup::lookup(who)?;
+
+			let (current, _) = SponsorOf::<T>::get(&who).ok_or(Error::<T>::NotSponsored)?;
+			ensure!(current == sponsor, Error::<T>::NotSponsored);
+
+			SponsorOf::<T>::remove(&who);
+			Sponsees::<T>::mutate(&sponsor, |count| *count = count.saturating_sub(1));
+			<T as Trait>::Currency::unreserve(&sponsor, T::SponseeDeposit::get());
+			Self::deposit_event(RawEvent::Unsponsored(sponsor, who));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account ID of the pot of `sponsor`.
+	pub fn pot_of(sponsor: &T::AccountId) -> T::AccountId {
+		T::ModuleId::get().into_sub_account(sponsor)
+	}
+
+	/// The pot paying a `fee` of `call` from `who`, if its sponsor covers the call and the pot
+	/// can afford the fee.
+	pub fn sponsoring_pot(
+		who: &T::AccountId,
+		call: &<T as frame_system::Trait>::Call,
+		fee: BalanceOf<T>,
+	) -> Option<T::AccountId> {
+		let (sponsor, filter) = SponsorOf::<T>::get(who)?;
+		if !filter.filter(call) {
+			return None;
+		}
+
+		let pot = Self::pot_of(&sponsor);
+		let new_balance = <T as Trait>::Currency::free_balance(&pot).checked_sub(&fee)?;
+		if new_balance < <T as Trait>::Currency::minimum_balance() {
+			return None;
+		}
+		<T as Trait>::Currency::ensure_can_withdraw(
+			&pot,
+			fee,
+			WithdrawReason::TransactionPayment.into(),
+			new_balance,
+		).ok()?;
+		Some(pot)
+	}
+
+	/// Check that the sponsee count of each sponsor matches the accounts it whitelisted.
+	#[cfg(feature = "try-runtime")]
+	pub fn post_upgrade() -> Result<(), &'static str> {
+		use frame_support::storage::IterableStorageMap;
+		use sp_std::collections::btree_map::BTreeMap;
+
+		let mut counts = BTreeMap::<T::AccountId, u32>::new();
+		for (_, (sponsor, _)) in SponsorOf::<T>::iter() {
+			*counts.entry(sponsor).or_default() += 1;
+		}
+		for (sponsor, count) in Sponsees::<T>::iter() {
+			// Counts drop to zero rather than being removed.
+			let whitelisted = counts.remove(&sponsor).unwrap_or(0);
+			ensure!(whitelisted == count, "The sponsee count of a sponsor is wrong");
+		}
+		ensure!(counts.is_empty(), "A sponsor of whitelisted accounts has no sponsee count");
+		Ok(())
+	}
+}
+
+/// Pay the transaction fee like `ChargeTransactionPayment`, but draw it from the sponsor's pot
+/// if the sender is whitelisted for the call.
+///
+/// Sponsors only pay for transactions without a tip, so that their sponsees cannot spend the pot
+/// on priority. The sender pays if the pot cannot afford the fee.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeTransactionPaymentOrSponsor<T: Trait + Send + Sync>(
+	#[codec(compact)] BalanceOf<T>
+);
+
+impl<T: Trait + Send + Sync> ChargeTransactionPaymentOrSponsor<T> where
+	<T as frame_system::Trait>::Call: Dispatchable<Info=DispatchInfo, PostInfo=PostDispatchInfo>,
+	BalanceOf<T>: Send + Sync + FixedPointOperand,
+{
+	/// Utility constructor. Used only in client/factory code.
+	pub fn from(fee: BalanceOf<T>) -> Self {
+		Self(fee)
+	}
+
+	/// The account paying a `fee` of `call` from `who`: its sponsor's pot or `who` itself.
+	pub fn payer(
+		&self,
+		who: &T::AccountId,
+		call: &<T as frame_system::Trait>::Call,
+		fee: BalanceOf<T>,
+	) -> T::AccountId {
+		let sponsoring_pot = if self.0.is_zero() {
+			Module::<T>::sponsoring_pot(who, call, fee)
+		} else {
+			None
+		};
+		sponsoring_pot.unwrap_or_else(|| who.clone())
+	}
+
+	fn withdraw_fee(
+		&self,
+		who: &T::AccountId,
+		call: &<T as frame_system::Trait>::Call,
+		info: &DispatchInfoOf<<T as frame_system::Trait>::Call>,
+		len: usize,
+	) -> Result<
+		(BalanceOf<T>, T::AccountId, Option<NegativeImbalanceOf<T>>),
+		TransactionValidityError,
+	> {
+		let tip = self.0;
+		let fee = pallet_transaction_payment::Module::<T>::compute_fee(len as u32, info, tip);
+
+		// Only mess with balances if fee is not zero.
+		if fee.is_zero() {
+			return Ok((fee, who.clone(), None));
+		}
+
+		let payer = self.payer(who, call, fee);
+		let reasons: WithdrawReasons = if tip.is_zero() {
+			WithdrawReason::TransactionPayment.into()
+		} else {
+			WithdrawReason::TransactionPayment | WithdrawReason::Tip
+		};
+		match <T as Trait>::Currency::withdraw(
+			&payer,
+			fee,
+			reasons,
+			ExistenceRequirement::KeepAlive,
+		) {
+			Ok(imbalance) => Ok((fee, payer, Some(imbalance))),
+			Err(_) => Err(InvalidTransaction::Payment.into()),
+		}
+	}
+
+	/// Get an appropriate priority for a transaction with the given length and info.
+	///
+	/// This will try and optimise the `fee/weight` `fee/length`, whichever is consuming more of
+	/// the maximum corresponding limit, like `ChargeTransactionPayment` does.
+	fn get_priority(
+		len: usize,
+		info: &DispatchInfoOf<<T as frame_system::Trait>::Call>,
+		final_fee: BalanceOf<T>,
+	) -> TransactionPriority {
+		let weight_saturation = T::MaximumBlockWeight::get() / info.weight.max(1);
+		let len_saturation = T::MaximumBlockLength::get() as u64 / (len as u64).max(1);
+		let coefficient: BalanceOf<T> =
+			weight_saturation.min(len_saturation).saturated_into::<BalanceOf<T>>();
+		final_fee.saturating_mul(coefficient).saturated_into::<TransactionPriority>()
+	}
+}
+
+impl<T: Trait + Send + Sync> sp_std::fmt::Debug for ChargeTransactionPaymentOrSponsor<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "ChargeTransactionPaymentOrSponsor<{:?}>", self.0)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + Send + Sync> SignedExtension for ChargeTransactionPaymentOrSponsor<T> where
+	BalanceOf<T>: Send + Sync + From<u64> + FixedPointOperand,
+	<T as frame_system::Trait>::Call:
+		Dispatchable<Info=DispatchInfo, PostInfo=PostDispatchInfo> + GetDispatchInfo,
+{
+	const IDENTIFIER: &'static str = "ChargeTransactionPaymentOrSponsor";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Trait>::Call;
+	type AdditionalSigned = ();
+	/// The tip, the account that paid, the imbalance withdrawn from it and the fee.
+	type Pre = (
+		BalanceOf<T>,
+		Self::AccountId,
+		Option<NegativeImbalanceOf<T>>,
+		BalanceOf<T>,
+	);
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> { Ok(()) }
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		let (fee, _, _) = self.withdraw_fee(who, call, info, len)?;
+
+		let mut r = ValidTransaction::default();
+		// NOTE: we probably want to maximize the _fee (of any type) per weight unit_ here, which
+		// will be a bit more than setting the priority to tip. For now, this is enough.
+		r.priority = Self::get_priority(len, info, fee);
+		Ok(r)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let (fee, payer, imbalance) = self.withdraw_fee(who, call, info, len)?;
+		Ok((self.0, payer, imbalance, fee))
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		_result: &dispatch::DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		let (tip, payer, imbalance, fee) = pre;
+		if let Some(payed) = imbalance {
+			let actual_fee = pallet_transaction_payment::Module::<T>::compute_actual_fee(
+				len as u32,
+				info,
+				post_info,
+				tip,
+			);
+			let refund = fee.saturating_sub(actual_fee);
+			let refunded = <T as Trait>::Currency::deposit_into_existing(&payer, refund);
+			let actual_payment = match refunded {
+				Ok(refund_imbalance) => {
+					// The refund cannot be larger than the up front payed max weight.
+					// `PostDispatchInfo::calc_unspent` guards against such a case.
+					match payed.offset(refund_imbalance) {
+						Ok(actual_payment) => actual_payment,
+						Err(_) => return Err(InvalidTransaction::Payment.into()),
+					}
+				},
+				// We do not recreate the account using the refund. The up front payment is gone
+				// in that case.
+				Err(_) => payed,
+			};
+			let imbalances = actual_payment.split(tip);
+			T::OnTransactionPayment::on_unbalanced(Imbalance::merge(imbalances.0, imbalances.1));
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,153 @@
+use crate::{Module, Trait};
+use codec::{Encode, Decode};
+use sp_core::H256;
+use frame_support::{
+	impl_outer_origin, impl_outer_event, impl_outer_dispatch, parameter_types,
+	traits::InstanceFilter,
+	weights::{Weight, IdentityFee},
+};
+use sp_runtime::{
+	traits::{BlakeTwo256, IdentityLookup}, testing::Header, ModuleId, Perbill, RuntimeDebug,
+};
+use frame_system as system;
+use crate as pallet_sponsor;
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		frame_system::System,
+		pallet_sponsor::Sponsor,
+	}
+}
+
+mod sponsor {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system<T>,
+		pallet_balances<T>,
+		sponsor<T>,
+	}
+}
+
+// Configure a mock runtime to test the pallet.
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+}
+
+impl system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type PalletInfo = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Trait for Test {
+	type MaxLocks = MaxLocks;
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const TransactionByteFee: u64 = 1;
+}
+
+impl pallet_transaction_payment::Trait for Test {
+	type Currency = Balances;
+	type OnTransactionPayment = ();
+	type TransactionByteFee = TransactionByteFee;
+	type WeightToFee = IdentityFee<u64>;
+	type FeeMultiplierUpdate = ();
+}
+
+/// The calls a sponsor pays for in the tests.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+pub enum Filter {
+	Any,
+	RemarkOnly,
+}
+
+impl Default for Filter {
+	fn default() -> Self { Self::Any }
+}
+
+impl InstanceFilter<Call> for Filter {
+	fn filter(&self, c: &Call) -> bool {
+		match self {
+			Filter::Any => true,
+			Filter::RemarkOnly => matches!(c, Call::System(system::Call::remark(..))),
+		}
+	}
+}
+
+parameter_types! {
+	pub const SponsorModuleId: ModuleId = ModuleId(*b"py/spnsr");
+	pub const SponseeDeposit: u64 = 10;
+	pub const MaxSponsees: u32 = 2;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type Currency = Balances;
+	type CallFilter = Filter;
+	type ModuleId = SponsorModuleId;
+	type SponseeDeposit = SponseeDeposit;
+	type MaxSponsees = MaxSponsees;
+}
+
+pub type System = system::Module<Test>;
+pub type Balances = pallet_balances::Module<Test>;
+pub type Sponsor = Module<Test>;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	// account 2 is a device without funds.
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 1000), (3, 100)],
+	}.assimilate_storage(&mut t).unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,157 @@
+use crate::{Error, RawEvent, ChargeTransactionPaymentOrSponsor, mock::*};
+use frame_support::{
+	assert_ok, assert_noop,
+	weights::{DispatchInfo, PostDispatchInfo},
+};
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+/// The length of the test transactions, which adds 10 to every fee.
+const LEN: usize = 10;
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+fn remark() -> Call {
+	Call::System(frame_system::Call::remark(vec![]))
+}
+
+/// A call that costs a fee of 15 without a tip.
+fn info() -> DispatchInfo {
+	DispatchInfo { weight: 5, ..Default::default() }
+}
+
+type Pre = <ChargeTransactionPaymentOrSponsor<Test> as SignedExtension>::Pre;
+
+fn pre_dispatch(who: u64, call: &Call, tip: u64) -> Result<Pre, TransactionValidityError> {
+	ChargeTransactionPaymentOrSponsor::<Test>::from(tip).pre_dispatch(&who, call, &info(), LEN)
+}
+
+#[test]
+fn funds_move_in_and_out_of_the_pot() {
+	new_test_ext().execute_with(|| {
+		let pot = Sponsor::pot_of(&1);
+
+		assert_ok!(Sponsor::fund(Origin::signed(1), 500));
+		assert_eq!(Balances::free_balance(1), 500);
+		assert_eq!(Balances::free_balance(pot), 500);
+		assert_eq!(last_event(), TestEvent::sponsor(RawEvent::Funded(1, 500)));
+
+		assert_ok!(Sponsor::defund(Origin::signed(1), 200));
+		assert_eq!(Balances::free_balance(1), 700);
+		assert_eq!(Balances::free_balance(pot), 300);
+		assert_eq!(last_event(), TestEvent::sponsor(RawEvent::Defunded(1, 200)));
+	});
+}
+
+#[test]
+fn sponsoring_reserves_a_deposit_per_account() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Sponsor::sponsor(Origin::signed(1), 2, Filter::Any));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_eq!(Sponsor::sponsees(1), 1);
+
+		// changing the filter does not take another deposit.
+		assert_ok!(Sponsor::sponsor(Origin::signed(1), 2, Filter::RemarkOnly));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_eq!(Sponsor::sponsor_of(2), Some((1, Filter::RemarkOnly)));
+
+		assert_noop!(
+			Sponsor::sponsor(Origin::signed(3), 2, Filter::Any),
+			Error::<Test>::AlreadySponsored,
+		);
+		assert_noop!(Sponsor::unsponsor(Origin::signed(3), 2), Error::<Test>::NotSponsored);
+
+		assert_ok!(Sponsor::unsponsor(Origin::signed(1), 2));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Sponsor::sponsees(1), 0);
+		assert_eq!(Sponsor::sponsor_of(2), None);
+		assert_eq!(last_event(), TestEvent::sponsor(RawEvent::Unsponsored(1, 2)));
+	});
+}
+
+#[test]
+fn whitelists_are_bounded() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Sponsor::sponsor(Origin::signed(1), 2, Filter::Any));
+		assert_ok!(Sponsor::sponsor(Origin::signed(1), 4, Filter::Any));
+		assert_noop!(
+			Sponsor::sponsor(Origin::signed(1), 5, Filter::Any),
+			Error::<Test>::TooManySponsees,
+		);
+	});
+}
+
+#[test]
+fn sponsored_fees_are_paid_from_the_pot() {
+	new_test_ext().execute_with(|| {
+		let pot = Sponsor::pot_of(&1);
+		assert_ok!(Sponsor::fund(Origin::signed(1), 100));
+		assert_ok!(Sponsor::sponsor(Origin::signed(1), 2, Filter::RemarkOnly));
+
+		let pre = pre_dispatch(2, &remark(), 0).expect("the pot pays the fee");
+		assert_eq!(Balances::free_balance(pot), 85);
+
+		// the unused weight is refunded to the pot.
+		let post_info = PostDispatchInfo { actual_weight: Some(2), pays_fee: Default::default() };
+		assert_ok!(ChargeTransactionPaymentOrSponsor::<Test>::post_dispatch(
+			pre,
+			&info(),
+			&post_info,
+			LEN,
+			&Ok(()),
+		));
+		assert_eq!(Balances::free_balance(pot), 88);
+		assert_eq!(Balances::free_balance(2), 0);
+	});
+}
+
+#[test]
+fn filtered_calls_and_tips_are_paid_by_the_sender() {
+	new_test_ext().execute_with(|| {
+		let pot = Sponsor::pot_of(&1);
+		assert_ok!(Sponsor::fund(Origin::signed(1), 100));
+		assert_ok!(Sponsor::sponsor(Origin::signed(1), 3, Filter::RemarkOnly));
+
+		let defund = Call::Sponsor(crate::Call::defund(1));
+		assert_ok!(pre_dispatch(3, &defund, 0));
+		assert_eq!(Balances::free_balance(3), 85);
+
+		assert_ok!(pre_dispatch(3, &remark(), 1));
+		assert_eq!(Balances::free_balance(3), 69);
+		assert_eq!(Balances::free_balance(pot), 100);
+	});
+}
+
+#[test]
+fn an_empty_pot_leaves_the_fee_to_the_sender() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Sponsor::sponsor(Origin::signed(1), 2, Filter::Any));
+
+		let extension = ChargeTransactionPaymentOrSponsor::<Test>::from(0);
+		assert_eq!(extension.payer(&2, &remark(), 15), 2);
+		assert_eq!(
+			pre_dispatch(2, &remark(), 0).err(),
+			Some(InvalidTransaction::Payment.into()),
+		);
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn post_upgrade_checks_sponsee_counts() {
+	use frame_support::StorageMap;
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Sponsor::sponsor(Origin::signed(1), 2, Filter::Any));
+		assert_ok!(Sponsor::sponsor(Origin::signed(1), 3, Filter::Any));
+		assert_ok!(Sponsor::unsponsor(Origin::signed(1), 3));
+		assert_ok!(Sponsor::post_upgrade());
+
+		crate::Sponsees::<Test>::insert(1, 2);
+		assert!(Sponsor::post_upgrade().is_err());
+	});
+}
@@ -0,0 +1,146 @@
+//! RPC interface for the template pallet.
+//!
+//! The stored value is read straight from storage: full clients read their own state, while
+//! light clients fetch a read proof of it from full nodes through their on-demand fetcher.
+
+use std::sync::Arc;
+use std::marker::PhantomData;
+use codec::Decode;
+use futures::{future, FutureExt, TryFutureExt};
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode};
+use jsonrpc_derive::rpc;
+use sc_client_api::{
+	StorageProvider, backend::Backend,
+	light::{future_header, Fetcher, RemoteBlockchain, RemoteReadRequest},
+};
+use sp_blockchain::{Error as ClientError, HeaderBackend};
+use sp_core::{hashing::twox_128, storage::StorageKey};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Template RPC methods.
+#[rpc]
+pub trait TemplateApi<BlockHash> {
+	/// The value stored by the template pallet at the given block, or at the best block.
+	#[rpc(name = "template_something")]
+	fn something(&self, at: Option<BlockHash>) -> BoxFuture<Option<u32>>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// Reading the storage failed.
+	StorageError,
+	/// The stored value could not be decoded.
+	DecodeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::StorageError => 1,
+			Error::DecodeError => 2,
+		}
+	}
+}
+
+fn error(kind: Error, message: &str, e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(kind.into()),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+/// The storage key of the template pallet's `Something`.
+fn something_key() -> Vec<u8> {
+	[twox_128(b"TemplateModule"), twox_128(b"Something")].concat()
+}
+
+fn decode_something(value: Option<Vec<u8>>) -> Result<Option<u32>, RpcError> {
+	value
+		.map(|v| u32::decode(&mut &v[..]))
+		.transpose()
+		.map_err(|e| error(Error::DecodeError, "Unable to decode the stored value.", e))
+}
+
+/// An implementation of the [`TemplateApi`] for full clients.
+pub struct FullTemplate<C, BE, B> {
+	client: Arc<C>,
+	_marker: PhantomData<(BE, B)>,
+}
+
+impl<C, BE, B> FullTemplate<C, BE, B> {
+	/// Create new `FullTemplate` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		FullTemplate { client, _marker: Default::default() }
+	}
+}
+
+impl<C, BE, Block> TemplateApi<<Block as BlockT>::Hash> for FullTemplate<C, BE, Block>
+where
+	Block: BlockT,
+	BE: Backend<Block> + 'static,
+	C: Send + Sync + 'static,
+	C: HeaderBackend<Block> + StorageProvider<Block, BE>,
+{
+	fn something(&self, at: Option<<Block as BlockT>::Hash>) -> BoxFuture<Option<u32>> {
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		let result = self.client.storage(&at, &StorageKey(something_key()))
+			.map_err(|e| error(Error::StorageError, "Unable to read storage.", e))
+			.and_then(|value| decode_something(value.map(|v| v.0)));
+		Box::new(future::ready(result).compat())
+	}
+}
+
+/// An implementation of the [`TemplateApi`] for light clients.
+pub struct LightTemplate<C, F, B> {
+	client: Arc<C>,
+	remote_blockchain: Arc<dyn RemoteBlockchain<B>>,
+	fetcher: Arc<F>,
+}
+
+impl<C, F, B> LightTemplate<C, F, B> {
+	/// Create new `LightTemplate` with the given references to the client, the remote
+	/// blockchain and the fetcher.
+	pub fn new(
+		client: Arc<C>,
+		remote_blockchain: Arc<dyn RemoteBlockchain<B>>,
+		fetcher: Arc<F>,
+	) -> Self {
+		LightTemplate { client, remote_blockchain, fetcher }
+	}
+}
+
+impl<C, F, Block> TemplateApi<<Block as BlockT>::Hash> for LightTemplate<C, F, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static,
+	C: HeaderBackend<Block>,
+	F: Fetcher<Block> + 'static,
+{
+	fn something(&self, at: Option<<Block as BlockT>::Hash>) -> BoxFuture<Option<u32>> {
+		// If the block hash is not supplied assume the best block.
+		let hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let fetcher = self.fetcher.clone();
+		let key = something_key();
+
+		let header = future_header(&*self.remote_blockchain, &*self.fetcher, BlockId::hash(hash))
+			.and_then(move |header| future::ready(
+				header.ok_or_else(|| ClientError::UnknownBlock(format!("{}", hash)))
+			));
+		let value = header
+			.and_then(move |header| fetcher.remote_read(RemoteReadRequest {
+				block: hash,
+				header,
+				keys: vec![key.clone()],
+				retry_count: None,
+			}).map_ok(move |mut values| values.remove(&key).flatten()))
+			.map_err(|e| error(Error::StorageError, "Unable to fetch storage.", e))
+			.and_then(|value| future::ready(decode_something(value)));
+
+		Box::new(value.boxed().compat())
+	}
+}
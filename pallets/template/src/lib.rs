@@ -4,7 +4,15 @@
 /// Learn more about FRAME and the core library of Substrate FRAME pallets:
 /// https://substrate.dev/docs/en/knowledgebase/runtime/frame
 
-use frame_support::{decl_module, decl_storage, decl_event, decl_error, dispatch, traits::Get};
+use sp_std::prelude::*;
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, dispatch, ensure, RuntimeDebug,
+	traits::{
+		Currency, Get, ReservableCurrency,
+		schedule::{Named as ScheduleNamed, DispatchTime, LOWEST_PRIORITY},
+	},
+};
 use frame_system::ensure_signed;
 
 #[cfg(test)]
@@ -17,8 +25,46 @@ mod tests;
 pub trait Trait: frame_system::Trait {
 	/// Because this pallet emits events, it depends on the runtime's definition of an event.
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The overarching call type, which scheduled writes are dispatched as.
+	type Call: From<Call<Self>>;
+
+	/// The overarching origin, which scheduled writes are dispatched with.
+	type Origin: From<<Self as frame_system::Trait>::Origin>
+		+ Into<Result<RawOrigin<Self::AccountId>, <Self as Trait>::Origin>>;
+
+	/// The caller origin, overarching type of all pallets origins.
+	type PalletsOrigin: From<RawOrigin<Self::AccountId>>;
+
+	/// The scheduler which enqueues writes for a future block.
+	type Scheduler: ScheduleNamed<Self::BlockNumber, <Self as Trait>::Call, Self::PalletsOrigin>;
+
+	/// The currency the deposits of scheduled writes are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The deposit reserved for a scheduled write until it runs or is cancelled.
+	type ScheduleDeposit: Get<BalanceOf<Self>>;
+
+	/// How many blocks ahead writes can be scheduled.
+	type MaxScheduleDelay: Get<Self::BlockNumber>;
+}
+
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// The origin of the writes the scheduler dispatches, which no extrinsic can have.
+#[derive(PartialEq, Eq, Clone, RuntimeDebug, Encode, Decode)]
+pub enum RawOrigin<AccountId> {
+	/// The write was scheduled by this account.
+	Scheduled(AccountId),
 }
 
+/// The origin of this pallet.
+pub type Origin<T> = RawOrigin<<T as frame_system::Trait>::AccountId>;
+
+/// The prefix of the identifiers under which writes are scheduled.
+const TEMPLATE_ID: [u8; 8] = *b"template";
+
 // The pallet's runtime storage items.
 // https://substrate.dev/docs/en/knowledgebase/runtime/storage
 decl_storage! {
@@ -29,16 +75,27 @@ decl_storage! {
 		// Learn more about declaring storage items:
 		// https://substrate.dev/docs/en/knowledgebase/runtime/storage#declaring-storage-items
 		Something get(fn something): Option<u32>;
+		/// The deposits and the values of the scheduled writes, by sender and block.
+		ScheduledSets get(fn scheduled_set):
+			double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) T::BlockNumber
+			=> Option<(BalanceOf<T>, u32)>;
 	}
 }
 
 // Pallets use events to inform users when important changes are made.
 // https://substrate.dev/docs/en/knowledgebase/runtime/events
 decl_event!(
-	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId {
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		BlockNumber = <T as frame_system::Trait>::BlockNumber,
+	{
 		/// Event documentation should end with an array that provides descriptive names for event
 		/// parameters. [something, who]
 		SomethingStored(u32, AccountId),
+		/// A write was scheduled for a future block. [at, something, who]
+		SetScheduled(BlockNumber, u32, AccountId),
+		/// A scheduled write was cancelled. [at, who]
+		ScheduledSetCancelled(BlockNumber, AccountId),
 	}
 );
 
@@ -49,6 +106,16 @@ decl_error! {
 		NoneValue,
 		/// Errors should have helpful documentation associated with them.
 		StorageOverflow,
+		/// Writes can only be scheduled for future blocks.
+		ScheduledInPast,
+		/// Writes can only be scheduled up to `MaxScheduleDelay` blocks ahead.
+		ScheduledTooFar,
+		/// The sender already scheduled a write for this block.
+		AlreadyScheduled,
+		/// The sender has no write scheduled for this block.
+		NotScheduled,
+		/// The scheduler did not accept the write.
+		SchedulingFailed,
 	}
 }
 
@@ -56,10 +123,16 @@ decl_error! {
 // These functions materialize as "extrinsics", which are often compared to transactions.
 // Dispatchable functions must be annotated with a weight and must return a DispatchResult.
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+	pub struct Module<T: Trait> for enum Call where origin: <T as frame_system::Trait>::Origin {
 		// Errors must be initialized if they are used by the pallet.
 		type Error = Error<T>;
 
+		/// The deposit reserved for a scheduled write until it runs or is cancelled.
+		const ScheduleDeposit: BalanceOf<T> = T::ScheduleDeposit::get();
+
+		/// How many blocks ahead writes can be scheduled.
+		const MaxScheduleDelay: T::BlockNumber = T::MaxScheduleDelay::get();
+
 		// Events must be initialized if they are used by the pallet.
 		fn deposit_event() = default;
 
@@ -99,5 +172,124 @@ decl_module! {
 				},
 			}
 		}
+
+		/// Schedule `something` to be written to storage at block `at`, as if the sender called
+		/// `do_something` in that block. Every account can schedule one write per block, at most
+		/// `MaxScheduleDelay` blocks ahead, and `ScheduleDeposit` is reserved until it runs or is
+		/// cancelled.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3, 4)]
+		pub fn schedule_set(
+			origin,
+			at: T::BlockNumber,
+			something: u32,
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			let now = frame_system::Module::<T>::block_number();
+			ensure!(at > now, Error::<T>::ScheduledInPast);
+			ensure!(at - now <= T::MaxScheduleDelay::get(), Error::<T>::ScheduledTooFar);
+			ensure!(!ScheduledSets::<T>::contains_key(&who, at), Error::<T>::AlreadyScheduled);
+
+			let deposit = T::ScheduleDeposit::get();
+			T::Currency::reserve(&who, deposit)?;
+			let scheduled = T::Scheduler::schedule_named(
+				Self::schedule_id(&who, at),
+				DispatchTime::At(at),
+				None,
+				LOWEST_PRIORITY,
+				RawOrigin::Scheduled(who.clone()).into(),
+				Call::execute_set(at).into(),
+			);
+			if scheduled.is_err() {
+				T::Currency::unreserve(&who, deposit);
+				Err(Error::<T>::SchedulingFailed)?
+			}
+			ScheduledSets::<T>::insert(&who, at, (deposit, something));
+
+			Self::deposit_event(RawEvent::SetScheduled(at, something, who));
+			Ok(())
+		}
+
+		/// Cancel the write the sender scheduled for block `at`, returning its deposit.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3, 4)]
+		pub fn cancel_set(origin, at: T::BlockNumber) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(ScheduledSets::<T>::contains_key(&who, at), Error::<T>::NotScheduled);
+
+			T::Scheduler::cancel_named(Self::schedule_id(&who, at))
+				.map_err(|_| Error::<T>::NotScheduled)?;
+			Self::return_deposit(&who, at);
+
+			Self::deposit_event(RawEvent::ScheduledSetCancelled(at, who));
+			Ok(())
+		}
+
+		/// Write the value the sender scheduled for block `at`, returning its deposit. This is
+		/// what the scheduler dispatches, in block `at` or later if that block is full, with the
+		/// `Scheduled` origin of the sender; it cannot be called directly.
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2, 3)]
+		pub fn execute_set(origin, at: T::BlockNumber) -> dispatch::DispatchResult {
+			let scheduled: Result<RawOrigin<T::AccountId>, _> =
+				<T as Trait>::Origin::from(origin).into();
+			let RawOrigin::Scheduled(who) =
+				scheduled.map_err(|_| dispatch::DispatchError::BadOrigin)?;
+			ensure!(at <= frame_system::Module::<T>::block_number(), Error::<T>::NotScheduled);
+			let something = Self::return_deposit(&who, at).ok_or(Error::<T>::NotScheduled)?;
+			Something::put(something);
+
+			Self::deposit_event(RawEvent::SomethingStored(something, who));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The identifier of the write `who` scheduled for block `at`.
+	fn schedule_id(who: &T::AccountId, at: T::BlockNumber) -> Vec<u8> {
+		(TEMPLATE_ID, who, at).encode()
+	}
+
+	/// Unreserve the deposit of the write `who` scheduled for block `at`, returning its value.
+	fn return_deposit(who: &T::AccountId, at: T::BlockNumber) -> Option<u32> {
+		let (deposit, something) = ScheduledSets::<T>::take(who, at)?;
+		T::Currency::unreserve(who, deposit);
+		Some(something)
+	}
+
+	/// Check that every scheduled write is in the agenda of the scheduler, whose named tasks have
+	/// the identifiers `scheduled`, and the other way round, and that the deposits of the writes
+	/// of each account are reserved.
+	#[cfg(feature = "try-runtime")]
+	pub fn post_upgrade(
+		scheduled: &sp_std::collections::btree_set::BTreeSet<Vec<u8>>,
+	) -> Result<(), &'static str> {
+		use frame_support::{sp_runtime::traits::Saturating, storage::IterableStorageDoubleMap};
+		use sp_std::collections::btree_map::BTreeMap;
+
+		let mut deposits = BTreeMap::<T::AccountId, BalanceOf<T>>::new();
+		for (who, at, (deposit, _)) in ScheduledSets::<T>::iter() {
+			ensure!(
+				scheduled.contains(&Self::schedule_id(&who, at)),
+				"A scheduled write is missing from the agenda",
+			);
+			let total = deposits.entry(who).or_default();
+			*total = total.saturating_add(deposit);
+		}
+		for (who, total) in deposits {
+			ensure!(
+				T::Currency::reserved_balance(&who) >= total,
+				"The deposits of scheduled writes are not reserved",
+			);
+		}
+		for id in scheduled {
+			if let Ok((TEMPLATE_ID, who, at)) =
+				<([u8; 8], T::AccountId, T::BlockNumber)>::decode(&mut &id[..])
+			{
+				ensure!(
+					ScheduledSets::<T>::contains_key(&who, at),
+					"The agenda holds a write which is not scheduled",
+				);
+			}
+		}
+		Ok(())
 	}
 }
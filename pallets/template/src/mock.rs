@@ -1,13 +1,27 @@
 use crate::{Module, Trait};
 use sp_core::H256;
-use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+use frame_support::{
+	impl_outer_origin, impl_outer_dispatch, parameter_types,
+	traits::OnInitialize,
+	weights::Weight,
+};
 use sp_runtime::{
 	traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
 };
-use frame_system as system;
+use frame_system::{self as system, EnsureRoot};
+use crate as pallet_template;
 
 impl_outer_origin! {
-	pub enum Origin for Test {}
+	pub enum Origin for Test where system = frame_system {
+		pallet_template<T>,
+	}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		frame_system::System,
+		pallet_template::TemplateModule,
+	}
 }
 
 // Configure a mock runtime to test the pallet.
@@ -16,7 +30,7 @@ impl_outer_origin! {
 pub struct Test;
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
-	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockWeight: Weight = 32 * 1024;
 	pub const MaximumBlockLength: u32 = 2 * 1024;
 	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
 }
@@ -24,7 +38,7 @@ parameter_types! {
 impl system::Trait for Test {
 	type BaseCallFilter = ();
 	type Origin = Origin;
-	type Call = ();
+	type Call = Call;
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -43,19 +57,93 @@ impl system::Trait for Test {
 	type AvailableBlockRatio = AvailableBlockRatio;
 	type Version = ();
 	type PalletInfo = ();
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
 }
 
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+}
+
+impl pallet_balances::Trait for Test {
+	type MaxLocks = ();
+	type Balance = u64;
+	type Event = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	// Fits two scheduled `execute_set` calls into a block, but not a third one.
+	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * MaximumBlockWeight::get();
+	pub const MaxScheduledPerBlock: u32 = 50;
+}
+
+impl pallet_scheduler::Trait for Test {
+	type Event = ();
+	type Origin = Origin;
+	type PalletsOrigin = OriginCaller;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = EnsureRoot<u64>;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const ScheduleDeposit: u64 = 10;
+	pub const MaxScheduleDelay: u64 = 100;
+}
+
 impl Trait for Test {
 	type Event = ();
+	type Call = Call;
+	type Origin = Origin;
+	type PalletsOrigin = OriginCaller;
+	type Scheduler = Scheduler;
+	type Currency = Balances;
+	type ScheduleDeposit = ScheduleDeposit;
+	type MaxScheduleDelay = MaxScheduleDelay;
 }
 
+pub type System = system::Module<Test>;
+pub type Balances = pallet_balances::Module<Test>;
+pub type Scheduler = pallet_scheduler::Module<Test>;
 pub type TemplateModule = Module<Test>;
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	// accounts 1 to 5 can pay for the deposits of scheduled writes, account 6 cannot.
+	pallet_balances::GenesisConfig::<Test> {
+		balances: (1..=6).map(|who| (who, if who == 6 { 5 } else { 100 })).collect(),
+	}.assimilate_storage(&mut storage).unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Advance to block `n`, dispatching the calls scheduled on the way.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		Scheduler::on_initialize(System::block_number());
+	}
+}
+
+/// The identifiers of the named tasks in the agenda of the scheduler.
+#[cfg(feature = "try-runtime")]
+pub fn scheduled_ids() -> sp_std::collections::btree_set::BTreeSet<Vec<u8>> {
+	use codec::{Decode, Encode};
+	use frame_support::storage::IterableStorageMap;
+
+	// the fields of scheduled tasks are private, but their encoding starts with the identifier.
+	pallet_scheduler::Agenda::<Test>::iter()
+		.flat_map(|(_, tasks)| tasks.into_iter().flatten())
+		.filter_map(|task| Option::<Vec<u8>>::decode(&mut &task.encode()[..]).ok().flatten())
+		.collect()
 }
@@ -1,5 +1,6 @@
-use crate::{Error, mock::*};
-use frame_support::{assert_ok, assert_noop};
+use crate::{Error, RawOrigin, mock::*};
+use frame_support::{assert_ok, assert_noop, traits::OnInitialize};
+use sp_runtime::traits::BadOrigin;
 
 #[test]
 fn it_works_for_default_value() {
@@ -21,3 +22,129 @@ fn correct_error_for_none_value() {
 		);
 	});
 }
+
+#[test]
+fn scheduled_set_runs_at_the_given_block() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::schedule_set(Origin::signed(1), 3, 42));
+		assert_eq!(Balances::reserved_balance(1), ScheduleDeposit::get());
+
+		run_to_block(2);
+		assert_eq!(TemplateModule::something(), None);
+		run_to_block(3);
+		assert_eq!(TemplateModule::something(), Some(42));
+		// the deposit is returned once the write ran.
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(TemplateModule::scheduled_set(1, 3), None);
+	});
+}
+
+#[test]
+fn scheduled_set_can_be_cancelled() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::schedule_set(Origin::signed(1), 3, 42));
+		// another account's write for the same block is independent.
+		assert_ok!(TemplateModule::schedule_set(Origin::signed(2), 3, 7));
+		assert_ok!(TemplateModule::cancel_set(Origin::signed(1), 3));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_noop!(TemplateModule::cancel_set(Origin::signed(1), 3), Error::<Test>::NotScheduled);
+
+		run_to_block(3);
+		assert_eq!(TemplateModule::something(), Some(7));
+	});
+}
+
+#[test]
+fn correct_errors_for_schedule_set() {
+	new_test_ext().execute_with(|| {
+		run_to_block(3);
+		assert_noop!(
+			TemplateModule::schedule_set(Origin::signed(1), 3, 42),
+			Error::<Test>::ScheduledInPast
+		);
+
+		assert_ok!(TemplateModule::schedule_set(Origin::signed(1), 5, 42));
+		assert_noop!(
+			TemplateModule::schedule_set(Origin::signed(1), 5, 43),
+			Error::<Test>::AlreadyScheduled
+		);
+		assert_noop!(TemplateModule::cancel_set(Origin::signed(1), 4), Error::<Test>::NotScheduled);
+
+		assert_noop!(
+			TemplateModule::schedule_set(Origin::signed(1), 3 + MaxScheduleDelay::get() + 1, 42),
+			Error::<Test>::ScheduledTooFar
+		);
+		assert_noop!(
+			TemplateModule::schedule_set(Origin::signed(6), 5, 42),
+			pallet_balances::Error::<Test, pallet_balances::DefaultInstance>::InsufficientBalance
+		);
+	});
+}
+
+#[test]
+fn scheduled_set_only_runs_when_due() {
+	new_test_ext().execute_with(|| {
+		let scheduled = || Origin::from(RawOrigin::Scheduled(1));
+		assert_noop!(TemplateModule::execute_set(scheduled(), 1), Error::<Test>::NotScheduled);
+		assert_ok!(TemplateModule::schedule_set(Origin::signed(1), 3, 42));
+		// running the write early would return the deposit and leave the block free.
+		assert_noop!(TemplateModule::execute_set(scheduled(), 3), Error::<Test>::NotScheduled);
+		assert_eq!(Balances::reserved_balance(1), ScheduleDeposit::get());
+	});
+}
+
+#[test]
+fn scheduled_set_cannot_be_called_directly() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::schedule_set(Origin::signed(1), 3, 42));
+		run_to_block(2);
+		System::set_block_number(3);
+		// the sender can neither take the deposit back early nor write another value.
+		assert_noop!(TemplateModule::execute_set(Origin::signed(1), 3), BadOrigin);
+		assert_eq!(TemplateModule::scheduled_set(1, 3), Some((ScheduleDeposit::get(), 42)));
+
+		Scheduler::on_initialize(3);
+		assert_eq!(TemplateModule::something(), Some(42));
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn writes_beyond_the_scheduler_weight_are_postponed() {
+	new_test_ext().execute_with(|| {
+		// only two of these fit into the scheduler's weight limit of a block.
+		for who in 1..=5 {
+			assert_ok!(TemplateModule::schedule_set(Origin::signed(who), 3, who as u32));
+		}
+
+		run_to_block(3);
+		assert_eq!(TemplateModule::something(), Some(2));
+		run_to_block(4);
+		assert_eq!(TemplateModule::something(), Some(4));
+		run_to_block(5);
+		assert_eq!(TemplateModule::something(), Some(5));
+		// nothing is left to run.
+		run_to_block(10);
+		assert_eq!(TemplateModule::something(), Some(5));
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn post_upgrade_checks_deposits_and_the_agenda() {
+	use frame_support::{StorageDoubleMap, traits::ReservableCurrency};
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::schedule_set(Origin::signed(1), 3, 42));
+		assert_ok!(TemplateModule::schedule_set(Origin::signed(2), 4, 7));
+		assert_ok!(TemplateModule::post_upgrade(&scheduled_ids()));
+		assert!(TemplateModule::post_upgrade(&Default::default()).is_err());
+
+		crate::ScheduledSets::<Test>::remove(2, 4);
+		assert!(TemplateModule::post_upgrade(&scheduled_ids()).is_err());
+		assert_ok!(TemplateModule::cancel_set(Origin::signed(1), 3));
+		Balances::unreserve(&2, ScheduleDeposit::get());
+		crate::ScheduledSets::<Test>::insert(2, 4, (ScheduleDeposit::get(), 7));
+		assert!(TemplateModule::post_upgrade(&scheduled_ids()).is_err());
+	});
+}
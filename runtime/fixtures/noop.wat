@@ -0,0 +1,8 @@
+;; The smallest contract accepted by pallet_contracts: it imports its memory from the
+;; environment and exports `deploy` and `call` entry points that do nothing.
+;; `noop.wasm` is this module compiled with `wat2wasm noop.wat`.
+(module
+	(import "env" "memory" (memory 1 1))
+	(func (export "deploy"))
+	(func (export "call"))
+)
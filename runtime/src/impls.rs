@@ -0,0 +1,311 @@
+//! Some configurable implementations as associated type for the runtime.
+
+use crate::{
+	AccountId, Balance, Balances, Fees, System, FeeTreasuryShare, MinGasPrice, CENTS,
+};
+#[cfg(not(feature = "babe"))]
+use crate::Aura;
+#[cfg(feature = "babe")]
+use crate::Babe;
+use frame_support::{
+	ConsensusEngineId,
+	traits::{Currency, FindAuthor, Imbalance, OnUnbalanced},
+	weights::{
+		WeightToFeePolynomial, WeightToFeeCoefficient, WeightToFeeCoefficients,
+		constants::ExtrinsicBaseWeight,
+	},
+};
+use sp_core::{sr25519, U256};
+use sp_runtime::{MultiSigner, Perbill, traits::IdentifyAccount};
+use smallvec::smallvec;
+
+type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
+
+/// Finds the author of the current block from the Aura pre-runtime digest and maps the
+/// authority key to the account it signs for.
+#[cfg(not(feature = "babe"))]
+pub struct AuraAuthor;
+#[cfg(not(feature = "babe"))]
+impl FindAuthor<AccountId> for AuraAuthor {
+	fn find_author<'a, I>(digests: I) -> Option<AccountId>
+		where I: 'a + IntoIterator<Item=(ConsensusEngineId, &'a [u8])>
+	{
+		let index = <Aura as FindAuthor<u32>>::find_author(digests)?;
+		let authority = Aura::authorities().get(index as usize)?.clone();
+		Some(MultiSigner::from(sr25519::Public::from(authority)).into_account())
+	}
+}
+
+/// Finds the author of the current block from the BABE pre-runtime digest and maps the
+/// authority key to the account it signs for.
+#[cfg(feature = "babe")]
+pub struct BabeAuthor;
+#[cfg(feature = "babe")]
+impl FindAuthor<AccountId> for BabeAuthor {
+	fn find_author<'a, I>(digests: I) -> Option<AccountId>
+		where I: 'a + IntoIterator<Item=(ConsensusEngineId, &'a [u8])>
+	{
+		let index = <Babe as FindAuthor<u32>>::find_author(digests)?;
+		let (authority, _) = Babe::authorities().get(index as usize)?.clone();
+		Some(MultiSigner::from(sr25519::Public::from(authority)).into_account())
+	}
+}
+
+/// Finds the author of the current block with the consensus engine the runtime is built for.
+#[cfg(not(feature = "babe"))]
+pub type BlockAuthor = AuraAuthor;
+#[cfg(feature = "babe")]
+pub type BlockAuthor = BabeAuthor;
+
+/// Pays funds to the author of the current block. Falls back to the treasury pot when no
+/// author can be found, e.g. outside of block production.
+pub struct ToAuthor;
+impl OnUnbalanced<NegativeImbalance> for ToAuthor {
+	fn on_nonzero_unbalanced(amount: NegativeImbalance) {
+		let digest = System::digest();
+		let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
+		match BlockAuthor::find_author(pre_runtime_digests) {
+			Some(author) => Fees::reward_author(&author, amount),
+			None => Fees::on_unbalanced(amount),
+		}
+	}
+}
+
+/// Splits dust and transaction fees between the treasury pot and the block author, according
+/// to `FeeTreasuryShare`.
+pub struct DealWithFees;
+impl OnUnbalanced<NegativeImbalance> for DealWithFees {
+	fn on_nonzero_unbalanced(amount: NegativeImbalance) {
+		let treasury_share = FeeTreasuryShare::get().deconstruct() as u32;
+		let (to_treasury, to_author) = amount.ration(treasury_share, 100 - treasury_share);
+		Fees::on_unbalanced(to_treasury);
+		ToAuthor::on_unbalanced(to_author);
+	}
+}
+
+/// Handles converting a weight scalar to a fee value, based on the scale and granularity of the
+/// node's balance type.
+///
+/// This should typically create a mapping between the following ranges:
+///   - [0, MaximumBlockWeight]
+///   - [Balance::min, Balance::max]
+///
+/// Yet, it can be used for any other sort of change to weight-fee. Some examples being:
+///   - Setting it to `0` will essentially disable the weight fee.
+///   - Setting it to `1` will cause the literal `#[weight = x]` values to be charged.
+///
+/// Here the base weight of an extrinsic is mapped to 1/10 of a CENT, which puts a basic balance
+/// transfer at a fraction of a CENT before the length fee and the fee multiplier are applied.
+pub struct WeightToFee;
+impl WeightToFeePolynomial for WeightToFee {
+	type Balance = Balance;
+	fn polynomial() -> WeightToFeeCoefficients<Self::Balance> {
+		let p = CENTS;
+		let q = 10 * Balance::from(ExtrinsicBaseWeight::get());
+		smallvec![WeightToFeeCoefficient {
+			degree: 1,
+			negative: false,
+			coeff_frac: Perbill::from_rational_approximation(p % q, q),
+			coeff_integer: p / q,
+		}]
+	}
+}
+
+/// Charges the same price for every unit of EVM gas.
+pub struct FixedGasPrice;
+impl pallet_evm::FeeCalculator for FixedGasPrice {
+	fn min_gas_price() -> U256 {
+		MinGasPrice::get().into()
+	}
+}
+
+#[cfg(test)]
+mod multiplier_tests {
+	use super::*;
+	use crate::{
+		Runtime, System, TransactionPayment, AvailableBlockRatio, MaximumBlockWeight,
+		MinimumMultiplier, TargetBlockFullness,
+	};
+	use frame_support::weights::{Weight, DispatchClass, GetDispatchInfo};
+	use pallet_transaction_payment::Multiplier;
+	use sp_runtime::{FixedPointNumber, traits::Convert};
+
+	type FeeMultiplierUpdate =
+		<Runtime as pallet_transaction_payment::Trait>::FeeMultiplierUpdate;
+
+	fn max_normal() -> Weight {
+		AvailableBlockRatio::get() * MaximumBlockWeight::get()
+	}
+
+	fn target() -> Weight {
+		TargetBlockFullness::get() * max_normal()
+	}
+
+	fn run_with_system_weight<F>(w: Weight, assertions: F) where F: Fn() -> () {
+		let mut t: sp_io::TestExternalities = frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap()
+			.into();
+		t.execute_with(|| {
+			System::set_block_limits(w, 0);
+			assertions()
+		});
+	}
+
+	#[test]
+	fn base_weight_costs_a_tenth_of_a_cent() {
+		assert_eq!(WeightToFee::calc(&ExtrinsicBaseWeight::get()), CENTS / 10);
+	}
+
+	#[test]
+	fn transfer_costs_a_fraction_of_a_cent() {
+		let call = crate::Call::Balances(crate::BalancesCall::transfer(Default::default(), 1));
+		let info = call.get_dispatch_info();
+		assert_eq!(info.class, DispatchClass::Normal);
+
+		let fee = WeightToFee::calc(&(info.weight + ExtrinsicBaseWeight::get()));
+		assert!(fee > CENTS / 10, "{} !> {}", fee, CENTS / 10);
+		assert!(fee < CENTS, "{} !< {}", fee, CENTS);
+	}
+
+	#[test]
+	fn multiplier_starts_at_one() {
+		run_with_system_weight(0, || {
+			assert_eq!(TransactionPayment::next_fee_multiplier(), Multiplier::one());
+		})
+	}
+
+	#[test]
+	fn multiplier_grows_under_sustained_full_blocks() {
+		run_with_system_weight(max_normal(), || {
+			let mut fm = Multiplier::one();
+			// a day worth of completely full blocks.
+			for _ in 0..crate::DAYS {
+				let next = FeeMultiplierUpdate::convert(fm);
+				assert!(next > fm, "{:?} !> {:?}", next, fm);
+				fm = next;
+			}
+			// the adjustment is slow: a full day of congestion should not blow fees up.
+			assert!(fm < Multiplier::saturating_from_integer(2), "{:?} !< 2", fm);
+		})
+	}
+
+	#[test]
+	fn multiplier_can_grow_from_minimum() {
+		let minimum = MinimumMultiplier::get();
+		// if the minimum is too small, then this will not change, and we are doomed forever.
+		run_with_system_weight(target() * 101 / 100, || {
+			let next = FeeMultiplierUpdate::convert(minimum);
+			assert!(next > minimum, "{:?} !> {:?}", next, minimum);
+		})
+	}
+
+	#[test]
+	fn multiplier_is_stable_at_target_fullness() {
+		run_with_system_weight(target(), || {
+			let mut fm = Multiplier::saturating_from_rational(3, 2);
+			for _ in 0..100 {
+				let next = FeeMultiplierUpdate::convert(fm);
+				assert_eq!(next, fm);
+				fm = next;
+			}
+		})
+	}
+
+	#[test]
+	fn multiplier_converges_to_minimum_on_empty_blocks() {
+		let minimum = MinimumMultiplier::get();
+		run_with_system_weight(0, || {
+			let mut fm = minimum.saturating_mul(Multiplier::saturating_from_integer(2));
+			let mut iterations = 0u32;
+			loop {
+				let next = FeeMultiplierUpdate::convert(fm);
+				assert!(next <= fm, "{:?} !<= {:?}", next, fm);
+				if next == fm { break; }
+				fm = next;
+				iterations += 1;
+				assert!(iterations < 1_000_000, "multiplier did not converge");
+			}
+			assert_eq!(fm, minimum);
+		})
+	}
+}
+
+#[cfg(all(test, not(feature = "babe")))]
+mod fee_split_tests {
+	use super::*;
+	use crate::{Runtime, Event, DigestItem, DOLLARS};
+	use codec::Encode;
+	use sp_consensus_aura::{AURA_ENGINE_ID, sr25519::AuthorityId as AuraId};
+	use sp_runtime::generic::Digest;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap();
+		pallet_aura::GenesisConfig::<Runtime> {
+			authorities: vec![
+				AuraId::from(sr25519::Public::from_raw([1; 32])),
+				AuraId::from(sr25519::Public::from_raw([2; 32])),
+			],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn initialize_block(digest: Digest<crate::Hash>) {
+		System::initialize(
+			&1,
+			&Default::default(),
+			&Default::default(),
+			&digest,
+			frame_system::InitKind::Full,
+		);
+	}
+
+	fn aura_digest(slot: u64) -> Digest<crate::Hash> {
+		Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] }
+	}
+
+	fn fees_events() -> Vec<pallet_fees::Event<Runtime>> {
+		System::events().into_iter().filter_map(|r| match r.event {
+			Event::pallet_fees(e) => Some(e),
+			_ => None,
+		}).collect()
+	}
+
+	#[test]
+	fn fees_are_split_between_treasury_and_author() {
+		new_test_ext().execute_with(|| {
+			// slot 3 with two authorities is authored by the second one.
+			initialize_block(aura_digest(3));
+			let author = AccountId::from([2; 32]);
+
+			DealWithFees::on_unbalanced(Balances::issue(10 * DOLLARS));
+
+			assert_eq!(Fees::pot(), 8 * DOLLARS);
+			assert_eq!(Balances::free_balance(&author), 2 * DOLLARS);
+			assert_eq!(Balances::free_balance(&AccountId::from([1; 32])), 0);
+			assert_eq!(Balances::total_issuance(), 10 * DOLLARS);
+			assert_eq!(fees_events(), vec![
+				pallet_fees::RawEvent::TreasuryDeposit(8 * DOLLARS),
+				pallet_fees::RawEvent::AuthorRewarded(author, 2 * DOLLARS),
+			]);
+		});
+	}
+
+	#[test]
+	fn fees_go_to_treasury_without_author() {
+		new_test_ext().execute_with(|| {
+			initialize_block(Default::default());
+
+			DealWithFees::on_unbalanced(Balances::issue(10 * DOLLARS));
+
+			assert_eq!(Fees::pot(), 10 * DOLLARS);
+			assert_eq!(Balances::total_issuance(), 10 * DOLLARS);
+			assert_eq!(fees_events(), vec![
+				pallet_fees::RawEvent::TreasuryDeposit(8 * DOLLARS),
+				pallet_fees::RawEvent::TreasuryDeposit(2 * DOLLARS),
+			]);
+		});
+	}
+}
@@ -7,33 +7,39 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use sp_std::prelude::*;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use codec::{Encode, Decode};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, H160, H256, U256};
 use sp_runtime::{
 	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
+	FixedPointNumber, ModuleId, RuntimeDebug,
 	transaction_validity::{TransactionValidity, TransactionSource},
 };
 use sp_runtime::traits::{
-	BlakeTwo256, Block as BlockT, IdentityLookup, Verify, IdentifyAccount, NumberFor, Saturating,
+	BlakeTwo256, Block as BlockT, Verify, IdentifyAccount, NumberFor, Saturating, ConvertInto,
 };
 use sp_api::impl_runtime_apis;
+#[cfg(not(feature = "babe"))]
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
 use pallet_grandpa::fg_primitives;
 use sp_version::RuntimeVersion;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
+use pallet_transaction_payment::{Multiplier, TargetedFeeAdjustment};
+use pallet_contracts_rpc_runtime_api::ContractExecResult;
 
 // A few exports that help ease life for downstream crates.
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
 pub use pallet_timestamp::Call as TimestampCall;
 pub use pallet_balances::Call as BalancesCall;
-pub use sp_runtime::{Permill, Perbill};
+pub use pallet_contracts::Schedule as ContractsSchedule;
+pub use sp_runtime::{Percent, Permill, Perbill, Perquintill};
 pub use frame_support::{
 	construct_runtime, parameter_types, StorageValue,
-	traits::{KeyOwnerProofSystem, Randomness},
+	traits::{KeyOwnerProofSystem, Randomness, InstanceFilter},
 	weights::{
-		Weight, IdentityFee,
+		Weight,
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 	},
 };
@@ -41,6 +47,20 @@ pub use frame_support::{
 /// Import the template pallet.
 pub use pallet_template;
 
+/// Implementations of some helper traits passed into runtime modules as associated types.
+pub mod impls;
+use impls::{DealWithFees, FixedGasPrice, WeightToFee};
+
+/// The transaction priority policy of the runtime.
+pub mod priority;
+
+/// Dry runs of runtime upgrades.
+#[cfg(feature = "try-runtime")]
+pub mod try_runtime;
+
+#[cfg(test)]
+mod tests;
+
 /// An index to a block.
 pub type BlockNumber = u32;
 
@@ -58,6 +78,15 @@ pub type AccountIndex = u32;
 /// Balance of an account.
 pub type Balance = u128;
 
+/// Identifier of a user-created asset.
+pub type AssetId = u32;
+
+/// Identifier of an NFT collection.
+pub type CollectionId = pallet_nft::CollectionId;
+
+/// Identifier of a token within an NFT collection.
+pub type TokenId = pallet_nft::TokenId;
+
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
@@ -83,22 +112,31 @@ pub mod opaque {
 	/// Opaque block identifier type.
 	pub type BlockId = generic::BlockId<Block>;
 
+	#[cfg(not(feature = "babe"))]
 	impl_opaque_keys! {
 		pub struct SessionKeys {
 			pub aura: Aura,
 			pub grandpa: Grandpa,
 		}
 	}
+
+	#[cfg(feature = "babe")]
+	impl_opaque_keys! {
+		pub struct SessionKeys {
+			pub babe: Babe,
+			pub grandpa: Grandpa,
+		}
+	}
 }
 
 pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-template"),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 2,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
@@ -110,6 +148,23 @@ pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+// BABE epochs; a slot lasts as long as a block, so an epoch is as many slots as blocks.
+pub const EPOCH_DURATION_IN_BLOCKS: BlockNumber = 10 * MINUTES;
+pub const EPOCH_DURATION_IN_SLOTS: u64 = EPOCH_DURATION_IN_BLOCKS as u64;
+
+/// 1 in 4 BABE slots (on average, not counting collisions) will have a primary block author.
+pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
+
+// Money is measured in fractions of a DOLLAR of the native token.
+pub const MILLICENTS: Balance = 1_000_000_000;
+pub const CENTS: Balance = 1_000 * MILLICENTS;
+pub const DOLLARS: Balance = 100 * CENTS;
+
+/// The deposit for keeping `items` storage items of `bytes` total bytes around.
+pub const fn deposit(items: u32, bytes: u32) -> Balance {
+	items as Balance * 15 * CENTS + (bytes as Balance) * 6 * CENTS
+}
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -123,7 +178,12 @@ parameter_types! {
 	pub const BlockHashCount: BlockNumber = 2400;
 	/// We allow for 2 seconds of compute with a 6 second average block time.
 	pub const MaximumBlockWeight: Weight = 2 * WEIGHT_PER_SECOND;
+	/// The share of the block `Normal` extrinsics can use. The rest is reserved for
+	/// `Operational` ones, which can use the whole block.
 	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	/// The share of the block `Normal` extrinsics without a priority boost can use. The rest of
+	/// the `Normal` share is reserved for boosted calls, see the `priority` module.
+	pub const UnboostedBlockRatio: Perbill = Perbill::from_percent(60);
 	/// Assume 10% of weight for average on_initialize calls.
 	pub MaximumExtrinsicWeight: Weight = AvailableBlockRatio::get()
 		.saturating_sub(Perbill::from_percent(10)) * MaximumBlockWeight::get();
@@ -141,7 +201,7 @@ impl frame_system::Trait for Runtime {
 	/// The aggregated dispatch type that is available for extrinsics.
 	type Call = Call;
 	/// The lookup mechanism to get account ID from whatever is passed in dispatchers.
-	type Lookup = IdentityLookup<AccountId>;
+	type Lookup = Indices;
 	/// The index type for storing how many extrinsics an account has signed.
 	type Index = Index;
 	/// The index type for blocks.
@@ -192,10 +252,48 @@ impl frame_system::Trait for Runtime {
 	type SystemWeightInfo = ();
 }
 
+#[cfg(not(feature = "babe"))]
 impl pallet_aura::Trait for Runtime {
 	type AuthorityId = AuraId;
 }
 
+#[cfg(feature = "babe")]
+parameter_types! {
+	pub const EpochDuration: u64 = EPOCH_DURATION_IN_SLOTS;
+	pub const ExpectedBlockTime: u64 = MILLISECS_PER_BLOCK;
+}
+
+#[cfg(feature = "babe")]
+impl pallet_babe::Trait for Runtime {
+	type EpochDuration = EpochDuration;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	// The authority set never changes without a session pallet.
+	type EpochChangeTrigger = pallet_babe::SameAuthoritiesForever;
+
+	type KeyOwnerProofSystem = ();
+
+	type KeyOwnerProof = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::Proof;
+
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = ();
+
+	type WeightInfo = ();
+}
+
+/// The source of on-chain randomness. With BABE it is derived from the VRF outputs of block
+/// authors, otherwise from the hashes of recent blocks.
+#[cfg(feature = "babe")]
+pub type RandomnessSource = Babe;
+#[cfg(not(feature = "babe"))]
+pub type RandomnessSource = RandomnessCollectiveFlip;
+
 impl pallet_grandpa::Trait for Runtime {
 	type Event = Event;
 	type Call = Call;
@@ -222,13 +320,17 @@ parameter_types! {
 impl pallet_timestamp::Trait for Runtime {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
+	#[cfg(not(feature = "babe"))]
 	type OnTimestampSet = Aura;
+	#[cfg(feature = "babe")]
+	type OnTimestampSet = Babe;
 	type MinimumPeriod = MinimumPeriod;
 	type WeightInfo = ();
 }
 
 parameter_types! {
 	pub const ExistentialDeposit: u128 = 500;
+	/// Vesting holds at most a single lock per account, so it only takes one of these slots.
 	pub const MaxLocks: u32 = 50;
 }
 
@@ -238,56 +340,447 @@ impl pallet_balances::Trait for Runtime {
 	type Balance = Balance;
 	/// The ubiquitous event type.
 	type Event = Event;
-	type DustRemoval = ();
+	type DustRemoval = DealWithFees;
 	type ExistentialDeposit = ExistentialDeposit;
 	type AccountStore = System;
 	type WeightInfo = ();
 }
 
+parameter_types! {
+	/// The deposit reserved when claiming an account index.
+	pub const IndexDeposit: Balance = DOLLARS;
+}
+
+impl pallet_indices::Trait for Runtime {
+	/// The type for short account indices.
+	type AccountIndex = AccountIndex;
+	type Currency = Balances;
+	type Deposit = IndexDeposit;
+	type Event = Event;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const MinVestedTransfer: Balance = 100 * DOLLARS;
+}
+
+impl pallet_vesting::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BlockNumberToBalance = ConvertInto;
+	type MinVestedTransfer = MinVestedTransfer;
+	type WeightInfo = ();
+}
+
 parameter_types! {
 	pub const TransactionByteFee: Balance = 1;
+	/// The portion of the normal block weight we aim to fill. Fees go up above it and down below.
+	pub const TargetBlockFullness: Perquintill = Perquintill::from_percent(25);
+	/// How fast the fee multiplier reacts to the distance from the target fullness.
+	pub AdjustmentVariable: Multiplier = Multiplier::saturating_from_rational(1, 100_000);
+	/// The multiplier never drops below this, so that it can always grow again.
+	pub MinimumMultiplier: Multiplier = Multiplier::saturating_from_rational(1, 1_000_000_000u128);
 }
 
 impl pallet_transaction_payment::Trait for Runtime {
 	type Currency = Balances;
-	type OnTransactionPayment = ();
+	type OnTransactionPayment = DealWithFees;
 	type TransactionByteFee = TransactionByteFee;
-	type WeightToFee = IdentityFee<Balance>;
-	type FeeMultiplierUpdate = ();
+	type WeightToFee = WeightToFee;
+	type FeeMultiplierUpdate =
+		TargetedFeeAdjustment<Self, TargetBlockFullness, AdjustmentVariable, MinimumMultiplier>;
+}
+
+parameter_types! {
+	pub const FeesModuleId: ModuleId = ModuleId(*b"py/feesp");
+	/// The share of dust and fees that goes to the treasury pot. The rest goes to the block author.
+	pub const FeeTreasuryShare: Percent = Percent::from_percent(80);
+}
+
+impl pallet_fees::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type ModuleId = FeesModuleId;
+}
+
+parameter_types! {
+	pub const AssetDeposit: Balance = 100 * DOLLARS;
+	// One storage item; key size is 16+4; value size without name and symbol is 16+1+1+1.
+	pub const MetadataDepositBase: Balance = deposit(1, 39);
+	// Additional byte of name or symbol.
+	pub const MetadataDepositPerByte: Balance = deposit(0, 1);
+	pub const StringLimit: u32 = 50;
+}
+
+impl pallet_assets::Trait for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type Currency = Balances;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type StringLimit = StringLimit;
+}
+
+parameter_types! {
+	pub const CollectionDeposit: Balance = 10 * DOLLARS;
+	// One storage item; key size is 16+8; value size without data is 16.
+	pub const NftMetadataDepositBase: Balance = deposit(1, 40);
+	// Additional byte of token data.
+	pub const NftMetadataDepositPerByte: Balance = deposit(0, 1);
+	pub const NftStringLimit: u32 = 128;
+}
+
+impl pallet_nft::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type CollectionDeposit = CollectionDeposit;
+	type MetadataDepositBase = NftMetadataDepositBase;
+	type MetadataDepositPerByte = NftMetadataDepositPerByte;
+	type StringLimit = NftStringLimit;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	// One storage item; key size is 32; value is size 4+258 (the encoded `IdentityInfo` with
+	// a display name and a few short fields).
+	pub const BasicDeposit: Balance = deposit(1, 258);
+	// Additional field of 66 bytes, a raw key and value of up to 32 bytes each.
+	pub const FieldDeposit: Balance = deposit(0, 66);
+	// One storage item; key size is 32; value size is 32+33 (the super and the sub name).
+	pub const SubAccountDeposit: Balance = deposit(1, 53);
+	pub const MaxSubAccounts: u32 = 100;
+	pub const MaxAdditionalFields: u32 = 100;
+	pub const MaxRegistrars: u32 = 20;
+}
+
+impl pallet_identity::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BasicDeposit = BasicDeposit;
+	type FieldDeposit = FieldDeposit;
+	type SubAccountDeposit = SubAccountDeposit;
+	type MaxSubAccounts = MaxSubAccounts;
+	type MaxAdditionalFields = MaxAdditionalFields;
+	type MaxRegistrars = MaxRegistrars;
+	// Deposits of slashed identities go to the treasury pot.
+	type Slashed = Fees;
+	// Only root can add registrars or remove identities by force.
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type RegistrarOrigin = frame_system::EnsureRoot<AccountId>;
+	type WeightInfo = ();
+}
+
+/// The display name of `who`, or `parent/sub` if `who` is a named sub-account of an identity.
+///
+/// Only names stored as raw bytes can be displayed; hashed names resolve to `None`.
+pub fn display_name(who: &AccountId) -> Option<Vec<u8>> {
+	use pallet_identity::Data;
+	let display = |who: &AccountId| match Identity::identity(who)?.info.display {
+		Data::Raw(name) => Some(name),
+		_ => None,
+	};
+
+	if let Some(name) = display(who) {
+		return Some(name);
+	}
+	let (parent, sub) = Identity::super_of(who)?;
+	let mut name = display(&parent)?;
+	name.push(b'/');
+	match sub {
+		Data::Raw(sub) => name.extend(sub),
+		_ => return None,
+	}
+	Some(name)
+}
+
+impl pallet_utility::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	// One storage item; key size is 32; value is size 4+4+16+32 bytes = 56 bytes.
+	pub const DepositBase: Balance = deposit(1, 88);
+	// Additional storage item size of 32 bytes.
+	pub const DepositFactor: Balance = deposit(0, 32);
+	pub const MaxSignatories: u16 = 100;
+}
+
+impl pallet_multisig::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type DepositBase = DepositBase;
+	type DepositFactor = DepositFactor;
+	type MaxSignatories = MaxSignatories;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	// One storage item; key size 32, value size 8.
+	pub const ProxyDepositBase: Balance = deposit(1, 8);
+	// Additional storage item size of 33 bytes.
+	pub const ProxyDepositFactor: Balance = deposit(0, 33);
+	pub const MaxProxies: u16 = 32;
+	pub const AnnouncementDepositBase: Balance = deposit(1, 8);
+	pub const AnnouncementDepositFactor: Balance = deposit(0, 66);
+	pub const MaxPending: u16 = 32;
+}
+
+/// The type used to represent the kinds of proxying allowed, and the calls a sponsor pays for.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+pub enum ProxyType {
+	/// Any call.
+	Any,
+	/// Any call that cannot move funds out of the proxied account.
+	NonTransfer,
+	/// Calls that administer the chain.
+	Governance,
+	/// Calls into the template pallet only.
+	TemplateOnly,
+}
+
+impl Default for ProxyType {
+	fn default() -> Self {
+		Self::Any
+	}
+}
+
+impl InstanceFilter<Call> for ProxyType {
+	fn filter(&self, c: &Call) -> bool {
+		match self {
+			ProxyType::Any => true,
+			// Also excluded are the calls that let another account move funds later on: an NFT
+			// approval, a sponsorship spending the pot of the sender, and the registrar fee
+			// of a judgement.
+			ProxyType::NonTransfer => !matches!(c,
+				Call::Balances(..) |
+				Call::Vesting(pallet_vesting::Call::vested_transfer(..)) |
+				Call::Assets(pallet_assets::Call::transfer(..)) |
+				Call::Nft(pallet_nft::Call::transfer(..)) |
+				Call::Nft(pallet_nft::Call::approve(..)) |
+				Call::Contracts(..) |
+				Call::EVM(..) |
+				Call::Sponsor(pallet_sponsor::Call::fund(..)) |
+				Call::Sponsor(pallet_sponsor::Call::defund(..)) |
+				Call::Sponsor(pallet_sponsor::Call::sponsor(..)) |
+				Call::Identity(pallet_identity::Call::request_judgement(..)) |
+				Call::Indices(pallet_indices::Call::transfer(..))
+			),
+			ProxyType::Governance => matches!(c,
+				Call::Sudo(..) |
+				Call::Utility(..)
+			),
+			ProxyType::TemplateOnly => matches!(c, Call::TemplateModule(..)),
+		}
+	}
+
+	fn is_superset(&self, o: &Self) -> bool {
+		match (self, o) {
+			(x, y) if x == y => true,
+			(ProxyType::Any, _) => true,
+			(_, ProxyType::Any) => false,
+			(ProxyType::NonTransfer, _) => true,
+			_ => false,
+		}
+	}
+}
+
+impl pallet_proxy::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type ProxyType = ProxyType;
+	type ProxyDepositBase = ProxyDepositBase;
+	type ProxyDepositFactor = ProxyDepositFactor;
+	type MaxProxies = MaxProxies;
+	type WeightInfo = ();
+	type MaxPending = MaxPending;
+	type CallHasher = BlakeTwo256;
+	type AnnouncementDepositBase = AnnouncementDepositBase;
+	type AnnouncementDepositFactor = AnnouncementDepositFactor;
 }
 
+parameter_types! {
+	pub const TombstoneDeposit: Balance = 16 * MILLICENTS;
+	pub const RentByteFee: Balance = 4 * MILLICENTS;
+	pub const RentDepositOffset: Balance = 1000 * MILLICENTS;
+	pub const SurchargeReward: Balance = 150 * MILLICENTS;
+}
+
+parameter_types! {
+	pub const RateLimitPeriod: BlockNumber = MINUTES;
+	pub const MaxTransactionsPerPeriod: u32 = 50;
+}
+
+impl pallet_rate_limit::Trait for Runtime {
+	type Period = RateLimitPeriod;
+	type MaxTransactions = MaxTransactionsPerPeriod;
+}
+
+parameter_types! {
+	pub const SponsorModuleId: ModuleId = ModuleId(*b"py/spnsr");
+	// One storage item; key size is 16+32; value size is 32+1.
+	pub const SponseeDeposit: Balance = deposit(1, 81);
+	pub const MaxSponsees: u32 = 1_000;
+}
+
+impl pallet_sponsor::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type CallFilter = ProxyType;
+	type ModuleId = SponsorModuleId;
+	type SponseeDeposit = SponseeDeposit;
+	type MaxSponsees = MaxSponsees;
+}
+
+impl pallet_contracts::Trait for Runtime {
+	type Time = Timestamp;
+	type Randomness = RandomnessSource;
+	type Currency = Balances;
+	type Event = Event;
+	type DetermineContractAddress = pallet_contracts::SimpleAddressDeterminer<Runtime>;
+	type TrieIdGenerator = pallet_contracts::TrieIdFromParentCounter<Runtime>;
+	type RentPayment = ();
+	type SignedClaimHandicap = pallet_contracts::DefaultSignedClaimHandicap;
+	type TombstoneDeposit = TombstoneDeposit;
+	type StorageSizeOffset = pallet_contracts::DefaultStorageSizeOffset;
+	type RentByteFee = RentByteFee;
+	type RentDepositOffset = RentDepositOffset;
+	type SurchargeReward = SurchargeReward;
+	type MaxDepth = pallet_contracts::DefaultMaxDepth;
+	type MaxValueSize = pallet_contracts::DefaultMaxValueSize;
+	type WeightPrice = pallet_transaction_payment::Module<Self>;
+}
+
+parameter_types! {
+	/// The chain id Ethereum transactions are signed for.
+	pub const ChainId: u64 = 42;
+	/// A simple transfer of 21_000 gas costs a fiftieth of a CENT.
+	pub const MinGasPrice: Balance = MILLICENTS / 1_000;
+	/// A block full of normal extrinsics fits 20_000_000 gas.
+	pub const WeightPerGas: Weight = 75_000;
+	/// The receipts of Ethereum transactions are kept for a day.
+	pub const ReceiptRetention: BlockNumber = DAYS;
+}
+
+impl pallet_evm::Trait for Runtime {
+	type FeeCalculator = FixedGasPrice;
+	type CallOrigin = pallet_evm::EnsureAddressTruncated;
+	type WithdrawOrigin = pallet_evm::EnsureAddressTruncated;
+	type AddressMapping = pallet_evm::HashedAddressMapping<BlakeTwo256>;
+	type Currency = Balances;
+	type Event = Event;
+	type Precompiles = (
+		pallet_evm::precompiles::ECRecover,
+		pallet_evm::precompiles::Sha256,
+		pallet_evm::precompiles::Ripemd160,
+		pallet_evm::precompiles::Identity,
+	);
+	type ChainId = ChainId;
+}
+
+impl pallet_eth::Trait for Runtime {
+	type Event = Event;
+	type WeightPerGas = WeightPerGas;
+	type ReceiptRetention = ReceiptRetention;
+}
+
+/// The receipt of an Ethereum transaction, as kept for `eth_getTransactionReceipt`.
+pub type EthReceipt = pallet_eth::Receipt<BlockNumber>;
+
 impl pallet_sudo::Trait for Runtime {
 	type Event = Event;
 	type Call = Call;
 }
 
+parameter_types! {
+	/// Scheduled calls may use up to 80% of a block, leaving room for mandatory extrinsics.
+	pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * MaximumBlockWeight::get();
+	pub const MaxScheduledPerBlock: u32 = 50;
+}
+
+impl pallet_scheduler::Trait for Runtime {
+	type Event = Event;
+	type Origin = Origin;
+	type PalletsOrigin = OriginCaller;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const ScheduleDeposit: Balance = DOLLARS;
+	pub const MaxScheduleDelay: BlockNumber = 7 * DAYS;
+}
+
 /// Configure the template pallet in pallets/template.
 impl pallet_template::Trait for Runtime {
 	type Event = Event;
+	type Call = Call;
+	type Origin = Origin;
+	type PalletsOrigin = OriginCaller;
+	type Scheduler = Scheduler;
+	type Currency = Balances;
+	type ScheduleDeposit = ScheduleDeposit;
+	type MaxScheduleDelay = MaxScheduleDelay;
 }
 
-// Create the runtime by composing the FRAME pallets that were previously configured.
-construct_runtime!(
-	pub enum Runtime where
-		Block = Block,
-		NodeBlock = opaque::Block,
-		UncheckedExtrinsic = UncheckedExtrinsic
-	{
-		System: frame_system::{Module, Call, Config, Storage, Event<T>},
-		RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Module, Call, Storage},
-		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
-		Aura: pallet_aura::{Module, Config<T>, Inherent},
-		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
-		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
-		TransactionPayment: pallet_transaction_payment::{Module, Storage},
-		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
-		// Include the custom logic from the template pallet in the runtime.
-		TemplateModule: pallet_template::{Module, Call, Storage, Event<T>},
-	}
+/// Create the runtime by composing the FRAME pallets that were previously configured, with the
+/// given block authoring pallet.
+macro_rules! construct_runtime_with_consensus {
+	($($consensus:tt)*) => {
+		construct_runtime!(
+			pub enum Runtime where
+				Block = Block,
+				NodeBlock = opaque::Block,
+				UncheckedExtrinsic = UncheckedExtrinsic
+			{
+				System: frame_system::{Module, Call, Config, Storage, Event<T>},
+				RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Module, Call, Storage},
+				Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
+				$($consensus)*
+				Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
+				Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
+				Indices: pallet_indices::{Module, Call, Storage, Config<T>, Event<T>},
+				Vesting: pallet_vesting::{Module, Call, Storage, Event<T>, Config<T>},
+				TransactionPayment: pallet_transaction_payment::{Module, Storage},
+				Fees: pallet_fees::{Module, Event<T>},
+				Sponsor: pallet_sponsor::{Module, Call, Storage, Event<T>},
+				RateLimit: pallet_rate_limit::{Module, Storage},
+				Assets: pallet_assets::{Module, Call, Storage, Event<T>},
+				Nft: pallet_nft::{Module, Call, Storage, Event<T>},
+				Identity: pallet_identity::{Module, Call, Storage, Event<T>},
+				Utility: pallet_utility::{Module, Call, Event},
+				Multisig: pallet_multisig::{Module, Call, Storage, Event<T>},
+				Proxy: pallet_proxy::{Module, Call, Storage, Event<T>},
+				Scheduler: pallet_scheduler::{Module, Call, Storage, Event<T>},
+				Contracts: pallet_contracts::{Module, Call, Config, Storage, Event<T>},
+				EVM: pallet_evm::{Module, Config, Call, Storage, Event<T>},
+				Eth: pallet_eth::{Module, Call, Storage, Event, ValidateUnsigned},
+				Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
+				// Include the custom logic from the template pallet in the runtime.
+				TemplateModule: pallet_template::{Module, Call, Storage, Event<T>, Origin<T>},
+			}
+		);
+	};
+}
+
+#[cfg(not(feature = "babe"))]
+construct_runtime_with_consensus!(Aura: pallet_aura::{Module, Config<T>, Inherent},);
+
+#[cfg(feature = "babe")]
+construct_runtime_with_consensus!(
+	Babe: pallet_babe::{Module, Call, Storage, Config, Inherent, ValidateUnsigned},
 );
 
-/// The address format for describing accounts.
-pub type Address = AccountId;
+/// The address format for describing accounts: either a full account ID or a short index.
+pub type Address = pallet_indices::address::Address<AccountId, AccountIndex>;
 /// Block header type as expected by this runtime.
 pub type Header = generic::Header<BlockNumber, BlakeTwo256>;
 /// Block type as expected by this runtime.
@@ -303,13 +796,65 @@ pub type SignedExtra = (
 	frame_system::CheckGenesis<Runtime>,
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
+	priority::CheckPriorityPolicy,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>
+	pallet_rate_limit::CheckRateLimit<Runtime>,
+	pallet_sponsor::ChargeTransactionPaymentOrSponsor<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
+/// The data `SignedExtra` adds to the signature of an extrinsic.
+pub type SignedExtraAdditional =
+	<SignedExtra as sp_runtime::traits::SignedExtension>::AdditionalSigned;
+
+/// The `SignedExtra` of an extrinsic valid during `era`, with the given nonce and tip.
+pub fn signed_extra(era: generic::Era, nonce: Index, tip: Balance) -> SignedExtra {
+	(
+		frame_system::CheckSpecVersion::new(),
+		frame_system::CheckTxVersion::new(),
+		frame_system::CheckGenesis::new(),
+		frame_system::CheckEra::from(era),
+		frame_system::CheckNonce::from(nonce),
+		priority::CheckPriorityPolicy,
+		frame_system::CheckWeight::new(),
+		pallet_rate_limit::CheckRateLimit::new(),
+		pallet_sponsor::ChargeTransactionPaymentOrSponsor::from(tip),
+	)
+}
+
+/// The data [`signed_extra`] adds to the signature, in the order of `SignedExtra`: the versions
+/// of the runtime, the genesis hash of the chain and the hash of the block the era starts at,
+/// which is the genesis hash for immortal extrinsics.
+pub fn signed_extra_additional(
+	spec_version: u32,
+	transaction_version: u32,
+	genesis_hash: Hash,
+	birth_hash: Hash,
+) -> SignedExtraAdditional {
+	(spec_version, transaction_version, genesis_hash, birth_hash, (), (), (), (), ())
+}
+
+/// Validate `tx` for the transaction pool. On top of the checks of `SignedExtra`, the
+/// transactions of the sender with a lower nonce, which are waiting in the pool, count towards
+/// its rate limit.
+pub fn validate_transaction(
+	source: TransactionSource,
+	tx: UncheckedExtrinsic,
+) -> TransactionValidity {
+	let queued = tx.signature.as_ref().and_then(|(address, _, extra)| {
+		let who = Indices::lookup_address(address.clone())?;
+		// The nonce of `CheckNonce` is private, but it is all the extension encodes.
+		let nonce = codec::Compact::<Index>::decode(&mut &extra.4.encode()[..]).ok()?;
+		Some((who, nonce.0))
+	});
+	let valid = Executive::validate_transaction(source, tx)?;
+	if let Some((who, nonce)) = queued {
+		RateLimit::check_queued(&who, nonce)?;
+	}
+	Ok(valid)
+}
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -361,7 +906,7 @@ impl_runtime_apis! {
 		}
 
 		fn random_seed() -> <Block as BlockT>::Hash {
-			RandomnessCollectiveFlip::random_seed()
+			RandomnessSource::random_seed()
 		}
 	}
 
@@ -370,7 +915,7 @@ impl_runtime_apis! {
 			source: TransactionSource,
 			tx: <Block as BlockT>::Extrinsic,
 		) -> TransactionValidity {
-			Executive::validate_transaction(source, tx)
+			crate::validate_transaction(source, tx)
 		}
 	}
 
@@ -380,6 +925,7 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[cfg(not(feature = "babe"))]
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> u64 {
 			Aura::slot_duration()
@@ -390,6 +936,43 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[cfg(feature = "babe")]
+	impl sp_consensus_babe::BabeApi<Block> for Runtime {
+		fn configuration() -> sp_consensus_babe::BabeGenesisConfiguration {
+			// The genesis epoch configuration: a primary slot probability of `c` and secondary
+			// slots for the slots without a primary author.
+			sp_consensus_babe::BabeGenesisConfiguration {
+				slot_duration: Babe::slot_duration(),
+				epoch_length: EpochDuration::get(),
+				c: PRIMARY_PROBABILITY,
+				genesis_authorities: Babe::authorities(),
+				randomness: Babe::randomness(),
+				allowed_slots: sp_consensus_babe::AllowedSlots::PrimaryAndSecondaryPlainSlots,
+			}
+		}
+
+		fn current_epoch_start() -> sp_consensus_babe::SlotNumber {
+			Babe::current_epoch_start()
+		}
+
+		fn generate_key_ownership_proof(
+			_slot_number: sp_consensus_babe::SlotNumber,
+			_authority_id: sp_consensus_babe::AuthorityId,
+		) -> Option<sp_consensus_babe::OpaqueKeyOwnershipProof> {
+			// NOTE: this is the only implementation possible since we've
+			// defined our key owner proof type as a bottom type (i.e. a type
+			// with no values).
+			None
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			_equivocation_proof: sp_consensus_babe::EquivocationProof<<Block as BlockT>::Header>,
+			_key_owner_proof: sp_consensus_babe::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			None
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			opaque::SessionKeys::generate(seed)
@@ -439,7 +1022,111 @@ impl_runtime_apis! {
 			uxt: <Block as BlockT>::Extrinsic,
 			len: u32,
 		) -> pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo<Balance> {
-			TransactionPayment::query_info(uxt, len)
+			let mut info = TransactionPayment::query_info(uxt.clone(), len);
+			// The sender of a sponsored transaction pays nothing.
+			if let Some((address, _, extra)) = uxt.signature {
+				// The payment extension is the last of `SignedExtra`.
+				let (.., charge_payment) = extra;
+				if let Some(who) = Indices::lookup_address(address) {
+					if charge_payment.payer(&who, &uxt.function, info.partial_fee) != who {
+						info.partial_fee = 0;
+					}
+				}
+			}
+			info
+		}
+	}
+
+	impl pallet_assets_rpc_runtime_api::AssetsApi<Block, AccountId, AssetId, Balance> for Runtime {
+		fn balance(id: AssetId, who: AccountId) -> Balance {
+			Assets::balance(id, who)
+		}
+
+		fn total_supply(id: AssetId) -> Balance {
+			Assets::total_supply(id)
+		}
+	}
+
+	impl pallet_contracts_rpc_runtime_api::ContractsApi<Block, AccountId, Balance, BlockNumber>
+		for Runtime
+	{
+		fn call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: u64,
+			input_data: Vec<u8>,
+		) -> ContractExecResult {
+			let (exec_result, gas_consumed) =
+				Contracts::bare_call(origin, dest.into(), value, gas_limit, input_data);
+			match exec_result {
+				Ok(v) => ContractExecResult::Success {
+					flags: v.flags.bits(),
+					data: v.data,
+					gas_consumed,
+				},
+				Err(_) => ContractExecResult::Error,
+			}
+		}
+
+		fn get_storage(
+			address: AccountId,
+			key: [u8; 32],
+		) -> pallet_contracts_primitives::GetStorageResult {
+			Contracts::get_storage(address, key)
+		}
+
+		fn rent_projection(
+			address: AccountId,
+		) -> pallet_contracts_primitives::RentProjectionResult<BlockNumber> {
+			Contracts::rent_projection(address)
+		}
+	}
+
+	impl pallet_eth_rpc_runtime_api::EthApi<Block, EthReceipt> for Runtime {
+		fn chain_id() -> u64 {
+			ChainId::get()
+		}
+
+		fn balance(address: H160) -> U256 {
+			Eth::balance(address)
+		}
+
+		fn call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+		) -> Result<Vec<u8>, sp_runtime::DispatchError> {
+			Eth::call(from, to, data, value, gas_limit)
+		}
+
+		fn transaction_receipt(hash: H256) -> Option<EthReceipt> {
+			Eth::receipt(hash)
+		}
+
+		fn transaction_extrinsic(raw: Vec<u8>) -> <Block as BlockT>::Extrinsic {
+			UncheckedExtrinsic::new_unsigned(pallet_eth::Call::<Runtime>::transact(raw).into())
+		}
+	}
+
+	impl pallet_nft_rpc_runtime_api::NftApi<Block, AccountId, CollectionId, TokenId> for Runtime {
+		fn tokens_of(who: AccountId) -> Vec<(CollectionId, TokenId)> {
+			Nft::tokens_of(who)
+		}
+	}
+
+	impl pallet_identity_rpc_runtime_api::IdentityApi<Block, AccountId> for Runtime {
+		fn display_name(who: AccountId) -> Option<Vec<u8>> {
+			display_name(&who)
+		}
+	}
+
+	#[cfg(feature = "try-runtime")]
+	impl try_runtime::TryRuntime<Block> for Runtime {
+		fn on_runtime_upgrade() -> try_runtime::UpgradeReport {
+			try_runtime::on_runtime_upgrade()
 		}
 	}
 
@@ -471,7 +1158,15 @@ impl_runtime_apis! {
 
 			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
 			add_benchmark!(params, batches, pallet_balances, Balances);
+			add_benchmark!(params, batches, pallet_identity, Identity);
+			add_benchmark!(params, batches, pallet_indices, Indices);
+			add_benchmark!(params, batches, pallet_multisig, Multisig);
+			add_benchmark!(params, batches, pallet_nft, Nft);
+			add_benchmark!(params, batches, pallet_proxy, Proxy);
+			add_benchmark!(params, batches, pallet_scheduler, Scheduler);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
+			add_benchmark!(params, batches, pallet_utility, Utility);
+			add_benchmark!(params, batches, pallet_vesting, Vesting);
 
 			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
 			Ok(batches)
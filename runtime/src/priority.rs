@@ -0,0 +1,102 @@
+//! The transaction priority policy of the runtime.
+//!
+//! Some calls are more urgent than the fee they pay suggests. The [`policy`] table gives them a
+//! boost on top of the priority derived from their fee, and lets them use a share of the block
+//! weight that other calls of their dispatch class cannot use. Per dispatch class:
+//!
+//! - `Mandatory` extrinsics (inherents) are never limited.
+//! - `Operational` extrinsics can use the whole block.
+//! - Boosted `Normal` extrinsics can use `AvailableBlockRatio` of the block.
+//! - Other `Normal` extrinsics can use `UnboostedBlockRatio` of the block.
+
+use crate::{AccountId, Call, Runtime, MaximumBlockWeight, UnboostedBlockRatio};
+use codec::{Encode, Decode};
+use frame_support::{traits::Get, weights::DispatchClass};
+use sp_runtime::{
+	RuntimeDebug,
+	traits::{DispatchInfoOf, Saturating, SignedExtension},
+	transaction_validity::{
+		TransactionPriority, TransactionValidity, TransactionValidityError, InvalidTransaction,
+		ValidTransaction,
+	},
+};
+
+/// The boost of calls that keep the chain running.
+pub const OPERATIONAL_BOOST: TransactionPriority = TransactionPriority::max_value() / 2;
+
+/// The boost of template pallet calls that should not wait behind ordinary traffic.
+pub const TEMPLATE_BOOST: TransactionPriority = TransactionPriority::max_value() / 4;
+
+/// The priority boost of `call` sent by `who`, if it has one.
+pub fn policy(who: &AccountId, call: &Call) -> Option<TransactionPriority> {
+	let is_sudo_key = || *who == pallet_sudo::Module::<Runtime>::key();
+	match call {
+		// `Grandpa::note_stalled` is root-only, so it can only be sent wrapped in `Sudo::sudo`.
+		Call::Sudo(pallet_sudo::Call::sudo(inner))
+			if matches!(**inner, Call::Grandpa(pallet_grandpa::Call::note_stalled(..))) &&
+				is_sudo_key() => Some(OPERATIONAL_BOOST),
+		// Validator set changes go through `Sudo` as well, as there is no staking or session
+		// pallet. Only the sudo key can dispatch them, so other senders get no boost.
+		Call::Sudo(..) if is_sudo_key() => Some(OPERATIONAL_BOOST),
+		Call::TemplateModule(pallet_template::Call::do_something(..)) |
+		Call::TemplateModule(pallet_template::Call::schedule_set(..)) => Some(TEMPLATE_BOOST),
+		_ => None,
+	}
+}
+
+/// Apply the priority [`policy`]: boost the priority of the calls in the table, and keep other
+/// `Normal` calls out of the share of the block reserved for boosted ones.
+///
+/// This must come before `CheckWeight`, so that the block weight it reads does not include the
+/// checked extrinsic yet.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct CheckPriorityPolicy;
+
+impl CheckPriorityPolicy {
+	fn check(who: &AccountId, call: &Call, info: &DispatchInfoOf<Call>) -> TransactionValidity {
+		let boost = policy(who, call);
+
+		if boost.is_none() && info.class == DispatchClass::Normal {
+			let limit = UnboostedBlockRatio::get() * MaximumBlockWeight::get();
+			let weight = frame_system::Module::<Runtime>::block_weight()
+				.get(DispatchClass::Normal)
+				.saturating_add(<Runtime as frame_system::Trait>::ExtrinsicBaseWeight::get())
+				.saturating_add(info.weight);
+			if weight > limit {
+				return InvalidTransaction::ExhaustsResources.into();
+			}
+		}
+
+		Ok(ValidTransaction { priority: boost.unwrap_or_default(), ..Default::default() })
+	}
+}
+
+impl SignedExtension for CheckPriorityPolicy {
+	const IDENTIFIER: &'static str = "CheckPriorityPolicy";
+	type AccountId = <Runtime as frame_system::Trait>::AccountId;
+	type Call = Call;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> { Ok(()) }
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		Self::check(who, call, info)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> Result<(), TransactionValidityError> {
+		Self::check(who, call, info).map(|_| ())
+	}
+}
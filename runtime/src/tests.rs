@@ -0,0 +1,461 @@
+use crate::*;
+use codec::Encode;
+use frame_support::{assert_ok, assert_noop, traits::{InstanceFilter, OnInitialize}};
+use sp_keyring::AccountKeyring;
+use sp_runtime::{
+	generic::{Era, SignedPayload},
+	traits::{BlakeTwo256, Checkable, Hash, StaticLookup},
+};
+
+/// A contract that does nothing, compiled from `fixtures/noop.wat`.
+const NOOP_CONTRACT: &[u8] = include_bytes!("../fixtures/noop.wasm");
+
+const GAS_LIMIT: u64 = 10_000_000_000;
+
+// Build genesis storage with a couple of funded dev accounts.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: vec![
+			(AccountKeyring::Alice.to_account_id(), 100 * DOLLARS),
+			(AccountKeyring::Bob.to_account_id(), 100 * DOLLARS),
+		],
+	}.assimilate_storage(&mut t).unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Sign `call` with the key of `signer`, naming the sender by `address`.
+pub fn sign(call: Call, signer: AccountKeyring, address: Address, nonce: Index) -> UncheckedExtrinsic {
+	let payload = SignedPayload::new(call, signed_extra(Era::Immortal, nonce, 0))
+		.expect("signed extra of a fresh chain is valid; qed");
+	let signature = payload.using_encoded(|p| signer.sign(p));
+	let (call, extra, _) = payload.deconstruct();
+	UncheckedExtrinsic::new_signed(call, address, signature.into(), extra)
+}
+
+#[test]
+fn indices_resolve_to_accounts() {
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let bob = AccountKeyring::Bob.to_account_id();
+
+		assert_ok!(Indices::claim(Origin::signed(alice.clone()), 0));
+		assert_eq!(Balances::reserved_balance(&alice), IndexDeposit::get());
+
+		assert_eq!(Indices::lookup(Address::Index(0)), Ok(alice));
+		assert_eq!(Indices::lookup(Address::Id(bob.clone())), Ok(bob));
+		assert!(Indices::lookup(Address::Index(1)).is_err());
+	});
+}
+
+#[test]
+fn transfer_to_an_index() {
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let bob = AccountKeyring::Bob.to_account_id();
+		assert_ok!(Indices::claim(Origin::signed(bob.clone()), 7));
+
+		assert_ok!(Balances::transfer(Origin::signed(alice), Address::Index(7), DOLLARS));
+		assert_eq!(Balances::free_balance(&bob), 100 * DOLLARS - IndexDeposit::get() + DOLLARS);
+
+		assert_noop!(
+			Balances::transfer(Origin::signed(bob), Address::Index(8), DOLLARS),
+			sp_runtime::DispatchError::CannotLookup,
+		);
+	});
+}
+
+#[test]
+fn extrinsic_signed_by_index_checks_out() {
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		assert_ok!(Indices::claim(Origin::signed(alice.clone()), 0));
+
+		let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+		let context = frame_system::ChainContext::<Runtime>::default();
+
+		let by_index = sign(call.clone(), AccountKeyring::Alice, Address::Index(0), 0);
+		let checked = by_index.check(&context).expect("signature is valid");
+		assert_eq!(checked.signed.map(|(who, _)| who), Some(alice.clone()));
+
+		let by_id = sign(call.clone(), AccountKeyring::Alice, Address::Id(alice.clone()), 0);
+		let checked = by_id.check(&context).expect("signature is valid");
+		assert_eq!(checked.signed.map(|(who, _)| who), Some(alice));
+
+		// the index must point at the account that signed.
+		let wrong = sign(call, AccountKeyring::Bob, Address::Index(0), 0);
+		assert!(wrong.check(&context).is_err());
+	});
+}
+
+#[test]
+fn template_only_proxy_filter() {
+	let template_call = Call::TemplateModule(pallet_template::Call::do_something(1));
+	let transfer = Call::Balances(BalancesCall::transfer(Address::Index(0), 1));
+	let batch = Call::Utility(pallet_utility::Call::batch(vec![template_call.clone()]));
+
+	assert!(ProxyType::TemplateOnly.filter(&template_call));
+	assert!(!ProxyType::TemplateOnly.filter(&transfer));
+	assert!(!ProxyType::TemplateOnly.filter(&batch));
+
+	assert!(ProxyType::NonTransfer.filter(&template_call));
+	assert!(!ProxyType::NonTransfer.filter(&transfer));
+
+	assert!(ProxyType::Any.is_superset(&ProxyType::TemplateOnly));
+	assert!(ProxyType::NonTransfer.is_superset(&ProxyType::TemplateOnly));
+	assert!(!ProxyType::TemplateOnly.is_superset(&ProxyType::NonTransfer));
+}
+
+#[test]
+fn template_only_proxy_cannot_move_funds() {
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let bob = AccountKeyring::Bob.to_account_id();
+		assert_ok!(Proxy::add_proxy(
+			Origin::signed(alice.clone()),
+			bob.clone(),
+			ProxyType::TemplateOnly,
+			0,
+		));
+		let alice_balance = Balances::free_balance(&alice);
+
+		let call = Box::new(Call::TemplateModule(pallet_template::Call::do_something(7)));
+		assert_ok!(Proxy::proxy(Origin::signed(bob.clone()), alice.clone(), None, call));
+		assert_eq!(TemplateModule::something(), Some(7));
+
+		let call = Box::new(Call::Balances(
+			BalancesCall::transfer(Address::Id(bob.clone()), DOLLARS)
+		));
+		assert_ok!(Proxy::proxy(Origin::signed(bob), alice.clone(), None, call));
+		assert_eq!(Balances::free_balance(&alice), alice_balance);
+	});
+}
+
+#[test]
+fn non_transfer_proxy_cannot_hand_out_spending_rights() {
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let bob = AccountKeyring::Bob.to_account_id();
+		assert_ok!(Proxy::add_proxy(
+			Origin::signed(alice.clone()),
+			bob.clone(),
+			ProxyType::NonTransfer,
+			0,
+		));
+
+		let call = Box::new(Call::Sponsor(
+			pallet_sponsor::Call::sponsor(Address::Id(bob.clone()), ProxyType::Any)
+		));
+		assert_ok!(Proxy::proxy(Origin::signed(bob.clone()), alice.clone(), None, call));
+		assert_eq!(Sponsor::sponsor_of(&bob), None);
+
+		let filtered = [
+			Call::Nft(pallet_nft::Call::approve(0, 0, Address::Id(bob.clone()))),
+			Call::Identity(pallet_identity::Call::request_judgement(0, DOLLARS)),
+		];
+		for call in &filtered {
+			assert!(!ProxyType::NonTransfer.filter(call));
+		}
+		let call = Call::TemplateModule(pallet_template::Call::do_something(7));
+		assert!(ProxyType::NonTransfer.filter(&call));
+	});
+}
+
+#[test]
+fn batch_all_is_atomic() {
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let remark = Call::System(frame_system::Call::remark(vec![]));
+		// alice is not the sudo key, so the last call fails and everything is rolled back.
+		assert!(Utility::batch_all(Origin::signed(alice), vec![
+			Call::TemplateModule(pallet_template::Call::do_something(1)),
+			Call::TemplateModule(pallet_template::Call::do_something(2)),
+			remark.clone(),
+			Call::Sudo(pallet_sudo::Call::sudo(Box::new(remark))),
+		]).is_err());
+		assert_eq!(TemplateModule::something(), None);
+	});
+}
+
+#[test]
+fn genesis_vesting_holds_a_single_lock() {
+	let alice = AccountKeyring::Alice.to_account_id();
+	let bob = AccountKeyring::Bob.to_account_id();
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	pallet_balances::GenesisConfig::<Runtime> {
+		balances: vec![(alice.clone(), 100 * DOLLARS), (bob.clone(), 100 * DOLLARS)],
+	}.assimilate_storage(&mut t).unwrap();
+	// everything locked until block 10, then 5 DOLLARS a block for 20 blocks.
+	pallet_vesting::GenesisConfig::<Runtime> {
+		vesting: vec![(alice.clone(), 10, 20, 0)],
+	}.assimilate_storage(&mut t).unwrap();
+
+	sp_io::TestExternalities::new(t).execute_with(|| {
+		System::set_block_number(1);
+		assert_eq!(Balances::locks(&alice).len(), 1);
+		assert_eq!(Vesting::vesting_balance(&alice), Some(100 * DOLLARS));
+		assert_noop!(
+			Balances::transfer(Origin::signed(alice.clone()), Address::Id(bob.clone()), DOLLARS),
+			pallet_balances::Error::<Runtime>::LiquidityRestrictions,
+		);
+
+		// vesting keeps updating the same lock instead of adding new ones.
+		System::set_block_number(15);
+		assert_ok!(Vesting::vest(Origin::signed(alice.clone())));
+		assert_eq!(Balances::locks(&alice).len(), 1);
+		assert_eq!(Vesting::vesting_balance(&alice), Some(75 * DOLLARS));
+
+		System::set_block_number(30);
+		assert_ok!(Vesting::vest(Origin::signed(alice.clone())));
+		assert!(Balances::locks(&alice).is_empty());
+		assert_ok!(Balances::transfer(Origin::signed(alice), Address::Id(bob), DOLLARS));
+	});
+}
+
+#[test]
+fn deploy_and_call_a_contract() {
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let code_hash = BlakeTwo256::hash(NOOP_CONTRACT);
+
+		assert_ok!(Contracts::put_code(Origin::signed(alice.clone()), NOOP_CONTRACT.to_vec()));
+		assert_ok!(Contracts::instantiate(
+			Origin::signed(alice.clone()),
+			DOLLARS,
+			GAS_LIMIT,
+			code_hash,
+			vec![],
+		));
+
+		let contract = System::events().into_iter().find_map(|r| match r.event {
+			Event::pallet_contracts(pallet_contracts::RawEvent::Instantiated(owner, contract))
+				if owner == alice => Some(contract),
+			_ => None,
+		}).expect("the contract was instantiated");
+		assert_eq!(Balances::free_balance(&contract), DOLLARS);
+
+		let (result, _) = Contracts::bare_call(alice, contract, 0, GAS_LIMIT, vec![]);
+		assert!(result.is_ok());
+	});
+}
+
+#[test]
+fn scheduled_template_write_runs_within_the_block_limit() {
+	assert!(MaximumSchedulerWeight::get() < MaximumBlockWeight::get());
+
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		assert_ok!(TemplateModule::schedule_set(Origin::signed(alice), 3, 42));
+
+		for n in 2..=3 {
+			System::set_block_number(n);
+			let weight = <Scheduler as OnInitialize<BlockNumber>>::on_initialize(n);
+			assert!(weight <= MaximumSchedulerWeight::get());
+		}
+		assert_eq!(TemplateModule::something(), Some(42));
+	});
+}
+
+#[test]
+fn identities_resolve_to_display_names() {
+	use pallet_identity::{Data, IdentityInfo, Judgement};
+
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let bob = AccountKeyring::Bob.to_account_id();
+		let charlie = AccountKeyring::Charlie.to_account_id();
+
+		// only root manages the registrars.
+		assert!(Identity::add_registrar(Origin::signed(alice.clone()), bob.clone()).is_err());
+		assert_ok!(Identity::add_registrar(Origin::root(), bob.clone()));
+
+		let info = IdentityInfo { display: Data::Raw(b"alice".to_vec()), ..Default::default() };
+		assert_ok!(Identity::set_identity(Origin::signed(alice.clone()), info));
+		assert_eq!(Balances::reserved_balance(&alice), BasicDeposit::get());
+		assert_ok!(Identity::request_judgement(Origin::signed(alice.clone()), 0, 0));
+		assert_ok!(Identity::provide_judgement(
+			Origin::signed(bob.clone()),
+			0,
+			Address::Id(alice.clone()),
+			Judgement::Reasonable,
+		));
+		assert_eq!(display_name(&alice), Some(b"alice".to_vec()));
+
+		assert_ok!(Identity::set_subs(
+			Origin::signed(alice.clone()),
+			vec![(charlie.clone(), Data::Raw(b"ops".to_vec()))],
+		));
+		assert_eq!(display_name(&charlie), Some(b"alice/ops".to_vec()));
+		assert_eq!(display_name(&bob), None);
+	});
+}
+
+#[test]
+fn sponsors_pay_for_template_calls_of_their_devices() {
+	use frame_support::weights::GetDispatchInfo;
+	use sp_runtime::traits::SignedExtension;
+
+	new_test_ext().execute_with(|| {
+		let bob = AccountKeyring::Bob.to_account_id();
+		let device = AccountKeyring::Charlie.to_account_id();
+		let pot = Sponsor::pot_of(&bob);
+		assert_ok!(Sponsor::fund(Origin::signed(bob.clone()), 10 * DOLLARS));
+		assert_ok!(Sponsor::sponsor(
+			Origin::signed(bob),
+			Address::Id(device.clone()),
+			ProxyType::TemplateOnly,
+		));
+
+		let charge = |call: &Call| {
+			pallet_sponsor::ChargeTransactionPaymentOrSponsor::<Runtime>::from(0)
+				.pre_dispatch(&device, call, &call.get_dispatch_info(), 100)
+		};
+
+		let call = Call::TemplateModule(pallet_template::Call::do_something(1));
+		assert_ok!(charge(&call));
+		assert!(Balances::free_balance(&pot) < 10 * DOLLARS);
+
+		// the device has nothing to pay for calls its sponsor does not cover.
+		let transfer = Call::Balances(BalancesCall::transfer(Address::Id(pot), 1));
+		assert!(charge(&transfer).is_err());
+	});
+}
+
+#[test]
+fn rate_limited_transactions_are_rejected_by_the_pool() {
+	use sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource};
+
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let bob = AccountKeyring::Bob.to_account_id();
+		let call = Call::TemplateModule(pallet_template::Call::do_something(1));
+
+		// the next block is in the first window.
+		let limit = MaxTransactionsPerPeriod::get();
+		pallet_rate_limit::Counters::<Runtime>::insert(&alice, (0, limit));
+
+		let limited = sign(call.clone(), AccountKeyring::Alice, Address::Id(alice), 0);
+		assert_eq!(
+			validate_transaction(TransactionSource::External, limited),
+			Err(InvalidTransaction::Custom(pallet_rate_limit::RATE_LIMITED).into()),
+		);
+
+		let fresh = sign(call, AccountKeyring::Bob, Address::Id(bob), 0);
+		assert!(validate_transaction(TransactionSource::External, fresh).is_ok());
+	});
+}
+
+/// A transaction pool API validating transactions against the state of a test externalities.
+struct TestPoolApi(std::sync::Mutex<sp_io::TestExternalities>);
+
+impl sc_transaction_graph::ChainApi for TestPoolApi {
+	type Block = Block;
+	type Error = sc_transaction_graph::error::Error;
+	type ValidationFuture = futures::future::Ready<
+		Result<sp_runtime::transaction_validity::TransactionValidity, Self::Error>
+	>;
+	type BodyFuture = futures::future::Ready<Result<Option<Vec<UncheckedExtrinsic>>, Self::Error>>;
+
+	fn validate_transaction(
+		&self,
+		_at: &BlockId,
+		source: sp_runtime::transaction_validity::TransactionSource,
+		uxt: UncheckedExtrinsic,
+	) -> Self::ValidationFuture {
+		let validity = self.0.lock().unwrap()
+			.execute_with(|| validate_transaction(source, uxt));
+		futures::future::ready(Ok(validity))
+	}
+
+	fn block_id_to_number(&self, _at: &BlockId) -> Result<Option<BlockNumber>, Self::Error> {
+		Ok(Some(1))
+	}
+
+	fn block_id_to_hash(&self, _at: &BlockId) -> Result<Option<crate::Hash>, Self::Error> {
+		Ok(Some(Default::default()))
+	}
+
+	fn hash_and_length(&self, uxt: &UncheckedExtrinsic) -> (crate::Hash, usize) {
+		let encoded = uxt.encode();
+		(BlakeTwo256::hash(&encoded), encoded.len())
+	}
+
+	fn block_body(&self, _at: &BlockId) -> Self::BodyFuture {
+		futures::future::ready(Ok(None))
+	}
+}
+
+#[test]
+fn rate_limit_counts_the_transactions_in_the_pool() {
+	use sc_transaction_graph::{Pool, error::Error as PoolError};
+	use sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource};
+
+	let api = TestPoolApi(std::sync::Mutex::new(new_test_ext()));
+	let pool = Pool::new(Default::default(), true.into(), std::sync::Arc::new(api));
+	let alice = AccountKeyring::Alice.to_account_id();
+	let transactions = (0..=MaxTransactionsPerPeriod::get()).map(|nonce| {
+		let call = Call::TemplateModule(pallet_template::Call::do_something(nonce));
+		sign(call, AccountKeyring::Alice, Address::Id(alice.clone()), nonce)
+	});
+
+	let results = futures::executor::block_on(
+		pool.submit_at(&BlockId::Number(1), TransactionSource::External, transactions)
+	).unwrap();
+	let (last, accepted) = results.split_last().unwrap();
+	assert!(accepted.iter().all(Result::is_ok));
+	let limited = InvalidTransaction::Custom(pallet_rate_limit::RATE_LIMITED);
+	assert!(matches!(last, Err(PoolError::InvalidTransaction(e)) if *e == limited));
+	assert_eq!(pool.validated_pool().status().ready, MaxTransactionsPerPeriod::get() as usize);
+}
+
+#[test]
+fn boosted_calls_can_use_the_reserved_block_weight() {
+	use frame_support::weights::{DispatchClass, GetDispatchInfo};
+	use sp_runtime::{traits::SignedExtension, transaction_validity::InvalidTransaction};
+	use priority::{CheckPriorityPolicy, OPERATIONAL_BOOST, TEMPLATE_BOOST};
+
+	let sudo = Call::Sudo(pallet_sudo::Call::sudo(Box::new(
+		Call::System(frame_system::Call::remark(vec![]))
+	)));
+	let note_stalled = Call::Grandpa(pallet_grandpa::Call::note_stalled(10, 1));
+	let sudo_note_stalled = Call::Sudo(pallet_sudo::Call::sudo(Box::new(note_stalled.clone())));
+	let template = Call::TemplateModule(pallet_template::Call::do_something(1));
+	let remark = Call::System(frame_system::Call::remark(vec![]));
+
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let bob = AccountKeyring::Bob.to_account_id();
+		assert_ok!(Sudo::set_key(Origin::signed(Default::default()), Address::Id(alice.clone())));
+
+		assert_eq!(priority::policy(&alice, &sudo), Some(OPERATIONAL_BOOST));
+		// `note_stalled` is root-only, so it is only boosted when the sudo key sends it.
+		assert_eq!(priority::policy(&alice, &sudo_note_stalled), Some(OPERATIONAL_BOOST));
+		assert_eq!(priority::policy(&alice, &note_stalled), None);
+		assert_eq!(priority::policy(&bob, &sudo_note_stalled), None);
+		assert_eq!(priority::policy(&alice, &template), Some(TEMPLATE_BOOST));
+		assert_eq!(priority::policy(&alice, &remark), None);
+		// anyone can send a sudo call, but only the sudo key gets it boosted.
+		assert_eq!(priority::policy(&bob, &sudo), None);
+		assert_eq!(
+			CheckPriorityPolicy.validate(&bob, &sudo, &sudo.get_dispatch_info(), 0)
+				.map(|v| v.priority),
+			Ok(0),
+		);
+
+		let validate = |call: &Call| {
+			CheckPriorityPolicy.validate(&alice, call, &call.get_dispatch_info(), 0)
+		};
+		assert_eq!(validate(&template).map(|v| v.priority), Ok(TEMPLATE_BOOST));
+		assert_eq!(validate(&remark).map(|v| v.priority), Ok(0));
+
+		// fill the share of the block available to unboosted calls.
+		System::register_extra_weight_unchecked(
+			UnboostedBlockRatio::get() * MaximumBlockWeight::get(),
+			DispatchClass::Normal,
+		);
+		assert_eq!(validate(&remark), Err(InvalidTransaction::ExhaustsResources.into()));
+		assert!(validate(&template).is_ok());
+	});
+}
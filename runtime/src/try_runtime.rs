@@ -0,0 +1,69 @@
+//! Dry runs of runtime upgrades, for the `try-runtime` command of the node.
+//!
+//! The node executes [`TryRuntime::on_runtime_upgrade`] with the new runtime on top of the state
+//! of a live chain. It runs the migrations of all pallets like the first block after the upgrade
+//! would, then checks the invariants the pallets define for their storage.
+
+use sp_std::{collections::btree_set::BTreeSet, prelude::*};
+use codec::{Encode, Decode};
+use frame_support::{
+	storage::IterableStorageMap,
+	traits::{Get, OnRuntimeUpgrade},
+	weights::Weight,
+};
+use sp_runtime::{RuntimeDebug, RuntimeString};
+use crate::{AllModules, Assets, Nft, RateLimit, Runtime, Sponsor, TemplateModule};
+
+/// The outcome of the migrations.
+#[derive(Encode, Decode, RuntimeDebug, PartialEq)]
+pub struct UpgradeReport {
+	/// The weight consumed by the migrations.
+	pub weight: Weight,
+	/// The maximum weight of a block, which the migrations must fit in.
+	pub max_block_weight: Weight,
+	/// The pallets whose invariants do not hold after the migrations, with the reason.
+	pub failed_checks: Vec<(RuntimeString, RuntimeString)>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Dry runs of runtime upgrades.
+	pub trait TryRuntime {
+		/// Run the migrations of all pallets, then check their invariants.
+		fn on_runtime_upgrade() -> UpgradeReport;
+	}
+}
+
+/// The invariants of the pallets, by pallet name.
+const CHECKS: &[(&str, fn() -> Result<(), &'static str>)] = &[
+	("Assets", Assets::post_upgrade),
+	("Nft", Nft::post_upgrade),
+	("RateLimit", RateLimit::post_upgrade),
+	("Sponsor", Sponsor::post_upgrade),
+	("TemplateModule", || TemplateModule::post_upgrade(&scheduled_ids())),
+];
+
+/// The identifiers of the named tasks in the agenda of the scheduler.
+fn scheduled_ids() -> BTreeSet<Vec<u8>> {
+	// The fields of scheduled tasks are private, but their encoding starts with the identifier.
+	pallet_scheduler::Agenda::<Runtime>::iter()
+		.flat_map(|(_, tasks)| tasks.into_iter().flatten())
+		.filter_map(|task| Option::<Vec<u8>>::decode(&mut &task.encode()[..]).ok().flatten())
+		.collect()
+}
+
+/// Run the migrations like `Executive` does after an upgrade, then check the invariants.
+pub fn on_runtime_upgrade() -> UpgradeReport {
+	// System is not part of `AllModules`.
+	let weight = <frame_system::Module<Runtime> as OnRuntimeUpgrade>::on_runtime_upgrade()
+		.saturating_add(<AllModules as OnRuntimeUpgrade>::on_runtime_upgrade());
+
+	let failed_checks = CHECKS.iter()
+		.filter_map(|(pallet, check)| check().err().map(|reason| ((*pallet).into(), reason.into())))
+		.collect();
+
+	UpgradeReport {
+		weight,
+		max_block_weight: <Runtime as frame_system::Trait>::MaximumBlockWeight::get(),
+		failed_checks,
+	}
+}